@@ -102,7 +102,7 @@ pub(crate) const fn signature_mismatch() -> SignatureMismatch {
 
 /// The key was rejected
 #[derive(Debug, Error)]
-#[error("key rejected")]
+#[error("key rejected: {source}")]
 pub struct KeyRejected {
     #[from]
     source: Box<dyn StdError + Send + Sync + 'static>,
@@ -116,6 +116,17 @@ pub(crate) fn key_rejected(
     }
 }
 
+/// No key in the JWKS matched the key ID and algorithm of the JWT
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Error)]
+#[error("no matching key found to verify JWT")]
+pub struct NoMatchingKey {
+    _p: (),
+}
+
+pub(crate) const fn no_matching_key() -> NoMatchingKey {
+    NoMatchingKey { _p: () }
+}
+
 /// Missing private key
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Error)]
 #[error("cannot sign without a private key")]
@@ -143,6 +154,53 @@ pub(crate) fn unexpected(
     }
 }
 
+/// A claim did not satisfy a configured claim rule
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Error)]
+#[error("claim at `{pointer}` {reason}")]
+pub struct ClaimRuleViolation {
+    pointer: String,
+    reason: &'static str,
+}
+
+impl ClaimRuleViolation {
+    /// The JSON pointer to the claim that violated the rule
+    #[must_use]
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+}
+
+pub(crate) fn claim_rule_violation(
+    pointer: impl Into<String>,
+    reason: &'static str,
+) -> ClaimRuleViolation {
+    ClaimRuleViolation {
+        pointer: pointer.into(),
+        reason,
+    }
+}
+
+/// A claim rule was given a string that is not an RFC 6901 JSON pointer
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Error)]
+#[error("`{pointer}` is not a valid JSON pointer")]
+pub struct InvalidJsonPointer {
+    pointer: String,
+}
+
+impl InvalidJsonPointer {
+    /// The rejected pointer
+    #[must_use]
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+}
+
+pub(crate) fn invalid_json_pointer(pointer: impl Into<String>) -> InvalidJsonPointer {
+    InvalidJsonPointer {
+        pointer: pointer.into(),
+    }
+}
+
 /// An error occurring while creating a signature
 #[derive(Debug, Error)]
 pub enum SigningError {
@@ -241,6 +299,18 @@ pub enum JwtVerifyError {
     Unexpected(#[from] Unexpected),
 }
 
+/// An error occurring while verifying a JWT against a JWKS
+#[derive(Debug, Error)]
+pub enum JwksVerifyError {
+    /// No key in the JWKS could be used to verify the JWT
+    #[error(transparent)]
+    NoMatchingKey(#[from] NoMatchingKey),
+
+    /// The JWT was rejected
+    #[error(transparent)]
+    JwtVerifyError(#[from] JwtVerifyError),
+}
+
 /// An error occurring while verifying a JWT
 #[derive(Debug, Error)]
 pub enum JwtSigningError {
@@ -28,9 +28,11 @@ pub use hmac::Hmac;
 pub use rsa::Rsa;
 
 mod algorithm;
+mod key_operation;
 mod usage;
 
 pub use algorithm::Algorithm;
+pub use key_operation::KeyOperation;
 pub use usage::Usage;
 
 static CRATE_RNG: once_cell::sync::Lazy<ring::rand::SystemRandom> =
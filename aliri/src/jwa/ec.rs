@@ -49,7 +49,6 @@ impl Curve {
         }
     }
 
-    #[cfg(feature = "private-keys")]
     fn from_group(group: &EcGroupRef) -> Option<Self> {
         let nid = group.curve_name()?;
         if nid == P256.curve_name().unwrap() {
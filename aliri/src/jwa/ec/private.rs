@@ -75,7 +75,7 @@ impl PrivateKey {
         let pkcs8 = Base64::from_encoded(pkcs8_str).map_err(error::key_rejected)?;
 
         let ring_cache = Arc::new(
-            ring::signature::EcdsaKeyPair::from_pkcs8(
+            EcdsaKeyPair::from_pkcs8(
                 SigningAlgorithm::from(public_key.curve()).signing_algorithm(),
                 pkcs8.as_slice(),
             )
@@ -89,8 +89,12 @@ impl PrivateKey {
         })
     }
 
-    #[cfg_attr(not(test), allow(dead_code))]
-    pub(crate) fn to_pem(&self) -> Result<String, error::Unexpected> {
+    /// Exports the ECC key pair as a PKCS#8 PEM file
+    ///
+    /// # Errors
+    ///
+    /// Unable to encode the private key.
+    pub fn to_pem(&self) -> Result<String, error::Unexpected> {
         let x = PKey::private_key_from_pkcs8(self.pkcs8.as_slice())
             .map_err(error::unexpected)?
             .private_key_to_pem_pkcs8()
@@ -6,7 +6,6 @@ use openssl::{
     ec::{EcKey, EcPoint, PointConversionForm},
     pkey::PKey,
 };
-#[cfg(feature = "private-keys")]
 use openssl::{ec::EcKeyRef, pkey::HasPublic};
 use ring::signature::VerificationAlgorithm;
 use serde::{Deserialize, Serialize};
@@ -61,7 +60,22 @@ impl PublicKey {
         String::from_utf8(pem).unwrap()
     }
 
-    #[cfg(feature = "private-keys")]
+    /// Imports an ECC public key from a PEM file
+    ///
+    /// # Errors
+    ///
+    /// The provided PEM file is not a valid ECC public key on a supported curve.
+    pub fn from_pem(pem: &str) -> Result<Self, error::KeyRejected> {
+        let key = PKey::public_key_from_pem(pem.as_bytes()).map_err(error::key_rejected)?;
+        let key = key.ec_key().map_err(error::key_rejected)?;
+
+        if Curve::from_group(key.group()).is_none() {
+            return Err(error::key_rejected("unsupported elliptic curve"));
+        }
+
+        Ok(Self::from_openssl_eckey(&*key))
+    }
+
     pub(super) fn from_openssl_eckey<T: HasPublic>(key: &'_ EcKeyRef<T>) -> Self {
         let group = key.group();
 
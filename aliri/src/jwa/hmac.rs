@@ -6,7 +6,7 @@ use aliri_base64::{Base64Url, Base64UrlRef};
 use ring::rand::SecureRandom;
 use serde::{Deserialize, Serialize};
 
-use crate::{error, jws};
+use crate::{error, jwa, jwk, jws};
 
 /// HMAC secret
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -29,6 +29,22 @@ impl Hmac {
         Self { secret }
     }
 
+    /// HMAC using the provided secret, checking the length of the secret
+    /// against the key policy for the algorithm it will be used with
+    ///
+    /// # Errors
+    ///
+    /// The secret is shorter than the key policy allows for the algorithm.
+    pub fn try_new_with_policy(
+        secret: impl Into<Base64Url>,
+        alg: SigningAlgorithm,
+        policy: &jwk::KeyPolicy,
+    ) -> Result<Self, error::KeyRejected> {
+        let key = Self::new(secret);
+        policy.check_hmac(&key, Some(jwa::Algorithm::from(alg)))?;
+        Ok(key)
+    }
+
     /// Generates a new HMAC secret
     ///
     /// # Errors
@@ -56,7 +72,6 @@ impl Hmac {
         Ok(Self { secret })
     }
 
-    #[cfg_attr(not(test), allow(dead_code))]
     pub(crate) fn secret(&self) -> &Base64UrlRef {
         &self.secret
     }
@@ -81,7 +96,7 @@ pub enum SigningAlgorithm {
 impl SigningAlgorithm {
     /// Recommended key size in bytes for an HMAC secret
     #[must_use]
-    fn recommended_key_size(self) -> usize {
+    pub(crate) fn recommended_key_size(self) -> usize {
         match self {
             Self::HS256 => 256 / 8,
             Self::HS384 => 384 / 8,
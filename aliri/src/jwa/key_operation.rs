@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::jwa;
+
+/// An operation for which a key is intended to be used
+///
+/// These are the values of the `key_ops` parameter defined in
+/// [RFC7517, Section 4.3][RFC7517].
+///
+/// [RFC7517]: https://tools.ietf.org/html/rfc7517#section-4.3
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+#[must_use]
+pub enum KeyOperation {
+    /// Compute digital signature or MAC
+    Sign,
+
+    /// Verify digital signature or MAC
+    Verify,
+
+    /// Encrypt content
+    Encrypt,
+
+    /// Decrypt content and validate decryption, if applicable
+    Decrypt,
+
+    /// Encrypt key
+    WrapKey,
+
+    /// Decrypt key and validate decryption, if applicable
+    UnwrapKey,
+
+    /// Derive key
+    DeriveKey,
+
+    /// Derive bits not to be used as a key
+    DeriveBits,
+}
+
+impl KeyOperation {
+    /// Gets the usage consistent with this operation
+    pub fn to_usage(self) -> jwa::Usage {
+        match self {
+            Self::Sign | Self::Verify => jwa::Usage::Signing,
+            Self::Encrypt
+            | Self::Decrypt
+            | Self::WrapKey
+            | Self::UnwrapKey
+            | Self::DeriveKey
+            | Self::DeriveBits => jwa::Usage::Encryption,
+        }
+    }
+}
@@ -110,7 +110,7 @@ impl Rsa {
     }
 
     #[cfg(not(feature = "private-keys"))]
-    fn public_key(&self) -> &PublicKey {
+    pub(crate) fn public_key(&self) -> &PublicKey {
         &self.key
     }
 
@@ -6,7 +6,7 @@ use openssl::{bn::BigNum, rsa::Rsa};
 use serde::{Deserialize, Serialize};
 
 use super::SigningAlgorithm;
-use crate::{error, jws};
+use crate::{error, jwk, jws};
 
 /// RSA public key components
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -88,6 +88,26 @@ impl PublicKey {
 
         Ok(Self { modulus, exponent })
     }
+
+    /// Constructs a public key from the modulus and exponent, checking the
+    /// key against the key policy
+    ///
+    /// Unlike [`from_components()`][PublicKey::from_components()], this
+    /// checks the size of the modulus without counting any leading zeros.
+    ///
+    /// # Errors
+    ///
+    /// The modulus or exponent is not valid as an RSA public key, or the
+    /// modulus is smaller than the key policy allows.
+    pub fn try_from_components_with_policy(
+        modulus: impl Into<Base64Url>,
+        exponent: impl Into<Base64Url>,
+        policy: &jwk::KeyPolicy,
+    ) -> Result<Self, error::KeyRejected> {
+        let key = Self::from_components(modulus, exponent)?;
+        policy.check_rsa(&key)?;
+        Ok(key)
+    }
 }
 
 impl jws::Verifier for PublicKey {
@@ -4,8 +4,12 @@
 //!
 //! [RFC7517]: https://tools.ietf.org/html/rfc7517
 
-use std::convert::{TryFrom, TryInto};
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+};
 
+use aliri_base64::Base64Url;
 use aliri_braid::braid;
 use serde::{Deserialize, Serialize, Serializer};
 
@@ -14,6 +18,10 @@ use crate::{
     jws::{self, Signer, Verifier},
 };
 
+mod policy;
+
+pub use policy::KeyPolicy;
+
 /// An identifier for a JWK
 #[braid(serde, ref_doc = "A borrowed reference to JWK identifier ([`KeyId`])")]
 pub struct KeyId;
@@ -25,6 +33,7 @@ pub struct KeyId;
 pub struct Jwk {
     key_id: Option<KeyId>,
     usage: Option<jwa::Usage>,
+    key_ops: Option<Vec<jwa::KeyOperation>>,
     algorithm: Option<jwa::Algorithm>,
     key: Key,
 }
@@ -42,6 +51,12 @@ impl Jwk {
         self.usage
     }
 
+    /// The operations for which the key is intended to be used
+    #[must_use]
+    pub fn key_ops(&self) -> Option<&[jwa::KeyOperation]> {
+        self.key_ops.as_deref()
+    }
+
     /// The algorithm to be used with this JWK
     #[must_use]
     pub fn algorithm(&self) -> Option<jwa::Algorithm> {
@@ -70,6 +85,24 @@ impl Jwk {
         }
     }
 
+    /// Sets the operations for which the key is intended to be used
+    ///
+    /// Duplicate operations are ignored. Operations that are inconsistent
+    /// with the key's usage will never be permitted.
+    pub fn with_key_ops(self, ops: impl IntoIterator<Item = jwa::KeyOperation>) -> Self {
+        let mut key_ops = Vec::new();
+        for op in ops {
+            if !key_ops.contains(&op) {
+                key_ops.push(op);
+            }
+        }
+
+        Self {
+            key_ops: Some(key_ops),
+            ..self
+        }
+    }
+
     /// Sets the algorithm and usage consistent with that algorithm
     pub fn with_algorithm(self, alg: impl Into<jwa::Algorithm>) -> Self {
         let alg = alg.into();
@@ -87,6 +120,42 @@ impl Jwk {
             ..self
         }
     }
+
+    /// Computes the [RFC7638][] thumbprint of the key using SHA-256
+    ///
+    /// The thumbprint only covers the required public members of the key,
+    /// so a private key and its public counterpart share the same thumbprint.
+    ///
+    /// [RFC7638]: https://tools.ietf.org/html/rfc7638
+    pub fn thumbprint(&self) -> Base64Url {
+        let members: BTreeMap<String, serde_json::Value> =
+            match serde_json::to_value(self.key.clone().public_only()) {
+                Ok(serde_json::Value::Object(members)) => members.into_iter().collect(),
+                _ => unreachable!("keys always serialize to JSON objects"),
+            };
+
+        let canonical = serde_json::to_vec(&members).expect("JSON values can always be serialized");
+        let digest = ring::digest::digest(&ring::digest::SHA256, &canonical);
+
+        Base64Url::from_raw(digest.as_ref().to_vec())
+    }
+
+    /// Whether the key's usage and operations permit the given operation
+    fn permits(&self, op: jwa::KeyOperation) -> bool {
+        if let Some(u) = self.usage {
+            if u != op.to_usage() {
+                return false;
+            }
+        }
+
+        if let Some(ops) = &self.key_ops {
+            if !ops.contains(&op) {
+                return false;
+            }
+        }
+
+        true
+    }
 }
 
 #[cfg(feature = "hmac")]
@@ -96,6 +165,7 @@ impl From<jwa::Hmac> for Jwk {
         Self {
             key_id: None,
             usage: None,
+            key_ops: None,
             algorithm: None,
             key: Key::from(key),
         }
@@ -109,6 +179,7 @@ impl From<jwa::Rsa> for Jwk {
         Self {
             key_id: None,
             usage: None,
+            key_ops: None,
             algorithm: None,
             key: Key::from(key),
         }
@@ -122,6 +193,7 @@ impl From<jwa::rsa::PublicKey> for Jwk {
         Self {
             key_id: None,
             usage: None,
+            key_ops: None,
             algorithm: None,
             key: Key::from(key),
         }
@@ -135,6 +207,7 @@ impl From<jwa::rsa::PrivateKey> for Jwk {
         Self {
             key_id: None,
             usage: None,
+            key_ops: None,
             algorithm: None,
             key: Key::from(key),
         }
@@ -148,6 +221,7 @@ impl From<jwa::EllipticCurve> for Jwk {
         Self {
             key_id: None,
             usage: None,
+            key_ops: None,
             algorithm: None,
             key: Key::from(key),
         }
@@ -161,6 +235,7 @@ impl From<jwa::ec::PublicKey> for Jwk {
         Self {
             key_id: None,
             usage: None,
+            key_ops: None,
             algorithm: None,
             key: Key::from(key),
         }
@@ -174,6 +249,7 @@ impl From<jwa::ec::PrivateKey> for Jwk {
         Self {
             key_id: None,
             usage: None,
+            key_ops: None,
             algorithm: None,
             key: Key::from(key),
         }
@@ -185,6 +261,10 @@ impl Verifier for Jwk {
     type Error = error::JwkVerifyError;
 
     fn can_verify(&self, alg: Self::Algorithm) -> bool {
+        if !self.permits(jwa::KeyOperation::Verify) {
+            return false;
+        }
+
         if let Ok(alg) = jws::Algorithm::try_from(alg) {
             self.key.can_verify(alg)
         } else {
@@ -202,10 +282,8 @@ impl Verifier for Jwk {
             return Err(error::jwk_usage_mismatch().into());
         }
 
-        if let Some(u) = self.usage {
-            if u != jwa::Usage::Signing {
-                return Err(error::jwk_usage_mismatch().into());
-            }
+        if !self.permits(jwa::KeyOperation::Verify) {
+            return Err(error::jwk_usage_mismatch().into());
         }
 
         match self.algorithm {
@@ -228,6 +306,10 @@ impl Signer for Jwk {
     type Error = error::SigningError;
 
     fn can_sign(&self, alg: Self::Algorithm) -> bool {
+        if !self.permits(jwa::KeyOperation::Sign) {
+            return false;
+        }
+
         if let Ok(alg) = jws::Algorithm::try_from(alg) {
             self.key.can_sign(alg)
         } else {
@@ -240,10 +322,8 @@ impl Signer for Jwk {
             return Err(error::jwk_usage_mismatch().into());
         }
 
-        if let Some(u) = self.usage {
-            if u != jwa::Usage::Signing {
-                return Err(error::jwk_usage_mismatch().into());
-            }
+        if !self.permits(jwa::KeyOperation::Sign) {
+            return Err(error::jwk_usage_mismatch().into());
         }
 
         match self.algorithm {
@@ -261,13 +341,16 @@ impl Signer for Jwk {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-struct JwkDto {
+pub(crate) struct JwkDto {
     #[serde(rename = "kid", default, skip_serializing_if = "Option::is_none")]
-    key_id: Option<KeyId>,
+    pub(crate) key_id: Option<KeyId>,
 
     #[serde(rename = "use", default, skip_serializing_if = "Option::is_none")]
     usage: Option<jwa::Usage>,
 
+    #[serde(rename = "key_ops", default, skip_serializing_if = "Option::is_none")]
+    key_ops: Option<Vec<jwa::KeyOperation>>,
+
     #[serde(rename = "alg", default, skip_serializing_if = "Option::is_none")]
     algorithm: Option<jwa::Algorithm>,
 
@@ -276,24 +359,60 @@ struct JwkDto {
 }
 
 impl TryFrom<JwkDto> for Jwk {
-    type Error = error::IncompatibleAlgorithm;
+    type Error = error::KeyRejected;
 
     fn try_from(dto: JwkDto) -> Result<Self, Self::Error> {
         if let Some(alg) = &dto.algorithm {
             if !dto.key.is_compatible(*alg) {
-                return Err(error::incompatible_algorithm(*alg));
+                return Err(error::key_rejected(error::incompatible_algorithm(*alg)));
             }
         }
 
+        if let Some(ops) = &dto.key_ops {
+            let usage = dto
+                .usage
+                .or_else(|| dto.algorithm.map(jwa::Algorithm::to_usage));
+            check_key_ops(ops, usage)?;
+        }
+
+        KeyPolicy::default().check_key(&dto.key, dto.algorithm)?;
+
         Ok(Self {
             key_id: dto.key_id,
             usage: dto.usage,
+            key_ops: dto.key_ops,
             algorithm: dto.algorithm,
             key: dto.key,
         })
     }
 }
 
+/// Checks that the key operations are unique and consistent with the key's usage
+fn check_key_ops(
+    ops: &[jwa::KeyOperation],
+    usage: Option<jwa::Usage>,
+) -> Result<(), error::KeyRejected> {
+    for (idx, op) in ops.iter().enumerate() {
+        if ops[..idx].contains(op) {
+            return Err(error::key_rejected(format!(
+                "key operation {:?} is listed more than once",
+                op
+            )));
+        }
+
+        if let Some(usage) = usage {
+            if op.to_usage() != usage {
+                return Err(error::key_rejected(format!(
+                    "key operation {:?} is inconsistent with key usage {:?}",
+                    op, usage
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 struct JwkDtoRef<'a> {
     #[serde(rename = "kid")]
@@ -302,6 +421,9 @@ struct JwkDtoRef<'a> {
     #[serde(rename = "use")]
     usage: Option<jwa::Usage>,
 
+    #[serde(rename = "key_ops", skip_serializing_if = "Option::is_none")]
+    key_ops: Option<&'a [jwa::KeyOperation]>,
+
     #[serde(rename = "alg")]
     algorithm: Option<jwa::Algorithm>,
 
@@ -314,6 +436,7 @@ impl Serialize for Jwk {
         let dto = JwkDtoRef {
             key_id: self.key_id(),
             usage: self.usage(),
+            key_ops: self.key_ops(),
             algorithm: self.algorithm(),
             key: &self.key,
         };
@@ -613,6 +736,59 @@ mod tests {
                 assert_eq!(key.algorithm, None);
                 Ok(())
             }
+
+            const JWK_WITH_KEY_OPS: &str = r#"{
+                "use": "sig",
+                "key_ops": ["verify"],
+                "kty": "oct",
+                "k": "1nk4304g9iJ904hpKLBYo4vL10HIx8QC0scYYpY7vSg"
+            }"#;
+
+            const JWK_WITH_INCONSISTENT_KEY_OPS: &str = r#"{
+                "alg": "HS256",
+                "key_ops": ["verify", "encrypt"],
+                "kty": "oct",
+                "k": "1nk4304g9iJ904hpKLBYo4vL10HIx8QC0scYYpY7vSg"
+            }"#;
+
+            const JWK_WITH_DUPLICATE_KEY_OPS: &str = r#"{
+                "key_ops": ["sign", "verify", "sign"],
+                "kty": "oct",
+                "k": "1nk4304g9iJ904hpKLBYo4vL10HIx8QC0scYYpY7vSg"
+            }"#;
+
+            #[test]
+            fn deserialize_with_key_ops() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK_WITH_KEY_OPS)?;
+                assert_eq!(key.key_ops(), Some(&[jwa::KeyOperation::Verify][..]));
+                Ok(())
+            }
+
+            #[test]
+            fn key_ops_roundtrip() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK_WITH_KEY_OPS)?;
+                let serialized = serde_json::to_string(&key)?;
+                assert!(
+                    serialized.contains(r#""key_ops":["verify"]"#),
+                    "{}",
+                    serialized
+                );
+                let roundtrip: Jwk = serde_json::from_str(&serialized)?;
+                assert_eq!(roundtrip, key);
+                Ok(())
+            }
+
+            #[test]
+            fn rejects_key_ops_inconsistent_with_alg() {
+                let err = serde_json::from_str::<Jwk>(JWK_WITH_INCONSISTENT_KEY_OPS).unwrap_err();
+                assert!(err.to_string().contains("inconsistent"), "{}", err);
+            }
+
+            #[test]
+            fn rejects_duplicate_key_ops() {
+                let err = serde_json::from_str::<Jwk>(JWK_WITH_DUPLICATE_KEY_OPS).unwrap_err();
+                assert!(err.to_string().contains("more than once"), "{}", err);
+            }
         }
 
         #[cfg(feature = "rsa")]
@@ -633,6 +809,171 @@ mod tests {
                 assert_eq!(key.algorithm, None);
                 Ok(())
             }
+
+            #[test]
+            fn thumbprint_matches_rfc7638_example() -> Result<()> {
+                let key: Jwk = serde_json::from_str(
+                    r#"{
+                        "kty": "RSA",
+                        "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+                        "e": "AQAB",
+                        "alg": "RS256",
+                        "kid": "2011-04-29"
+                    }"#,
+                )?;
+
+                assert_eq!(
+                    key.thumbprint().to_string(),
+                    "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+                );
+                Ok(())
+            }
+        }
+    }
+
+    mod policy {
+        use super::*;
+
+        #[cfg(feature = "ec")]
+        mod ec {
+            use super::*;
+            use crate::test::ec::*;
+
+            #[test]
+            fn accepts_allowed_curve() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK_P256)?;
+                KeyPolicy::default().check(&key)?;
+                Ok(())
+            }
+
+            #[test]
+            fn rejects_disallowed_curve() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK_P256)?;
+                let policy = KeyPolicy::default().with_allowed_ec_curves([jwa::ec::Curve::P384]);
+                let err = policy.check(&key).unwrap_err();
+                assert!(err.to_string().contains("not allowed"), "{}", err);
+                Ok(())
+            }
+        }
+
+        #[cfg(feature = "hmac")]
+        mod hmac {
+            use super::*;
+            use crate::test::hmac::*;
+
+            const JWK_HS512_SHORT_SECRET: &str = r#"{
+                "alg": "HS512",
+                "kty": "oct",
+                "k": "1nk4304g9iJ904hpKLBYo4vL10HIx8QC0scYYpY7vSg"
+            }"#;
+
+            #[test]
+            fn accepts_secret_of_recommended_size() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK)?;
+                KeyPolicy::default().check(&key)?;
+                Ok(())
+            }
+
+            #[test]
+            fn rejects_short_secret_on_deserialize() {
+                let err = serde_json::from_str::<Jwk>(JWK_HS512_SHORT_SECRET).unwrap_err();
+                assert!(
+                    err.to_string()
+                        .contains("HMAC secret must be at least 64 bytes, but it was 32 bytes"),
+                    "{}",
+                    err
+                );
+            }
+
+            #[test]
+            fn rejects_short_secret_for_configured_alg() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK)?;
+                let policy = KeyPolicy::default()
+                    .with_min_hmac_secret_len(jwa::hmac::SigningAlgorithm::HS256, 48);
+                assert!(policy.check(&key).is_err());
+                Ok(())
+            }
+
+            #[test]
+            fn checks_secret_when_constructing_with_policy() {
+                let policy = KeyPolicy::default();
+                let short = Base64Url::from_raw(vec![0x42; 1]);
+                let err = jwa::Hmac::try_new_with_policy(
+                    short,
+                    jwa::hmac::SigningAlgorithm::HS256,
+                    &policy,
+                )
+                .unwrap_err();
+                assert!(
+                    err.to_string()
+                        .contains("HMAC secret must be at least 32 bytes, but it was 1 bytes"),
+                    "{}",
+                    err
+                );
+
+                let secret = Base64Url::from_raw(vec![0x42; 32]);
+                assert!(jwa::Hmac::try_new_with_policy(
+                    secret,
+                    jwa::hmac::SigningAlgorithm::HS256,
+                    &policy
+                )
+                .is_ok());
+            }
+
+            #[test]
+            fn holds_secret_without_alg_to_least_strict_minimum() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK_MINIMAL)?;
+                let policy = KeyPolicy::default()
+                    .with_min_hmac_secret_len(jwa::hmac::SigningAlgorithm::HS256, 48);
+                assert!(policy.check(&key).is_err());
+                KeyPolicy::default().check(&key)?;
+                Ok(())
+            }
+        }
+
+        #[cfg(feature = "rsa")]
+        mod rsa {
+            use super::*;
+            use crate::test::rsa::*;
+
+            #[test]
+            fn accepts_2048_bit_modulus() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK)?;
+                KeyPolicy::default().check(&key)?;
+                Ok(())
+            }
+
+            #[test]
+            fn checks_modulus_when_constructing_with_policy() {
+                let mut padded = vec![0; 128];
+                padded.resize(256, 0xff);
+                let err = jwa::rsa::PublicKey::try_from_components_with_policy(
+                    Base64Url::from_raw(padded),
+                    Base64Url::from_raw(vec![1, 0, 1]),
+                    &KeyPolicy::default(),
+                )
+                .unwrap_err();
+                assert!(
+                    err.to_string()
+                        .contains("RSA modulus must be at least 2048 bits, but it was 1024 bits"),
+                    "{}",
+                    err
+                );
+            }
+
+            #[test]
+            fn rejects_modulus_below_minimum() -> Result<()> {
+                let key: Jwk = serde_json::from_str(JWK)?;
+                let policy = KeyPolicy::default().with_min_rsa_modulus_bits(3072);
+                let err = policy.check(&key).unwrap_err();
+                assert!(
+                    err.to_string()
+                        .contains("RSA modulus must be at least 3072 bits, but it was 2048 bits"),
+                    "{}",
+                    err
+                );
+                Ok(())
+            }
         }
     }
 
@@ -680,6 +1021,7 @@ mod tests {
                 let key = Jwk {
                     key_id: None,
                     usage: Some(jwa::Usage::Encryption),
+                    key_ops: None,
                     algorithm: None,
                     key: Key::from(jwa::ec::PublicKey::from_public_point(
                         jwa::ec::Curve::P256,
@@ -697,6 +1039,7 @@ mod tests {
                 let key = Jwk {
                     key_id: None,
                     usage: Some(jwa::Usage::Signing),
+                    key_ops: None,
                     algorithm: None,
                     key: Key::from(jwa::ec::PublicKey::from_public_point(
                         jwa::ec::Curve::P256,
@@ -756,6 +1099,7 @@ mod tests {
                 let key = Jwk {
                     key_id: None,
                     usage: Some(jwa::Usage::Encryption),
+                    key_ops: None,
                     algorithm: None,
                     key: Key::Rsa(
                         jwa::Rsa::from_public_components(
@@ -838,6 +1182,7 @@ mod tests {
                 let key = Jwk {
                     key_id: None,
                     usage: Some(jwa::Usage::Encryption),
+                    key_ops: None,
                     algorithm: None,
                     key: Key::Hmac(jwa::Hmac::new(Vec::new())),
                 };
@@ -847,6 +1192,28 @@ mod tests {
                 assert!(err.is_usage_mismatch());
             }
 
+            #[test]
+            fn error_signing_with_verify_only_key() {
+                let key: Jwk = serde_json::from_str(JWK_MINIMAL).unwrap();
+                let key = key.with_key_ops([jwa::KeyOperation::Verify]);
+
+                assert!(!key.can_sign(jwa::Algorithm::HS256));
+                let err = dbg!(key.sign(jwa::Algorithm::HS256, &[])).unwrap_err();
+
+                assert!(matches!(err, error::SigningError::JwkUsageMismatch(_)));
+            }
+
+            #[test]
+            fn error_verifying_with_sign_only_key() {
+                let key: Jwk = serde_json::from_str(JWK_MINIMAL).unwrap();
+                let key = key.with_key_ops([jwa::KeyOperation::Sign]);
+
+                assert!(!key.can_verify(jwa::Algorithm::HS256));
+                let err = dbg!(key.verify(jwa::Algorithm::HS256, &[], &[])).unwrap_err();
+
+                assert!(err.is_usage_mismatch());
+            }
+
             #[test]
             fn verify_hs256() -> Result<(), error::JwkVerifyError> {
                 const MESSAGE: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCIsImtpZCI6IkVrS2h5UHF0ZCJ9.eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWUsImlhdCI6MTUxNjIzOTAyMn0";
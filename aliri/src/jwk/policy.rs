@@ -0,0 +1,238 @@
+use super::{Jwk, Key};
+use crate::{error, jwa};
+
+/// Minimum strength requirements that a key must meet in order to be accepted
+///
+/// The default policy follows the minimums required by [RFC7518][]: RSA
+/// moduli must be at least 2048 bits, HMAC secrets must be at least as
+/// large as the hash output of the algorithm they are used with, and all
+/// supported elliptic curves are allowed.
+///
+/// The default policy is applied whenever a [`Jwk`] is deserialized.
+/// Stricter policies can be applied to individual keys with
+/// [`check()`][KeyPolicy::check()] or to an entire key set with
+/// [`Jwks::retain_acceptable()`][crate::Jwks::retain_acceptable()].
+///
+/// [RFC7518]: https://tools.ietf.org/html/rfc7518
+///
+/// # Example
+///
+/// ```
+/// use aliri::{jwa, jwk};
+///
+/// let policy = jwk::KeyPolicy::default()
+///     .with_min_rsa_modulus_bits(3072)
+///     .with_min_hmac_secret_len(jwa::hmac::SigningAlgorithm::HS256, 64);
+///
+/// assert_eq!(policy.min_rsa_modulus_bits(), 3072);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(missing_copy_implementations)]
+#[must_use]
+pub struct KeyPolicy {
+    #[cfg(feature = "rsa")]
+    min_rsa_modulus_bits: usize,
+
+    #[cfg(feature = "hmac")]
+    min_hs256_secret_len: usize,
+
+    #[cfg(feature = "hmac")]
+    min_hs384_secret_len: usize,
+
+    #[cfg(feature = "hmac")]
+    min_hs512_secret_len: usize,
+
+    #[cfg(feature = "ec")]
+    allowed_ec_curves: Vec<jwa::ec::Curve>,
+}
+
+impl Default for KeyPolicy {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "rsa")]
+            min_rsa_modulus_bits: 2048,
+
+            #[cfg(feature = "hmac")]
+            min_hs256_secret_len: jwa::hmac::SigningAlgorithm::HS256.recommended_key_size(),
+
+            #[cfg(feature = "hmac")]
+            min_hs384_secret_len: jwa::hmac::SigningAlgorithm::HS384.recommended_key_size(),
+
+            #[cfg(feature = "hmac")]
+            min_hs512_secret_len: jwa::hmac::SigningAlgorithm::HS512.recommended_key_size(),
+
+            #[cfg(feature = "ec")]
+            allowed_ec_curves: vec![
+                jwa::ec::Curve::P256,
+                jwa::ec::Curve::P384,
+                jwa::ec::Curve::P521,
+            ],
+        }
+    }
+}
+
+impl KeyPolicy {
+    /// Checks whether the key meets the requirements of this policy
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the deficiency if the key does not
+    /// meet the requirements of this policy.
+    pub fn check(&self, jwk: &Jwk) -> Result<(), error::KeyRejected> {
+        self.check_key(&jwk.key, jwk.algorithm)
+    }
+
+    #[cfg_attr(not(feature = "hmac"), allow(unused_variables))]
+    pub(super) fn check_key(
+        &self,
+        key: &Key,
+        alg: Option<jwa::Algorithm>,
+    ) -> Result<(), error::KeyRejected> {
+        match key {
+            #[cfg(feature = "rsa")]
+            Key::Rsa(k) => self.check_rsa(k.public_key()),
+
+            #[cfg(feature = "hmac")]
+            Key::Hmac(k) => self.check_hmac(k, alg),
+
+            #[cfg(feature = "ec")]
+            Key::EllipticCurve(k) => self.check_ec(k.public_key().curve()),
+
+            #[cfg(not(any(feature = "hmac", feature = "rsa", feature = "ec")))]
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "rsa")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rsa")))]
+impl KeyPolicy {
+    /// The minimum size, in bits, of an acceptable RSA modulus
+    #[must_use]
+    pub fn min_rsa_modulus_bits(&self) -> usize {
+        self.min_rsa_modulus_bits
+    }
+
+    /// Sets the minimum size, in bits, of an acceptable RSA modulus
+    ///
+    /// A minimum below 2048 bits is honored when checking keys, but such
+    /// keys still cannot be used to verify signatures, as RSA verification
+    /// requires a modulus of at least 2048 bits.
+    pub fn with_min_rsa_modulus_bits(self, bits: usize) -> Self {
+        Self {
+            min_rsa_modulus_bits: bits,
+            ..self
+        }
+    }
+
+    pub(crate) fn check_rsa(&self, key: &jwa::rsa::PublicKey) -> Result<(), error::KeyRejected> {
+        let bits = modulus_bits(key.modulus().as_slice());
+        if bits < self.min_rsa_modulus_bits {
+            return Err(error::key_rejected(format!(
+                "RSA modulus must be at least {} bits, but it was {} bits",
+                self.min_rsa_modulus_bits, bits
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rsa")]
+fn modulus_bits(modulus: &[u8]) -> usize {
+    let significant = match modulus.iter().position(|&b| b != 0) {
+        Some(idx) => &modulus[idx..],
+        None => return 0,
+    };
+
+    significant.len() * 8 - significant[0].leading_zeros() as usize
+}
+
+#[cfg(feature = "hmac")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hmac")))]
+impl KeyPolicy {
+    /// The minimum length, in bytes, of an HMAC secret used with the given algorithm
+    #[must_use]
+    pub fn min_hmac_secret_len(&self, alg: jwa::hmac::SigningAlgorithm) -> usize {
+        match alg {
+            jwa::hmac::SigningAlgorithm::HS256 => self.min_hs256_secret_len,
+            jwa::hmac::SigningAlgorithm::HS384 => self.min_hs384_secret_len,
+            jwa::hmac::SigningAlgorithm::HS512 => self.min_hs512_secret_len,
+        }
+    }
+
+    /// Sets the minimum length, in bytes, of an HMAC secret used with the given algorithm
+    pub fn with_min_hmac_secret_len(self, alg: jwa::hmac::SigningAlgorithm, len: usize) -> Self {
+        match alg {
+            jwa::hmac::SigningAlgorithm::HS256 => Self {
+                min_hs256_secret_len: len,
+                ..self
+            },
+            jwa::hmac::SigningAlgorithm::HS384 => Self {
+                min_hs384_secret_len: len,
+                ..self
+            },
+            jwa::hmac::SigningAlgorithm::HS512 => Self {
+                min_hs512_secret_len: len,
+                ..self
+            },
+        }
+    }
+
+    /// HMAC keys that do not declare an algorithm are held to the least
+    /// strict of the HMAC minimums.
+    pub(crate) fn check_hmac(
+        &self,
+        key: &jwa::Hmac,
+        alg: Option<jwa::Algorithm>,
+    ) -> Result<(), error::KeyRejected> {
+        let min_len = match alg {
+            Some(jwa::Algorithm::Signing(crate::jws::Algorithm::Hmac(alg))) => {
+                self.min_hmac_secret_len(alg)
+            }
+            _ => self
+                .min_hs256_secret_len
+                .min(self.min_hs384_secret_len)
+                .min(self.min_hs512_secret_len),
+        };
+
+        let len = key.secret().as_slice().len();
+        if len < min_len {
+            return Err(error::key_rejected(format!(
+                "HMAC secret must be at least {} bytes, but it was {} bytes",
+                min_len, len
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ec")))]
+impl KeyPolicy {
+    /// The elliptic curves that are acceptable
+    #[must_use]
+    pub fn allowed_ec_curves(&self) -> &[jwa::ec::Curve] {
+        &self.allowed_ec_curves
+    }
+
+    /// Sets the elliptic curves that are acceptable
+    pub fn with_allowed_ec_curves(self, curves: impl IntoIterator<Item = jwa::ec::Curve>) -> Self {
+        Self {
+            allowed_ec_curves: curves.into_iter().collect(),
+            ..self
+        }
+    }
+
+    fn check_ec(&self, curve: jwa::ec::Curve) -> Result<(), error::KeyRejected> {
+        if !self.allowed_ec_curves.contains(&curve) {
+            return Err(error::key_rejected(format!(
+                "elliptic curve {:?} is not allowed",
+                curve
+            )));
+        }
+
+        Ok(())
+    }
+}
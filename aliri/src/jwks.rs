@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+
 use serde::Serialize;
 
 use crate::{jwa, jwk, Jwk};
@@ -14,12 +16,31 @@ impl Jwks {
         self.keys.push(key);
     }
 
+    /// Removes any keys that do not meet the requirements of the key policy
+    pub fn retain_acceptable(&mut self, policy: &jwk::KeyPolicy) {
+        self.keys.retain(|key| match policy.check(key) {
+            Ok(()) => true,
+            Err(err) => {
+                #[cfg(feature = "tracing")]
+                {
+                    let error: &dyn std::error::Error = &err;
+                    tracing::warn!(error, jwk.kid = ?key.key_id(), "ignoring rejected JWK");
+                }
+                let _ = err;
+                false
+            }
+        });
+    }
+
     /// A view of the keys in this set
     pub fn keys(&self) -> &[Jwk] {
         &self.keys
     }
 
     /// Gets the best key based on the algorithm requested
+    ///
+    /// Keys are selected for verification, so a key whose `key_ops` does not
+    /// include `verify` is never selected.
     pub fn get_key<A: Into<jwa::Algorithm>>(&self, alg: A) -> Option<&Jwk> {
         get_key_impl(self.keys(), alg.into())
     }
@@ -72,6 +93,12 @@ fn get_key_impl(keys: &[Jwk], alg: jwa::Algorithm) -> Option<&Jwk> {
             }
         }
 
+        if let Some(key_ops) = k.key_ops() {
+            if !key_ops.contains(&jwa::KeyOperation::Verify) {
+                return best;
+            }
+        }
+
         match best {
             Some((_, best_score)) if best_score < score => Some((k, score)),
             None => Some((k, score)),
@@ -120,6 +147,12 @@ fn get_key_by_id_impl<'a>(
             }
         }
 
+        if let Some(key_ops) = k.key_ops() {
+            if !key_ops.contains(&jwa::KeyOperation::Verify) {
+                return best;
+            }
+        }
+
         match best {
             Some((_, best_score)) if best_score < score => Some((k, score)),
             None => Some((k, score)),
@@ -209,6 +242,97 @@ mod tests {
             assert_eq!(jwks.keys.len(), 1);
             Ok(())
         }
+
+        #[test]
+        #[cfg_attr(feature = "tracing", traced_test)]
+        fn retains_only_acceptable_keys() -> Result<()> {
+            let mut jwks: Jwks = serde_json::from_str(JWKS)?;
+            jwks.retain_acceptable(&jwk::KeyPolicy::default().with_min_rsa_modulus_bits(4096));
+            assert!(jwks.keys.is_empty());
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "hmac")]
+    mod hmac {
+        use super::*;
+
+        const JWKS_WITH_WEAK_KEY: &str = r#"
+            {
+                "keys": [
+                    {
+                        "kid": "weak",
+                        "alg": "HS256",
+                        "kty": "oct",
+                        "k": "c2hvcnQ"
+                    },
+                    {
+                        "kid": "strong",
+                        "alg": "HS256",
+                        "kty": "oct",
+                        "k": "1nk4304g9iJ904hpKLBYo4vL10HIx8QC0scYYpY7vSg"
+                    }
+                ]
+            }
+        "#;
+
+        #[test]
+        #[cfg_attr(feature = "tracing", traced_test)]
+        fn ignores_weak_keys() -> Result<()> {
+            let jwks: Jwks = serde_json::from_str(JWKS_WITH_WEAK_KEY)?;
+            dbg!(&jwks);
+            assert_eq!(jwks.keys.len(), 1);
+            assert_eq!(jwks.keys[0].key_id().unwrap().as_str(), "strong");
+            Ok(())
+        }
+
+        const JWKS_WITH_SIGN_ONLY_KEY: &str = r#"
+            {
+                "keys": [
+                    {
+                        "kid": "shared",
+                        "alg": "HS256",
+                        "kty": "oct",
+                        "key_ops": ["sign"],
+                        "k": "1nk4304g9iJ904hpKLBYo4vL10HIx8QC0scYYpY7vSg"
+                    },
+                    {
+                        "kid": "shared",
+                        "alg": "HS256",
+                        "kty": "oct",
+                        "key_ops": ["verify"],
+                        "k": "c2VwYXJhdGUga2V5IGZvciB2ZXJpZmljYXRpb24gb25seQ"
+                    },
+                    {
+                        "kid": "sign-only",
+                        "alg": "HS256",
+                        "kty": "oct",
+                        "key_ops": ["sign"],
+                        "k": "1nk4304g9iJ904hpKLBYo4vL10HIx8QC0scYYpY7vSg"
+                    }
+                ]
+            }
+        "#;
+
+        #[test]
+        #[cfg_attr(feature = "tracing", traced_test)]
+        fn selects_only_keys_permitted_to_verify() -> Result<()> {
+            let jwks: Jwks = serde_json::from_str(JWKS_WITH_SIGN_ONLY_KEY)?;
+            assert_eq!(jwks.keys.len(), 3);
+
+            let shared = jwk::KeyIdRef::from_str("shared");
+            let key = jwks.get_key_by_id(shared, jwa::Algorithm::HS256).unwrap();
+            assert_eq!(key.key_ops(), Some(&[jwa::KeyOperation::Verify][..]));
+
+            let sign_only = jwk::KeyIdRef::from_str("sign-only");
+            assert!(jwks
+                .get_key_by_id(sign_only, jwa::Algorithm::HS256)
+                .is_none());
+
+            let key = jwks.get_key(jwa::Algorithm::HS256).unwrap();
+            assert_eq!(key.key_ops(), Some(&[jwa::KeyOperation::Verify][..]));
+            Ok(())
+        }
     }
 
     #[cfg(all(feature = "rsa", feature = "hmac", feature = "ec"))]
@@ -241,7 +365,7 @@ impl<'de> serde::Deserialize<'de> for Jwks {
         #[derive(serde::Deserialize)]
         #[serde(untagged)]
         enum MaybeJwk {
-            Jwk(Jwk),
+            Jwk(jwk::JwkDto),
             Unknown(JwkLike),
         }
 
@@ -262,7 +386,24 @@ impl<'de> serde::Deserialize<'de> for Jwks {
             .into_iter()
             .enumerate()
             .filter_map(|(idx, k)| match k {
-                MaybeJwk::Jwk(key) => Some(key),
+                MaybeJwk::Jwk(dto) => {
+                    let kid = dto.key_id.clone();
+                    match Jwk::try_from(dto) {
+                        Ok(key) => Some(key),
+                        Err(err) => {
+                            #[cfg(feature = "tracing")]
+                            {
+                                let error: &dyn std::error::Error = &err;
+                                tracing::warn!(
+                                    error, jwk.kid = ?kid, jwks.idx = idx,
+                                    "ignoring rejected JWK"
+                                );
+                            }
+                            let _ = (kid, err);
+                            None
+                        }
+                    }
+                }
                 MaybeJwk::Unknown(key) => {
                     #[cfg(feature = "tracing")]
                     tracing::warn!(
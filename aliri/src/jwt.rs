@@ -63,9 +63,16 @@ use serde::{Deserialize, Serialize};
 
 use crate::{error, jwa, jwk, jws, jws::Signer, Jwk};
 
+mod batch;
+mod dynamic;
+mod rules;
 #[cfg(all(not(feature = "no-unstable"), feature = "unstable"))]
 mod validator;
 
+pub use batch::BatchVerifier;
+pub use dynamic::DynamicClaims;
+pub use rules::{ClaimRule, ClaimRules};
+
 #[cfg(all(not(feature = "no-unstable"), feature = "unstable"))]
 use validator::Validator;
 
@@ -219,7 +226,9 @@ where
             claims: payload,
         })
     }
+}
 
+impl<'a, H> Decomposed<'a, H> {
     /// The untrusted headers of the JWT
     ///
     /// **WARNING:** *This headers has not been validated and should not be trusted.*
@@ -873,7 +882,13 @@ impl CoreValidator {
         }
     }
 
-    pub(crate) fn validate<H: CoreHeaders, T: CoreClaims>(
+    /// Validates the header and claims of a token whose signature has
+    /// already been verified, checking times against the system clock
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header or claims are rejected by the validator.
+    pub fn validate<H: CoreHeaders, T: CoreClaims>(
         &self,
         header: &H,
         claims: &T,
@@ -881,7 +896,13 @@ impl CoreValidator {
         self.validate_with_clock(header, claims, &System)
     }
 
-    pub(crate) fn validate_with_clock<C: Clock, H: CoreHeaders, T: CoreClaims>(
+    /// Validates the header and claims of a token whose signature has
+    /// already been verified, checking times against the given clock
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header or claims are rejected by the validator.
+    pub fn validate_with_clock<C: Clock, H: CoreHeaders, T: CoreClaims>(
         &self,
         header: &H,
         claims: &T,
@@ -0,0 +1,231 @@
+use std::{borrow::Borrow, collections::HashMap};
+
+use serde::Deserialize;
+
+use super::{CoreClaims, CoreHeaders, CoreValidator, HasAlgorithm, JwtRef, Validated};
+use crate::{error, jwa, jwk, Jwk, Jwks};
+
+/// Verifies many JWTs against a single JWKS and validator
+///
+/// Key lookups are memoized by key ID and algorithm, so a batch of tokens
+/// signed by a handful of keys only scans the JWKS a handful of times.
+/// When the `rayon` feature is enabled, [`par_verify()`][Self::par_verify()]
+/// spreads the work across the rayon thread pool.
+///
+/// # Example
+///
+/// ```
+/// use aliri::{jwa, jwt, Jwk, Jwks, JwtRef};
+/// use aliri_base64::Base64UrlRef;
+///
+/// let secret = Base64UrlRef::from_slice(b"test").to_owned();
+/// let mut jwks = Jwks::default();
+/// jwks.add_key(Jwk::from(jwa::Hmac::new(secret)).with_algorithm(jwa::Algorithm::HS256));
+///
+/// let validator = jwt::CoreValidator::default()
+///     .ignore_expiration()
+///     .add_approved_algorithm(jwa::Algorithm::HS256);
+///
+/// let tokens = [
+///     JwtRef::from_str(concat!(
+///         "eyJhbGciOiJIUzI1NiJ9.",
+///         "eyJzdWIiOiJBbGlyaSIsImF1ZCI6Im15X2FwaSIsImlzcyI6ImF1dGhvcml0eSJ9.",
+///         "2N5yyY2UjqlUKSSCpFVWzfixfBRTWahiN2PrUuiuxbE"
+///     )),
+///     JwtRef::from_str("not-a-jwt"),
+/// ];
+///
+/// let results = jwt::BatchVerifier::new(&jwks, &validator)
+///     .verify::<jwt::BasicClaims, jwt::BasicHeaders, _>(&tokens);
+///
+/// assert!(results[0].is_ok());
+/// assert!(results[1].is_err());
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[must_use]
+pub struct BatchVerifier<'a> {
+    jwks: &'a Jwks,
+    validator: &'a CoreValidator,
+}
+
+impl<'a> BatchVerifier<'a> {
+    /// Constructs a batch verifier for the given JWKS and validator
+    pub fn new(jwks: &'a Jwks, validator: &'a CoreValidator) -> Self {
+        Self { jwks, validator }
+    }
+
+    /// Verifies each token, returning the results in the same order as the tokens
+    pub fn verify<C, H, T>(
+        &self,
+        tokens: &[T],
+    ) -> Vec<Result<Validated<C, H>, error::JwksVerifyError>>
+    where
+        C: for<'de> Deserialize<'de> + CoreClaims,
+        H: for<'de> Deserialize<'de> + CoreHeaders,
+        T: Borrow<JwtRef>,
+    {
+        let mut cache = KeyCache::default();
+        tokens
+            .iter()
+            .map(|token| self.verify_one(&mut cache, token.borrow()))
+            .collect()
+    }
+
+    /// Verifies each token in parallel, returning the results in the same order
+    /// as the tokens
+    #[cfg(feature = "rayon")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    pub fn par_verify<C, H, T>(
+        &self,
+        tokens: &[T],
+    ) -> Vec<Result<Validated<C, H>, error::JwksVerifyError>>
+    where
+        C: for<'de> Deserialize<'de> + CoreClaims + Send,
+        H: for<'de> Deserialize<'de> + CoreHeaders + Send,
+        T: Borrow<JwtRef> + Sync,
+    {
+        use rayon::prelude::*;
+
+        tokens
+            .par_iter()
+            .map_init(KeyCache::default, |cache, token| {
+                self.verify_one(cache, token.borrow())
+            })
+            .collect()
+    }
+
+    fn verify_one<C, H>(
+        &self,
+        cache: &mut KeyCache<'a>,
+        token: &JwtRef,
+    ) -> Result<Validated<C, H>, error::JwksVerifyError>
+    where
+        C: for<'de> Deserialize<'de> + CoreClaims,
+        H: for<'de> Deserialize<'de> + CoreHeaders,
+    {
+        let decomposed = token.decompose::<H>()?;
+        let key = cache
+            .get(self.jwks, decomposed.kid(), decomposed.alg())
+            .ok_or_else(error::no_matching_key)?;
+
+        Ok(decomposed.verify(key, self.validator)?)
+    }
+}
+
+/// Memoized results of looking up keys in a JWKS
+#[derive(Debug, Default)]
+struct KeyCache<'a> {
+    without_kid: HashMap<jwa::Algorithm, Option<&'a Jwk>>,
+    by_kid: HashMap<jwk::KeyId, HashMap<jwa::Algorithm, Option<&'a Jwk>>>,
+}
+
+impl<'a> KeyCache<'a> {
+    fn get(
+        &mut self,
+        jwks: &'a Jwks,
+        kid: Option<&jwk::KeyIdRef>,
+        alg: jwa::Algorithm,
+    ) -> Option<&'a Jwk> {
+        let by_alg = match kid {
+            Some(kid) => {
+                if !self.by_kid.contains_key(kid) {
+                    self.by_kid.insert(kid.to_owned(), HashMap::new());
+                }
+                self.by_kid.get_mut(kid).unwrap()
+            }
+            None => &mut self.without_kid,
+        };
+
+        *by_alg
+            .entry(alg)
+            .or_insert_with(|| jwks.get_key_by_opt(kid, alg))
+    }
+}
+
+#[cfg(all(test, feature = "hmac"))]
+mod tests {
+    use color_eyre::Result;
+
+    use super::*;
+    use crate::jwt::{BasicClaims, BasicHeaders};
+
+    fn setup() -> Result<(Jwks, Vec<crate::jwt::Jwt>)> {
+        let key_a = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?)
+            .with_key_id(jwk::KeyId::from_static("a"))
+            .with_algorithm(jwa::Algorithm::HS256);
+        let key_b = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?)
+            .with_key_id(jwk::KeyId::from_static("b"))
+            .with_algorithm(jwa::Algorithm::HS256);
+        let unknown = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?)
+            .with_key_id(jwk::KeyId::from_static("c"))
+            .with_algorithm(jwa::Algorithm::HS256);
+
+        let claims = BasicClaims::new().with_future_expiration(60);
+        let tokens = vec![
+            claims.sign(
+                &key_a,
+                &BasicHeaders::with_key_id(jwa::Algorithm::HS256, "a"),
+            )?,
+            claims.sign(
+                &key_b,
+                &BasicHeaders::with_key_id(jwa::Algorithm::HS256, "b"),
+            )?,
+            claims.sign(
+                &unknown,
+                &BasicHeaders::with_key_id(jwa::Algorithm::HS256, "c"),
+            )?,
+            claims.sign(
+                &key_b,
+                &BasicHeaders::with_key_id(jwa::Algorithm::HS256, "a"),
+            )?,
+            claims.sign(
+                &key_a,
+                &BasicHeaders::with_key_id(jwa::Algorithm::HS256, "a"),
+            )?,
+        ];
+
+        let mut jwks = Jwks::default();
+        jwks.add_key(key_a);
+        jwks.add_key(key_b);
+
+        Ok((jwks, tokens))
+    }
+
+    fn check(results: &[Result<Validated, error::JwksVerifyError>]) {
+        assert_eq!(results.len(), 5);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        assert!(matches!(
+            results[2],
+            Err(error::JwksVerifyError::NoMatchingKey(_))
+        ));
+        assert!(matches!(
+            results[3],
+            Err(error::JwksVerifyError::JwtVerifyError(_))
+        ));
+        assert!(results[4].is_ok());
+    }
+
+    #[test]
+    fn verifies_each_token() -> Result<()> {
+        let (jwks, tokens) = setup()?;
+        let validator = CoreValidator::default();
+
+        let results = BatchVerifier::new(&jwks, &validator).verify(&tokens);
+
+        check(&results);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn verifies_each_token_in_parallel() -> Result<()> {
+        let (jwks, tokens) = setup()?;
+        let validator = CoreValidator::default();
+
+        let results = BatchVerifier::new(&jwks, &validator).par_verify(&tokens);
+
+        check(&results);
+        Ok(())
+    }
+}
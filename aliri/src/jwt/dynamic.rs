@@ -0,0 +1,148 @@
+use aliri_clock::UnixTime;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use super::{Audiences, BasicClaims, CoreClaims, IssuerRef, SubjectRef};
+
+/// Claims whose shape is not known until runtime
+///
+/// The full claims payload is retained as a [`serde_json::Value`], while the
+/// core claims are extracted up front so that the payload can be checked by a
+/// [`CoreValidator`][super::CoreValidator]. Individual claims can be accessed
+/// by [JSON pointer][RFC6901] and checked with [`ClaimRules`][super::ClaimRules].
+///
+/// [RFC6901]: https://tools.ietf.org/html/rfc6901
+///
+/// # Example
+///
+/// ```
+/// use aliri::jwt::{self, CoreClaims};
+///
+/// let claims: jwt::DynamicClaims = serde_json::from_str(r#"{
+///     "iss": "authority",
+///     "tenant_id": "acme",
+///     "realm_access": { "roles": ["admin"] }
+/// }"#).unwrap();
+///
+/// assert_eq!(claims.iss().unwrap().as_str(), "authority");
+/// assert_eq!(claims.get("/tenant_id").unwrap(), "acme");
+/// assert_eq!(claims.get("/realm_access/roles/0").unwrap(), "admin");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct DynamicClaims {
+    core: BasicClaims,
+    value: Value,
+}
+
+impl DynamicClaims {
+    /// Looks up a claim by JSON pointer
+    ///
+    /// Returns `None` if there is no value at the given pointer.
+    #[must_use]
+    pub fn get(&self, pointer: &str) -> Option<&Value> {
+        self.value.pointer(pointer)
+    }
+
+    /// The full claims payload
+    #[must_use]
+    pub fn as_value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Takes ownership of the full claims payload
+    #[must_use]
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+}
+
+impl CoreClaims for DynamicClaims {
+    fn nbf(&self) -> Option<UnixTime> {
+        self.core.nbf()
+    }
+
+    fn exp(&self) -> Option<UnixTime> {
+        self.core.exp()
+    }
+
+    fn aud(&self) -> &Audiences {
+        self.core.aud()
+    }
+
+    fn iss(&self) -> Option<&IssuerRef> {
+        self.core.iss()
+    }
+
+    fn sub(&self) -> Option<&SubjectRef> {
+        self.core.sub()
+    }
+}
+
+impl<'de> Deserialize<'de> for DynamicClaims {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+
+        if !value.is_object() {
+            return Err(de::Error::custom("JWT claims must be a JSON object"));
+        }
+
+        let core = BasicClaims::deserialize(&value).map_err(de::Error::custom)?;
+
+        Ok(Self { core, value })
+    }
+}
+
+impl Serialize for DynamicClaims {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::Result;
+
+    use super::*;
+
+    #[test]
+    fn extracts_core_claims() -> Result<()> {
+        let claims: DynamicClaims = serde_json::from_str(
+            r#"{
+                "aud": ["one", "two"],
+                "sub": "subject",
+                "exp": 100,
+                "nbf": 50,
+                "custom": true
+            }"#,
+        )?;
+
+        assert_eq!(claims.aud().iter().count(), 2);
+        assert_eq!(claims.sub().unwrap().as_str(), "subject");
+        assert_eq!(claims.exp(), Some(UnixTime(100)));
+        assert_eq!(claims.nbf(), Some(UnixTime(50)));
+        assert_eq!(claims.get("/custom"), Some(&Value::Bool(true)));
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_non_object_claims() {
+        assert!(serde_json::from_str::<DynamicClaims>("[1, 2, 3]").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_core_claims() {
+        assert!(serde_json::from_str::<DynamicClaims>(r#"{"exp": "tomorrow"}"#).is_err());
+    }
+
+    #[test]
+    fn serializes_full_payload() -> Result<()> {
+        const DATA: &str = r#"{"iss":"me","tenant_id":"acme"}"#;
+        let claims: DynamicClaims = serde_json::from_str(DATA)?;
+        assert_eq!(
+            serde_json::to_value(&claims)?,
+            serde_json::from_str::<Value>(DATA)?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,440 @@
+use std::iter::FromIterator;
+
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use super::{ClaimsValidator, DynamicClaims};
+use crate::error;
+
+/// A set of rules that a token's claims must satisfy
+///
+/// Rules address claims by [JSON pointer][RFC6901] and can be loaded from
+/// configuration, allowing claim requirements to be changed without
+/// recompiling. All rules must be satisfied for the claims to be accepted.
+///
+/// [RFC6901]: https://tools.ietf.org/html/rfc6901
+///
+/// # Example
+///
+/// ```
+/// use aliri::jwt;
+///
+/// let rules: jwt::ClaimRules = serde_json::from_str(r#"[
+///     { "pointer": "/tenant_id", "equals": "acme" },
+///     { "pointer": "/org_role", "one_of": ["admin", "owner"] },
+///     { "pointer": "/email", "matches": "@acme\\.com$" },
+///     { "pointer": "/groups", "contains": "engineering" },
+///     { "pointer": "/clearance", "range": { "min": 2, "max": 5 } }
+/// ]"#).unwrap();
+///
+/// let claims: jwt::DynamicClaims = serde_json::from_str(r#"{
+///     "tenant_id": "acme",
+///     "org_role": "owner",
+///     "email": "jane@acme.com",
+///     "groups": ["engineering", "ops"],
+///     "clearance": 3
+/// }"#).unwrap();
+///
+/// assert!(rules.check(&claims).is_ok());
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+#[must_use]
+pub struct ClaimRules {
+    rules: Vec<ClaimRule>,
+}
+
+impl ClaimRules {
+    /// Constructs an empty set of rules, which accepts any claims
+    pub const fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Adds a rule to the set
+    pub fn add_rule(self, rule: ClaimRule) -> Self {
+        let mut this = self;
+        this.rules.push(rule);
+        this
+    }
+
+    /// Adds multiple rules to the set
+    pub fn extend_rules<I: IntoIterator<Item = ClaimRule>>(self, rules: I) -> Self {
+        let mut this = self;
+        this.rules.extend(rules);
+        this
+    }
+
+    /// A view of the rules in this set
+    pub fn rules(&self) -> &[ClaimRule] {
+        &self.rules
+    }
+
+    /// Checks the claims against every rule in the set
+    ///
+    /// # Errors
+    ///
+    /// Returns the violation of the first rule that is not satisfied.
+    pub fn check(&self, claims: &DynamicClaims) -> Result<(), error::ClaimRuleViolation> {
+        self.rules
+            .iter()
+            .try_for_each(|rule| rule.check(claims.as_value()))
+    }
+}
+
+impl<H> ClaimsValidator<DynamicClaims, H> for ClaimRules {
+    fn validate(&self, _header: &H, claims: &DynamicClaims) -> Result<(), error::ClaimsRejected> {
+        self.check(claims)
+            .map_err(|err| error::ClaimsRejected::Custom(Box::new(err)))
+    }
+}
+
+impl FromIterator<ClaimRule> for ClaimRules {
+    fn from_iter<I: IntoIterator<Item = ClaimRule>>(iter: I) -> Self {
+        Self {
+            rules: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// A rule that the claim at a JSON pointer must satisfy
+///
+/// A rule fails if there is no claim at the given pointer. The pointer must
+/// be empty, addressing the whole claims payload, or start with `/`, and `~`
+/// may only appear in the escapes `~0` and `~1`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[must_use]
+pub struct ClaimRule {
+    #[serde(deserialize_with = "deserialize_pointer")]
+    pointer: String,
+
+    #[serde(flatten)]
+    condition: Condition,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Condition {
+    Equals(Value),
+    OneOf(Vec<Value>),
+    Matches(#[serde(with = "serde_regex")] Regex),
+    Contains(Value),
+    Range {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+}
+
+impl ClaimRule {
+    fn new(
+        pointer: impl Into<String>,
+        condition: Condition,
+    ) -> Result<Self, error::InvalidJsonPointer> {
+        Ok(Self {
+            pointer: check_pointer(pointer.into())?,
+            condition,
+        })
+    }
+
+    /// Requires that the claim equal the given value
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pointer is not a valid JSON pointer.
+    pub fn equals(
+        pointer: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> Result<Self, error::InvalidJsonPointer> {
+        Self::new(pointer, Condition::Equals(value.into()))
+    }
+
+    /// Requires that the claim equal one of the given values
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pointer is not a valid JSON pointer.
+    pub fn one_of<I>(
+        pointer: impl Into<String>,
+        values: I,
+    ) -> Result<Self, error::InvalidJsonPointer>
+    where
+        I: IntoIterator,
+        I::Item: Into<Value>,
+    {
+        Self::new(
+            pointer,
+            Condition::OneOf(values.into_iter().map(Into::into).collect()),
+        )
+    }
+
+    /// Requires that the claim be a string matching the given regular expression
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pointer is not a valid JSON pointer.
+    pub fn matches(
+        pointer: impl Into<String>,
+        regex: Regex,
+    ) -> Result<Self, error::InvalidJsonPointer> {
+        Self::new(pointer, Condition::Matches(regex))
+    }
+
+    /// Requires that the claim contain the given value
+    ///
+    /// If the claim is an array, one of its elements must equal the value.
+    /// If the claim is a string, the value must be a substring of it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pointer is not a valid JSON pointer.
+    pub fn contains(
+        pointer: impl Into<String>,
+        value: impl Into<Value>,
+    ) -> Result<Self, error::InvalidJsonPointer> {
+        Self::new(pointer, Condition::Contains(value.into()))
+    }
+
+    /// Requires that the claim be a number within the given inclusive bounds
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pointer is not a valid JSON pointer.
+    pub fn range(
+        pointer: impl Into<String>,
+        min: Option<f64>,
+        max: Option<f64>,
+    ) -> Result<Self, error::InvalidJsonPointer> {
+        Self::new(pointer, Condition::Range { min, max })
+    }
+
+    /// The JSON pointer to the claim checked by this rule
+    #[must_use]
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// Checks the rule against a claims payload
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claim is missing or does not satisfy the rule.
+    pub fn check(&self, claims: &Value) -> Result<(), error::ClaimRuleViolation> {
+        let claim = claims
+            .pointer(&self.pointer)
+            .ok_or_else(|| error::claim_rule_violation(&self.pointer, "is missing"))?;
+
+        let (satisfied, reason) = match &self.condition {
+            Condition::Equals(expected) => (claim == expected, "does not equal the expected value"),
+            Condition::OneOf(allowed) => (
+                allowed.iter().any(|v| v == claim),
+                "is not one of the allowed values",
+            ),
+            Condition::Matches(regex) => (
+                matches!(claim.as_str(), Some(s) if regex.is_match(s)),
+                "does not match the required pattern",
+            ),
+            Condition::Contains(expected) => {
+                let found = match (claim, expected) {
+                    (Value::Array(items), _) => items.contains(expected),
+                    (Value::String(s), Value::String(sub)) => s.contains(sub.as_str()),
+                    _ => false,
+                };
+                (found, "does not contain the expected value")
+            }
+            Condition::Range { min, max } => {
+                let within = match claim.as_f64() {
+                    Some(n) => min.iter().all(|&min| n >= min) && max.iter().all(|&max| n <= max),
+                    None => false,
+                };
+                (within, "is not within the allowed range")
+            }
+        };
+
+        if satisfied {
+            Ok(())
+        } else {
+            Err(error::claim_rule_violation(&self.pointer, reason))
+        }
+    }
+}
+
+/// Checks that the pointer is an RFC 6901 JSON pointer
+fn check_pointer(pointer: String) -> Result<String, error::InvalidJsonPointer> {
+    let starts_well = pointer.is_empty() || pointer.starts_with('/');
+    let escapes_well = pointer
+        .split('~')
+        .skip(1)
+        .all(|rest| rest.starts_with('0') || rest.starts_with('1'));
+
+    if starts_well && escapes_well {
+        Ok(pointer)
+    } else {
+        Err(error::invalid_json_pointer(pointer))
+    }
+}
+
+fn deserialize_pointer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let pointer = String::deserialize(deserializer)?;
+    check_pointer(pointer).map_err(de::Error::custom)
+}
+
+mod serde_regex {
+    use regex::Regex;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        regex: &Regex,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(regex.as_str())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Regex, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::Result;
+    use serde_json::json;
+
+    use super::*;
+
+    fn claims() -> DynamicClaims {
+        serde_json::from_value(json!({
+            "tenant_id": "acme",
+            "org_role": "owner",
+            "email": "jane@acme.com",
+            "groups": ["engineering", "ops"],
+            "clearance": 3,
+            "realm_access": { "roles": ["admin"] }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn equals() {
+        assert!(ClaimRule::equals("/tenant_id", "acme")
+            .unwrap()
+            .check(claims().as_value())
+            .is_ok());
+        assert!(ClaimRule::equals("/tenant_id", "other")
+            .unwrap()
+            .check(claims().as_value())
+            .is_err());
+    }
+
+    #[test]
+    fn one_of() {
+        let rule = ClaimRule::one_of("/org_role", ["admin", "owner"]).unwrap();
+        assert!(rule.check(claims().as_value()).is_ok());
+        let rule = ClaimRule::one_of("/org_role", ["admin", "member"]).unwrap();
+        assert!(rule.check(claims().as_value()).is_err());
+    }
+
+    #[test]
+    fn matches() {
+        let rule = ClaimRule::matches("/email", Regex::new("@acme\\.com$").unwrap()).unwrap();
+        assert!(rule.check(claims().as_value()).is_ok());
+        let rule = ClaimRule::matches("/clearance", Regex::new(".*").unwrap()).unwrap();
+        assert!(rule.check(claims().as_value()).is_err());
+    }
+
+    #[test]
+    fn contains() {
+        assert!(ClaimRule::contains("/groups", "ops")
+            .unwrap()
+            .check(claims().as_value())
+            .is_ok());
+        assert!(ClaimRule::contains("/realm_access/roles", "admin")
+            .unwrap()
+            .check(claims().as_value())
+            .is_ok());
+        assert!(ClaimRule::contains("/email", "@acme")
+            .unwrap()
+            .check(claims().as_value())
+            .is_ok());
+        assert!(ClaimRule::contains("/groups", "sales")
+            .unwrap()
+            .check(claims().as_value())
+            .is_err());
+    }
+
+    #[test]
+    fn range() {
+        let rule = ClaimRule::range("/clearance", Some(2.0), Some(5.0)).unwrap();
+        assert!(rule.check(claims().as_value()).is_ok());
+        let rule = ClaimRule::range("/clearance", Some(4.0), None).unwrap();
+        assert!(rule.check(claims().as_value()).is_err());
+        let rule = ClaimRule::range("/tenant_id", None, None).unwrap();
+        assert!(rule.check(claims().as_value()).is_err());
+    }
+
+    #[test]
+    fn missing_claim_is_rejected() {
+        let err = ClaimRule::equals("/missing", "x")
+            .unwrap()
+            .check(claims().as_value())
+            .unwrap_err();
+        assert_eq!(err.pointer(), "/missing");
+        assert_eq!(err.to_string(), "claim at `/missing` is missing");
+    }
+
+    #[test]
+    fn validator_rejects_first_failing_rule() {
+        let rules = ClaimRules::new()
+            .add_rule(ClaimRule::equals("/tenant_id", "acme").unwrap())
+            .add_rule(ClaimRule::one_of("/org_role", ["admin"]).unwrap());
+        let err = rules.validate(&(), &claims()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "claim at `/org_role` is not one of the allowed values"
+        );
+    }
+
+    #[test]
+    fn loads_from_config() -> Result<()> {
+        let rules: ClaimRules = serde_json::from_value(json!([
+            { "pointer": "/tenant_id", "equals": "acme" },
+            { "pointer": "/email", "matches": "@acme\\.com$" },
+            { "pointer": "/clearance", "range": { "min": 1 } },
+        ]))?;
+
+        assert_eq!(rules.rules().len(), 3);
+        rules.check(&claims())?;
+
+        let roundtrip: ClaimRules = serde_json::from_value(serde_json::to_value(&rules)?)?;
+        assert_eq!(roundtrip.rules().len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_pointers() {
+        for pointer in ["tenant_id", "/tenant~2id", "/tenant~"] {
+            let err = ClaimRule::equals(pointer, "acme").unwrap_err();
+            assert_eq!(err.pointer(), pointer);
+
+            let result = serde_json::from_value::<ClaimRules>(json!([
+                { "pointer": pointer, "equals": "acme" },
+            ]));
+            assert!(result.is_err(), "{}", pointer);
+        }
+
+        assert!(ClaimRule::equals("", "acme").is_ok());
+        assert!(ClaimRule::equals("/a~1b/~0c", "acme").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_regex_in_config() {
+        let result = serde_json::from_value::<ClaimRules>(json!([
+            { "pointer": "/email", "matches": "(" },
+        ]));
+        assert!(result.is_err());
+    }
+}
@@ -79,6 +79,9 @@ pub mod jws;
 pub mod jwt;
 
 pub(crate) mod test;
+#[cfg(feature = "test-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
+pub mod test_util;
 
 #[doc(inline)]
 pub use jwk::Jwk;
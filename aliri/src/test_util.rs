@@ -0,0 +1,339 @@
+//! Utilities for testing code that verifies JWTs
+//!
+//! These utilities are intended for use in tests only. They generate fresh
+//! keys on construction and panic if a token cannot be produced.
+
+use aliri_clock::{Clock, System, UnixTime};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{error, jwa, jwk, jws, jwt, Jwk, Jwks, Jwt};
+
+/// An in-process token issuer for tests
+///
+/// The issuer generates its own signing key and publishes the public
+/// portion through [`jwks()`][MockIssuer::jwks()]. Together with
+/// [`validator()`][MockIssuer::validator()], this is enough to construct an
+/// `aliri_oauth2::Authority` that accepts the issuer's tokens.
+///
+/// Besides valid tokens, the issuer can mint tokens that are expired, are
+/// intended for a different audience, or carry a bad signature, which is
+/// useful for testing that these are rejected.
+///
+/// # Example
+///
+/// ```
+/// use aliri::{jwa, test_util::MockIssuer};
+///
+/// let issuer = MockIssuer::new(jwa::Algorithm::HS256).unwrap();
+/// let validator = issuer.validator();
+///
+/// let token = issuer.token();
+/// let key = issuer.jwks().get_key(jwa::Algorithm::HS256).unwrap();
+/// let claims: aliri::jwt::Validated = token.verify(key, &validator).unwrap();
+///
+/// let expired = issuer.expired_token();
+/// assert!(expired.verify::<aliri::jwt::BasicClaims, aliri::jwt::BasicHeaders, _>(key, &validator).is_err());
+/// # let _ = claims;
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct MockIssuer {
+    alg: jwa::Algorithm,
+    key: Jwk,
+    impostor: Jwk,
+    jwks: Jwks,
+    issuer: jwt::Issuer,
+    audience: jwt::Audience,
+    lifetime: u64,
+}
+
+impl MockIssuer {
+    /// Constructs an issuer that signs tokens with a newly generated key for
+    /// the given algorithm
+    ///
+    /// # Errors
+    ///
+    /// Unable to generate a key.
+    pub fn new(alg: jwa::Algorithm) -> Result<Self, error::Unexpected> {
+        let key = generate_key(alg)?;
+        let impostor = generate_key(alg)?;
+
+        let mut jwks = Jwks::default();
+        jwks.add_key(key.clone().public_only());
+
+        Ok(Self {
+            alg,
+            key,
+            impostor,
+            jwks,
+            issuer: jwt::Issuer::from_static("https://issuer.example.com/"),
+            audience: jwt::Audience::from_static("https://api.example.com/"),
+            lifetime: 300,
+        })
+    }
+
+    /// Sets the issuer placed in the `iss` claim
+    pub fn with_issuer(self, issuer: impl Into<jwt::Issuer>) -> Self {
+        Self {
+            issuer: issuer.into(),
+            ..self
+        }
+    }
+
+    /// Sets the audience placed in the `aud` claim
+    pub fn with_audience(self, audience: impl Into<jwt::Audience>) -> Self {
+        Self {
+            audience: audience.into(),
+            ..self
+        }
+    }
+
+    /// Sets the number of seconds for which minted tokens are valid
+    pub fn with_lifetime_secs(self, lifetime: u64) -> Self {
+        Self { lifetime, ..self }
+    }
+
+    /// The algorithm used to sign tokens
+    pub fn algorithm(&self) -> jwa::Algorithm {
+        self.alg
+    }
+
+    /// The issuer placed in the `iss` claim
+    #[must_use]
+    pub fn issuer(&self) -> &jwt::IssuerRef {
+        &self.issuer
+    }
+
+    /// The audience placed in the `aud` claim
+    #[must_use]
+    pub fn audience(&self) -> &jwt::AudienceRef {
+        &self.audience
+    }
+
+    /// The number of seconds for which minted tokens are valid
+    pub fn lifetime_secs(&self) -> u64 {
+        self.lifetime
+    }
+
+    /// The ID of the signing key
+    #[must_use]
+    pub fn key_id(&self) -> &jwk::KeyIdRef {
+        self.key
+            .key_id()
+            .expect("mock issuer keys always have a key ID")
+    }
+
+    /// The key used to sign tokens, including its private components
+    pub fn signing_key(&self) -> &Jwk {
+        &self.key
+    }
+
+    /// The key set containing the public portion of the signing key
+    #[must_use]
+    pub fn jwks(&self) -> &Jwks {
+        &self.jwks
+    }
+
+    /// Replaces the signing key with a newly generated key
+    ///
+    /// The public portion of the new key is added to the key set. Previous
+    /// keys remain in the key set until
+    /// [`retire_previous_keys()`][MockIssuer::retire_previous_keys()] is
+    /// called.
+    ///
+    /// # Errors
+    ///
+    /// Unable to generate a key.
+    pub fn rotate_key(&mut self) -> Result<(), error::Unexpected> {
+        self.key = generate_key(self.alg)?;
+        self.jwks.add_key(self.key.clone().public_only());
+        Ok(())
+    }
+
+    /// Removes all keys other than the current signing key from the key set
+    pub fn retire_previous_keys(&mut self) {
+        let mut jwks = Jwks::default();
+        jwks.add_key(self.key.clone().public_only());
+        self.jwks = jwks;
+    }
+
+    /// A validator that accepts valid tokens minted by this issuer
+    pub fn validator(&self) -> jwt::CoreValidator {
+        jwt::CoreValidator::default()
+            .add_approved_algorithm(self.alg)
+            .add_allowed_audience(self.audience.clone())
+            .require_issuer(self.issuer.clone())
+    }
+
+    /// The claims placed in valid tokens minted by this issuer
+    pub fn claims(&self) -> jwt::BasicClaims {
+        jwt::BasicClaims::new()
+            .with_issuer(self.issuer.clone())
+            .with_audience(self.audience.clone())
+            .with_future_expiration(self.lifetime)
+    }
+
+    /// Signs arbitrary claims with the issuer's key
+    ///
+    /// # Panics
+    ///
+    /// Panics if the claims cannot be serialized.
+    pub fn sign<C: Serialize>(&self, claims: &C) -> Jwt {
+        self.sign_with(&self.key, claims)
+    }
+
+    /// Mints a valid token
+    pub fn token(&self) -> Jwt {
+        self.sign(&self.claims())
+    }
+
+    /// Mints a valid token with additional claims
+    ///
+    /// The additional claims must be a JSON object. Its members are added to
+    /// the default claims, replacing any that share the same name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the additional claims are not a JSON object.
+    pub fn token_with(&self, extra: Value) -> Jwt {
+        let extra = match extra {
+            Value::Object(extra) => extra,
+            _ => panic!("additional claims must be a JSON object"),
+        };
+
+        let mut claims = match serde_json::to_value(self.claims()) {
+            Ok(Value::Object(claims)) => claims,
+            _ => Map::new(),
+        };
+        claims.extend(extra);
+
+        self.sign(&claims)
+    }
+
+    /// Mints a token that expired one token lifetime ago
+    pub fn expired_token(&self) -> Jwt {
+        let now = System.now();
+        let claims = self
+            .claims()
+            .with_expiration(UnixTime(now.0.saturating_sub(self.lifetime.max(1))));
+        self.sign(&claims)
+    }
+
+    /// Mints a token for a different audience
+    pub fn wrong_audience_token(&self) -> Jwt {
+        let claims = self.claims().with_audience(jwt::Audience::from_static(
+            "https://wrong-audience.example.com/",
+        ));
+        self.sign(&claims)
+    }
+
+    /// Mints a token that claims to be signed by the issuer's key, but is not
+    pub fn bad_signature_token(&self) -> Jwt {
+        self.sign_with(&self.impostor, &self.claims())
+    }
+
+    fn sign_with<C: Serialize>(&self, key: &Jwk, claims: &C) -> Jwt {
+        let headers = jwt::BasicHeaders::with_key_id(self.alg, self.key_id());
+        Jwt::try_from_parts_with_signature(&headers, claims, key)
+            .expect("mock issuer failed to sign token")
+    }
+}
+
+fn generate_key(alg: jwa::Algorithm) -> Result<Jwk, error::Unexpected> {
+    let jwa::Algorithm::Signing(signing) = alg;
+
+    let key = match signing {
+        #[cfg(feature = "rsa")]
+        jws::Algorithm::Rsa(_) => Jwk::from(jwa::Rsa::generate()?),
+
+        #[cfg(feature = "ec")]
+        jws::Algorithm::EllipticCurve(alg) => Jwk::from(jwa::EllipticCurve::generate(alg.into())?),
+
+        #[cfg(feature = "hmac")]
+        jws::Algorithm::Hmac(alg) => Jwk::from(jwa::Hmac::generate(alg)?),
+    };
+
+    let kid = jwk::KeyId::new(key.thumbprint().to_string());
+    Ok(key.with_algorithm(alg).with_key_id(kid))
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "hmac", feature = "rsa"))]
+mod tests {
+    use color_eyre::Result;
+    use serde_json::json;
+
+    use super::*;
+    use crate::jwt::{BasicHeaders, BatchVerifier, CoreHeaders, DynamicClaims, Validated};
+
+    fn verify(
+        issuer: &MockIssuer,
+        token: &Jwt,
+    ) -> Result<Validated<DynamicClaims, BasicHeaders>, error::JwksVerifyError> {
+        BatchVerifier::new(issuer.jwks(), &issuer.validator())
+            .verify(std::slice::from_ref(token))
+            .pop()
+            .unwrap()
+    }
+
+    #[test]
+    fn mints_valid_tokens() -> Result<()> {
+        let issuer = MockIssuer::new(jwa::Algorithm::HS256)?;
+        let validated = verify(&issuer, &issuer.token())?;
+        assert_eq!(
+            validated.headers().kid(),
+            Some(issuer.key_id()),
+            "token should be signed by the issuer's key"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn mints_tokens_with_additional_claims() -> Result<()> {
+        let issuer = MockIssuer::new(jwa::Algorithm::HS256)?.with_audience("my_api");
+        let token = issuer.token_with(json!({ "scope": "read write", "sub": "tester" }));
+
+        let validated = verify(&issuer, &token)?;
+        assert_eq!(validated.claims().get("/scope").unwrap(), "read write");
+        assert_eq!(validated.claims().get("/aud").unwrap(), "my_api");
+        Ok(())
+    }
+
+    #[test]
+    fn mints_rejected_tokens() -> Result<()> {
+        let issuer = MockIssuer::new(jwa::Algorithm::HS256)?;
+        assert!(verify(&issuer, &issuer.expired_token()).is_err());
+        assert!(verify(&issuer, &issuer.wrong_audience_token()).is_err());
+        assert!(verify(&issuer, &issuer.bad_signature_token()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rotates_keys() -> Result<()> {
+        let mut issuer = MockIssuer::new(jwa::Algorithm::HS256)?;
+        let old_token = issuer.token();
+        let old_kid = issuer.key_id().to_owned();
+
+        issuer.rotate_key()?;
+        assert_ne!(issuer.key_id(), &*old_kid);
+        assert_eq!(issuer.jwks().keys().len(), 2);
+        assert!(verify(&issuer, &old_token).is_ok());
+        assert!(verify(&issuer, &issuer.token()).is_ok());
+
+        issuer.retire_previous_keys();
+        assert_eq!(issuer.jwks().keys().len(), 1);
+        assert!(verify(&issuer, &old_token).is_err());
+        assert!(verify(&issuer, &issuer.token()).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn publishes_only_public_keys() -> Result<()> {
+        let issuer = MockIssuer::new(jwa::Algorithm::RS256)?;
+        let published = &issuer.jwks().keys()[0];
+        assert_eq!(published, &issuer.signing_key().clone().public_only());
+        assert_ne!(published, issuer.signing_key());
+        Ok(())
+    }
+}
@@ -1,12 +1,15 @@
 //! Warp filters for extracting JSON Web Tokens (JWTs)
 
+use std::marker::PhantomData;
+
 use actix_web::{
     dev::Payload,
     http::{header, StatusCode},
     FromRequest, HttpRequest, ResponseError,
 };
-use aliri::{jwt, JwtRef};
+use aliri::{jwt, Jwt, JwtRef};
 use aliri_oauth2::{oauth2::HasScope, Authority, AuthorityError, ScopePolicy};
+use futures::future::LocalBoxFuture;
 use serde::Deserialize;
 use thiserror::Error;
 
@@ -54,6 +57,7 @@ impl ResponseError for AuthFailed {
             Self::JwtError(err) => err.status_code(),
             Self::VerificationError(err) => match err {
                 AuthorityError::PolicyDenial(_) => StatusCode::FORBIDDEN,
+                AuthorityError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
                 _ => StatusCode::UNAUTHORIZED,
             },
             Self::MissingAuthority => StatusCode::INTERNAL_SERVER_ERROR,
@@ -80,7 +84,9 @@ fn get_jwt_from_req(request: &HttpRequest) -> Result<&JwtRef, JwtError> {
 /// to be present in the token
 ///
 /// In order to work, an `Authority` must have been established in `actix_web`. This can be done
-/// using `App::app_data()` to attach the authority for verifying tokens.
+/// using `App::app_data()` to attach the authority for verifying tokens. By default, this is
+/// expected to be an [`Authority`], but any authority implementing [`aliri_traits::Authority`]
+/// for bearer tokens can be used by naming it as the second type parameter of [`Scoped`].
 ///
 /// # Examples
 ///
@@ -142,28 +148,47 @@ pub trait ScopeGuard {
     fn scope_policy() -> &'static ScopePolicy;
 }
 
-fn extract_and_verify_jwt<T>(request: &HttpRequest) -> Result<T::Claims, AuthFailed>
+fn extract_and_verify_jwt<T, A>(
+    request: &HttpRequest,
+) -> LocalBoxFuture<'static, Result<T::Claims, AuthFailed>>
 where
     T: ScopeGuard,
+    A: for<'a> aliri_traits::Authority<
+            'a,
+            T::Claims,
+            Token = &'a JwtRef,
+            Policy = &'a ScopePolicy,
+            VerifyError = AuthorityError,
+        > + Clone
+        + 'static,
 {
-    let authority = request
-        .app_data::<Authority>()
-        .ok_or(AuthFailed::MissingAuthority)?;
+    let extracted = request
+        .app_data::<A>()
+        .ok_or(AuthFailed::MissingAuthority)
+        .and_then(|authority| {
+            let token = get_jwt_from_req(request)?;
+            Ok((authority.clone(), token.to_owned()))
+        });
 
-    let token: &JwtRef = get_jwt_from_req(request)?;
+    Box::pin(async move {
+        let (authority, token): (A, Jwt) = extracted?;
 
-    let claims: T::Claims = authority.verify_token(token, T::scope_policy())?;
+        let claims: T::Claims = authority.verify(&token, T::scope_policy()).await?;
 
-    Ok(claims)
+        Ok(claims)
+    })
 }
 
 /// Convenience wrapper which implements [`FromRequest`] for types that implement [`ScopeGuard`]
 ///
+/// The authority used to verify the token is retrieved from the application data as
+/// an `A`, which defaults to [`Authority`].
+///
 /// See the [`scope_policy!`][crate::scope_policy] macro for a more convenient way to use this type.
 #[derive(Debug)]
-pub struct Scoped<T: ScopeGuard>(T::Claims);
+pub struct Scoped<T: ScopeGuard, A = Authority>(T::Claims, PhantomData<fn() -> A>);
 
-impl<T: ScopeGuard> Scoped<T> {
+impl<T: ScopeGuard, A> Scoped<T, A> {
     /// Borrows a reference to the inner ScopesGuard value
     pub fn claims(&self) -> &T::Claims {
         &self.0
@@ -175,14 +200,23 @@ impl<T: ScopeGuard> Scoped<T> {
     }
 }
 
-impl<T> FromRequest for Scoped<T>
+impl<T, A> FromRequest for Scoped<T, A>
 where
     T: ScopeGuard,
+    A: for<'a> aliri_traits::Authority<
+            'a,
+            T::Claims,
+            Token = &'a JwtRef,
+            Policy = &'a ScopePolicy,
+            VerifyError = AuthorityError,
+        > + Clone
+        + 'static,
 {
     type Error = AuthFailed;
-    type Future = futures::future::Ready<Result<Self, Self::Error>>;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
     fn from_request(request: &HttpRequest, _: &mut Payload) -> Self::Future {
-        futures::future::ready(extract_and_verify_jwt::<T>(request).map(Scoped))
+        let verify = extract_and_verify_jwt::<T, A>(request);
+        Box::pin(async move { Ok(Scoped(verify.await?, PhantomData)) })
     }
 }
 
@@ -259,9 +293,12 @@ where
 /// }
 /// ```
 #[derive(Debug)]
-pub struct AllowAll<C = aliri_oauth2::oauth2::BasicClaimsWithScope>(C);
+pub struct AllowAll<C = aliri_oauth2::oauth2::BasicClaimsWithScope, A = Authority>(
+    C,
+    PhantomData<fn() -> A>,
+);
 
-impl<C> AllowAll<C> {
+impl<C, A> AllowAll<C, A> {
     /// Borrows a reference to the inner claims payload
     pub fn claims(&self) -> &C {
         &self.0
@@ -273,7 +310,7 @@ impl<C> AllowAll<C> {
     }
 }
 
-impl<C> ScopeGuard for AllowAll<C>
+impl<C, A> ScopeGuard for AllowAll<C, A>
 where
     C: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims + 'static,
 {
@@ -285,14 +322,23 @@ where
     }
 }
 
-impl<C> FromRequest for AllowAll<C>
+impl<C, A> FromRequest for AllowAll<C, A>
 where
     C: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims + 'static,
+    A: for<'a> aliri_traits::Authority<
+            'a,
+            C,
+            Token = &'a JwtRef,
+            Policy = &'a ScopePolicy,
+            VerifyError = AuthorityError,
+        > + Clone
+        + 'static,
 {
     type Error = AuthFailed;
-    type Future = futures::future::Ready<Result<Self, Self::Error>>;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
     fn from_request(request: &HttpRequest, _: &mut Payload) -> Self::Future {
-        futures::future::ready(extract_and_verify_jwt::<Self>(request).map(AllowAll))
+        let verify = extract_and_verify_jwt::<Self, A>(request);
+        Box::pin(async move { Ok(AllowAll(verify.await?, PhantomData)) })
     }
 }
 
@@ -307,6 +353,15 @@ mod tests {
 
     use super::*;
 
+    #[actix_rt::test]
+    async fn unavailable_authority_is_distinguished_from_rejected_token() {
+        let unavailable = AuthFailed::from(AuthorityError::Unavailable("timed out".into()));
+        assert_eq!(unavailable.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let inactive = AuthFailed::from(AuthorityError::Inactive);
+        assert_eq!(inactive.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
     #[actix_rt::test]
     async fn test_with_missing_authority() -> Result<()> {
         let app = test::init_service(App::new().service(test_endpoint)).await;
@@ -66,6 +66,20 @@
 /// );
 /// ```
 ///
+/// Namespaced scopes can be matched with wildcards, hierarchies, or URL prefixes by providing
+/// the [`ScopeMatching`][aliri_oauth2::ScopeMatching] rules to apply before the scopes:
+///
+/// ```
+/// use aliri_actix::scope_policy;
+///
+/// scope_policy!(
+///     ReadOrders / ReadOrdersScope;
+///     matching = aliri_oauth2::ScopeMatching::exact().with_wildcards();
+///     "orders:read"
+/// );
+/// # fn main() {}
+/// ```
+///
 /// These scope guards can then be used on an actix-web endpoint in order to assert that
 /// the presented JWT token is valid according to the configured authority _and_ that it
 /// has the necessary scopes.
@@ -166,10 +180,10 @@
 // produce even better documentation.
 #[macro_export]
 macro_rules! scope_policy {
-    ($i:ident/$s:ident; $($($scope:literal),*);*) => {
-      scope_policy!($i/$s(::aliri_oauth2::oauth2::BasicClaimsWithScope); $($($scope),*);*);
+    ($i:ident/$s:ident; $(matching = $matching:expr;)? $($($scope:literal),*);*) => {
+      scope_policy!($i/$s(::aliri_oauth2::oauth2::BasicClaimsWithScope); $(matching = $matching;)? $($($scope),*);*);
     };
-    ($i:ident/$s:ident($claim:ty); $($($scope:literal),*);*) => {
+    ($i:ident/$s:ident($claim:ty); $(matching = $matching:expr;)? $($($scope:literal),*);*) => {
         #[derive(Clone, Copy, Debug, PartialEq, Eq)]
         pub struct $s;
 
@@ -203,6 +217,9 @@ macro_rules! scope_policy {
                             )*
                         )
                     )*
+                    $(
+                        .with_matching($matching)
+                    )?
                 })
             }
         }
@@ -104,8 +104,12 @@
 use std::{error::Error, fmt};
 
 use aliri_oauth2::{oauth2, ScopePolicy};
-use axum_core::response::{IntoResponse, Response};
-use http::StatusCode;
+use aliri_traits::Policy;
+use axum_core::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use http::{request::Parts, StatusCode};
 
 mod macros;
 
@@ -118,6 +122,133 @@ pub trait EndpointScopePolicy {
     fn scope_policy() -> &'static ScopePolicy;
 }
 
+/// Defines a claims policy for a given endpoint guard
+///
+/// Unlike [`EndpointScopePolicy`], the policy may check any of the claims,
+/// and may depend on the request, such as requiring that a tenant claim
+/// match a tenant in the request path. Use with the [`Authorized`] extractor.
+///
+/// # Example
+///
+/// ```
+/// use aliri_axum::{Authorized, EndpointPolicy};
+/// use aliri_traits::Policy;
+/// use axum::http::request::Parts;
+///
+/// pub struct CustomClaims {
+///     tenant: String,
+///     email: String,
+/// }
+///
+/// /// Requires that the token belong to the tenant in the request path
+/// pub struct SameTenant(String);
+///
+/// impl Policy for SameTenant {
+///     type Request = CustomClaims;
+///     type Denial = &'static str;
+///
+///     fn evaluate(&self, claims: &CustomClaims) -> Result<(), Self::Denial> {
+///         if claims.tenant == self.0 {
+///             Ok(())
+///         } else {
+///             Err("token belongs to a different tenant")
+///         }
+///     }
+/// }
+///
+/// pub struct TenantMember;
+///
+/// impl EndpointPolicy for TenantMember {
+///     type Claims = CustomClaims;
+///     type Policy = SameTenant;
+///
+///     fn policy(parts: &Parts) -> SameTenant {
+///         // Matches routes such as `/tenants/:tenant/...`
+///         let tenant = parts.uri.path().split('/').nth(2).unwrap_or_default();
+///         SameTenant(tenant.to_owned())
+///     }
+/// }
+///
+/// async fn tenant_endpoint(Authorized(claims): Authorized<TenantMember>) -> String {
+///     format!("Welcome, {}", claims.email)
+/// }
+/// ```
+pub trait EndpointPolicy {
+    /// The claims structure to extract from the request extensions and return if authorized
+    type Claims: Send + Sync + 'static;
+
+    /// The policy evaluated against the claims
+    type Policy: Policy<Request = Self::Claims>;
+
+    /// The policy to be enforced for the request when this type is used as an endpoint guard
+    ///
+    /// Policies that do not depend on the request can be held in a `static`
+    /// and returned by reference.
+    fn policy(parts: &Parts) -> Self::Policy;
+}
+
+/// Ensures that a claims object is authorized by the policy of an [`EndpointPolicy`]
+///
+/// Note: This extractor will _consume_ the claims from request extensions. Place
+/// any extractors that may need to copy data from the claims before this extractor
+/// in handler definitions.
+///
+/// In the event of authorization failures, more verbose messages can be generated by adding
+/// [`VerboseAuthxErrors`] to the `extensions` of the request.
+pub struct Authorized<P: EndpointPolicy>(pub P::Claims);
+
+impl<P: EndpointPolicy> Authorized<P> {
+    /// Takes ownership of the authorized claims
+    pub fn into_claims(self) -> P::Claims {
+        self.0
+    }
+
+    /// The authorized claims
+    pub fn claims(&self) -> &P::Claims {
+        &self.0
+    }
+}
+
+impl<P> fmt::Debug for Authorized<P>
+where
+    P: EndpointPolicy,
+    P::Claims: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Authorized").field(&self.0).finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl<P, S> FromRequestParts<S> for Authorized<P>
+where
+    P: EndpointPolicy,
+    S: Sync,
+{
+    type Rejection = AuthFailed;
+
+    async fn from_request_parts(req: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let policy = P::policy(req);
+
+        let claims = req
+            .extensions
+            .remove::<P::Claims>()
+            .ok_or(AuthFailed::MissingClaims)?;
+
+        policy.evaluate(&claims).map_err(|denial| {
+            if req.extensions.get::<VerboseAuthxErrors>().is_some() {
+                AuthFailed::PolicyDenied {
+                    denial: Some(denial.to_string()),
+                }
+            } else {
+                AuthFailed::PolicyDenied { denial: None }
+            }
+        })?;
+
+        Ok(Self(claims))
+    }
+}
+
 /// An error indicating that the request could not be authorized
 #[derive(Debug, PartialEq, Eq)]
 #[non_exhaustive]
@@ -132,6 +263,12 @@ pub enum AuthFailed {
     InsufficientScopes {
         policy: Option<&'static ScopePolicy>,
     },
+
+    /// The claims included in the token were denied by an [`EndpointPolicy`]
+    ///
+    /// If a denial is specified, then the error response will include the
+    /// reason the policy denied the request.
+    PolicyDenied { denial: Option<String> },
 }
 
 impl fmt::Display for AuthFailed {
@@ -154,6 +291,10 @@ impl fmt::Display for AuthFailed {
                 }
                 f.write_char(']')
             }
+            AuthFailed::PolicyDenied { denial: None } => f.write_str("access denied"),
+            AuthFailed::PolicyDenied {
+                denial: Some(denial),
+            } => write!(f, "access denied: {denial}"),
         }
     }
 }
@@ -193,6 +334,9 @@ impl IntoResponse for AuthFailed {
 
                 (StatusCode::FORBIDDEN, message).into_response()
             }
+            AuthFailed::PolicyDenied { .. } => {
+                (StatusCode::FORBIDDEN, self.to_string()).into_response()
+            }
         }
     }
 }
@@ -239,3 +383,86 @@ pub mod __private {
         Ok(claims)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    struct Claims {
+        email: &'static str,
+    }
+
+    struct EmailDomain(&'static str);
+
+    impl Policy for EmailDomain {
+        type Request = Claims;
+        type Denial = &'static str;
+
+        fn evaluate(&self, claims: &Claims) -> Result<(), Self::Denial> {
+            if claims.email.ends_with(self.0) {
+                Ok(())
+            } else {
+                Err("email domain not allowed")
+            }
+        }
+    }
+
+    struct AcmeEmployee;
+
+    impl EndpointPolicy for AcmeEmployee {
+        type Claims = Claims;
+        type Policy = EmailDomain;
+
+        fn policy(_: &Parts) -> Self::Policy {
+            EmailDomain("@acme.com")
+        }
+    }
+
+    fn request_with_email(email: &'static str) -> Parts {
+        let mut parts = Request::new(()).into_parts().0;
+        parts.extensions.insert(Claims { email });
+        parts
+    }
+
+    #[tokio::test]
+    async fn authorized_with_claims_satisfying_policy() {
+        let Authorized(claims) = Authorized::<AcmeEmployee>::from_request_parts(
+            &mut request_with_email("jo@acme.com"),
+            &(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(claims.email, "jo@acme.com");
+    }
+
+    #[tokio::test]
+    async fn authorized_without_claims_returns_error() {
+        let mut parts = Request::new(()).into_parts().0;
+        match Authorized::<AcmeEmployee>::from_request_parts(&mut parts, &()).await {
+            Err(AuthFailed::MissingClaims) => {}
+            Err(_) => panic!("Expected missing claims error"),
+            Ok(_) => panic!("Expected AuthFailed"),
+        }
+    }
+
+    #[tokio::test]
+    async fn authorized_with_denied_claims_returns_error() {
+        let mut parts = request_with_email("jo@globex.com");
+        match Authorized::<AcmeEmployee>::from_request_parts(&mut parts, &()).await {
+            Err(AuthFailed::PolicyDenied { denial: None }) => {}
+            Err(_) => panic!("Expected terse policy denied error"),
+            Ok(_) => panic!("Expected AuthFailed"),
+        }
+
+        let mut parts = request_with_email("jo@globex.com");
+        parts.extensions.insert(VerboseAuthxErrors);
+        let err = Authorized::<AcmeEmployee>::from_request_parts(&mut parts, &())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "access denied: email domain not allowed");
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+    }
+}
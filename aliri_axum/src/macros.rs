@@ -49,6 +49,20 @@
 /// );
 /// ```
 ///
+/// Namespaced scopes can be matched with wildcards, hierarchies, or URL prefixes by providing
+/// the [`ScopeMatching`][aliri_oauth2::ScopeMatching] rules to apply before the scopes:
+///
+/// ```
+/// use aliri_axum::scope_guard;
+/// use aliri_oauth2::ScopeMatching;
+///
+/// scope_guard!(
+///     ReadOrders;
+///     matching = ScopeMatching::exact().with_wildcards().with_hierarchy(':');
+///     "orders:read"
+/// );
+/// ```
+///
 /// These scope guards can then be used on an axum handler endpoint in order to assert that
 /// the presented JWT token is valid according to the configured authority _and_ that it
 /// has the necessary scopes.
@@ -129,19 +143,19 @@
 // produce even better documentation.
 #[macro_export]
 macro_rules! scope_guard {
-    ($vis:vis $i:ident; *) => {
+    ($vis:vis $i:ident; $(matching = $matching:expr;)? *) => {
         $crate::scope_guard!($vis $i(::aliri_oauth2::oauth2::BasicClaimsWithScope); *);
     };
-    ($vis:vis $i:ident; $scope:literal) => {
-        $crate::scope_guard!($vis $i; [$scope]);
+    ($vis:vis $i:ident; $(matching = $matching:expr;)? $scope:literal) => {
+        $crate::scope_guard!($vis $i; $(matching = $matching;)? [$scope]);
     };
-    ($vis:vis $i:ident; [$($scope:literal)||* $(,)?]) => {
-        $crate::scope_guard!($vis $i(::aliri_oauth2::oauth2::BasicClaimsWithScope); [$($scope)||*]);
+    ($vis:vis $i:ident; $(matching = $matching:expr;)? [$($scope:literal)||* $(,)?]) => {
+        $crate::scope_guard!($vis $i(::aliri_oauth2::oauth2::BasicClaimsWithScope); $(matching = $matching;)? [$($scope)||*]);
     };
-    ($vis:vis $i:ident($claim:ty); $scope:literal) => {
-        $crate::scope_guard!($vis $i($claim); [$scope]);
+    ($vis:vis $i:ident($claim:ty); $(matching = $matching:expr;)? $scope:literal) => {
+        $crate::scope_guard!($vis $i($claim); $(matching = $matching;)? [$scope]);
     };
-    ($vis:vis $i:ident($claim:ty); *) => {
+    ($vis:vis $i:ident($claim:ty); $(matching = $matching:expr;)? *) => {
         /// A scope guard that allows any request, extracting and returning the claims
         ///
         /// Note: This extractor will _consume_ the claims from request extensions. Place
@@ -187,7 +201,7 @@ macro_rules! scope_guard {
             }
         }
     };
-    ($vis:vis $i:ident($claim:ty); [$($scope:literal)||* $(,)?]) => {
+    ($vis:vis $i:ident($claim:ty); $(matching = $matching:expr;)? [$($scope:literal)||* $(,)?]) => {
         /// Ensures that a claims object authorizes access to a given scope
         ///
         /// Note: This extractor will _consume_ the claims from request extensions. Place
@@ -228,6 +242,9 @@ macro_rules! scope_guard {
                     $(
                         .or_allow($scope.parse().unwrap())
                     )*
+                    $(
+                        .with_matching($matching)
+                    )?
                 })
             }
         }
@@ -271,6 +288,21 @@ macro_rules! scope_guard {
 /// The above will define a scope guard type for each of the scopes, similar to the [`scope_guard!`]
 /// macro.
 ///
+/// The [`ScopeMatching`][aliri_oauth2::ScopeMatching] rules for all of the scope guards can be set
+/// with a `matching = <...>` declaration.
+///
+/// ```
+/// use aliri_axum::scope_guards;
+/// use aliri_oauth2::ScopeMatching;
+///
+/// scope_guards! {
+///     matching = ScopeMatching::exact().with_wildcards();
+///
+///     scope ReadOrders = "orders:read";
+///     scope WriteOrders = "orders:write";
+/// }
+/// ```
+///
 /// Using a custom claims type can be done with a `type Claims = <...>` declaration.
 ///
 /// ```
@@ -310,6 +342,16 @@ macro_rules! scope_guards {
             $crate::scope_guard!($vis $i($claims); $scope);
         )*
     };
+    (matching = $matching:expr; $($vis:vis scope $i:ident = $scope:tt);* $(;)?) => {
+        $(
+            $crate::scope_guard!($vis $i; matching = $matching; $scope);
+        )*
+    };
+    (type Claims = $claims:ty; matching = $matching:expr; $($vis:vis scope $i:ident = $scope:tt);* $(;)?) => {
+        $(
+            $crate::scope_guard!($vis $i($claims); matching = $matching; $scope);
+        )*
+    };
 }
 
 #[cfg(test)]
@@ -332,6 +374,36 @@ mod tests {
         scope TestingAdmin = ["testing admin"];
     }
 
+    scope_guards! {
+        type Claims = MyClaims;
+        matching = aliri_oauth2::ScopeMatching::exact().with_wildcards();
+
+        scope ReadOrders = "orders:read";
+        scope AnyOrders = *;
+    }
+
+    scope_guard!(
+        ReadOrdersNested(MyClaims);
+        matching = aliri_oauth2::ScopeMatching::exact().with_hierarchy(':');
+        "orders:read"
+    );
+
+    struct Auth0;
+
+    impl oauth2::ScopeSource for Auth0 {
+        fn scope_claims() -> &'static oauth2::ScopeClaims {
+            static CLAIMS: once_cell::sync::Lazy<oauth2::ScopeClaims> =
+                once_cell::sync::Lazy::new(|| {
+                    oauth2::ScopeClaims::new()
+                        .with_claim("scope")
+                        .with_claim("permissions")
+                });
+            &CLAIMS
+        }
+    }
+
+    scope_guard!(ReadOrdersPermission(oauth2::ClaimsWithScopeFrom<Auth0>); "orders:read");
+
     struct MyClaims(oauth2::Scope);
 
     impl oauth2::HasScope for MyClaims {
@@ -374,7 +446,7 @@ mod tests {
     async fn admin_only_scope_guard_without_claims_returns_error() {
         match AdminOnly::from_request_parts(&mut request_with_no_claims(), &()).await {
             Err(AuthFailed::MissingClaims) => {}
-            Err(AuthFailed::InsufficientScopes { .. }) => panic!("Expected missing claims error"),
+            Err(_) => panic!("Expected missing claims error"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -397,7 +469,7 @@ mod tests {
     async fn admin_only_scope_guard_with_no_scope_claims() {
         match AdminOnly::from_request_parts(&mut request_with_no_scope(), &()).await {
             Err(AuthFailed::InsufficientScopes { .. }) => {}
-            Err(AuthFailed::MissingClaims) => panic!("Expected insufficient scopes error"),
+            Err(_) => panic!("Expected insufficient scopes error"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -427,7 +499,7 @@ mod tests {
     async fn testing_scope_guard_with_admin_scope_claims() {
         match Testing::from_request_parts(&mut request_with_admin_scope(), &()).await {
             Err(AuthFailed::InsufficientScopes { .. }) => {}
-            Err(AuthFailed::MissingClaims) => panic!("Expected insufficient scopes error"),
+            Err(_) => panic!("Expected insufficient scopes error"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -436,7 +508,7 @@ mod tests {
     async fn testing_admin_scope_guard_with_testing_scope_claims() {
         match TestingAdmin::from_request_parts(&mut request_with_testing_scope(), &()).await {
             Err(AuthFailed::InsufficientScopes { .. }) => {}
-            Err(AuthFailed::MissingClaims) => panic!("Expected insufficient scopes error"),
+            Err(_) => panic!("Expected insufficient scopes error"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -445,11 +517,49 @@ mod tests {
     async fn testing_admin_scope_guard_with_admin_scope_claims() {
         match TestingAdmin::from_request_parts(&mut request_with_admin_scope(), &()).await {
             Err(AuthFailed::InsufficientScopes { .. }) => {}
-            Err(AuthFailed::MissingClaims) => panic!("Expected insufficient scopes error"),
+            Err(_) => panic!("Expected insufficient scopes error"),
+            Ok(_) => panic!("Expected AuthFailed"),
+        }
+    }
+
+    #[tokio::test]
+    async fn read_orders_scope_guard_with_wildcard_scope_claims() {
+        ReadOrders::from_request_parts(&mut request_with_scope(scope!["orders:*"]), &())
+            .await
+            .unwrap();
+
+        match ReadOrders::from_request_parts(&mut request_with_scope(scope!["users:*"]), &()).await
+        {
+            Err(AuthFailed::InsufficientScopes { .. }) => {}
+            Err(_) => panic!("Expected insufficient scopes error"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
 
+    #[tokio::test]
+    async fn read_orders_nested_scope_guard_with_parent_scope_claims() {
+        ReadOrdersNested::from_request_parts(&mut request_with_scope(scope!["orders"]), &())
+            .await
+            .unwrap();
+        AnyOrders::from_request_parts(&mut request_with_scope(scope![]), &())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_orders_permission_scope_guard_with_permissions_claim() {
+        let claims: oauth2::ClaimsWithScopeFrom<Auth0> = serde_json::from_value(
+            serde_json::json!({ "scope": "profile", "permissions": ["orders:read"] }),
+        )
+        .unwrap();
+        let mut parts = Request::new(()).into_parts().0;
+        parts.extensions.insert(claims);
+
+        ReadOrdersPermission::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn testing_admin_scope_guard_with_admin_and_testing_scope_claims() {
         TestingAdmin::from_request_parts(&mut request_with_admin_and_testing_scope(), &())
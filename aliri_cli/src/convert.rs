@@ -0,0 +1,44 @@
+use aliri::{jwa, jwk};
+use clap::Args;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+
+use crate::{
+    input,
+    keys::{self, with_metadata},
+};
+
+#[derive(Debug, Args)]
+pub struct Opts {
+    /// A file containing a PEM-encoded RSA or ECC key, or `-` to read it from standard input
+    pem: String,
+
+    /// The algorithm the key will be used with
+    #[arg(short, long = "alg", value_parser = input::parse_algorithm)]
+    algorithm: Option<jwa::Algorithm>,
+
+    /// The key ID to assign to the key; defaults to the key's thumbprint
+    #[arg(long)]
+    kid: Option<jwk::KeyId>,
+
+    /// Only output the public portion of the key
+    #[arg(long)]
+    public: bool,
+}
+
+impl Opts {
+    pub fn run(self) -> Result<String> {
+        let jwk = keys::jwk_from_pem(&input::read_file(&self.pem)?)?;
+
+        if let Some(alg) = self.algorithm {
+            if !jwk.is_compatible(alg) {
+                bail!("key is not compatible with algorithm {}", alg);
+            }
+        }
+
+        let jwk = with_metadata(jwk, self.algorithm, self.kid, self.public);
+        serde_json::to_string_pretty(&jwk).wrap_err("unable to serialize JWK")
+    }
+}
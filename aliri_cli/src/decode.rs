@@ -0,0 +1,66 @@
+use aliri::{jwt, JwtRef};
+use aliri_base64::Base64Url;
+use clap::Args;
+use color_eyre::{eyre::WrapErr, Result};
+use serde_json::{json, Value};
+
+use crate::input;
+
+#[derive(Debug, Args)]
+pub struct Opts {
+    /// The token to decode, or `-` to read it from standard input
+    token: String,
+}
+
+impl Opts {
+    pub fn run(self) -> Result<String> {
+        let token = input::read_token(&self.token)?;
+        let decoded = decode(JwtRef::from_str(&token))?;
+        Ok(serde_json::to_string_pretty(&decoded)?)
+    }
+}
+
+/// Decodes the header and claims of a token without verifying its signature
+pub fn decode(token: &JwtRef) -> Result<Value> {
+    let decomposed: jwt::Decomposed<Value> = token.decompose().wrap_err("malformed JWT")?;
+
+    let payload = Base64Url::from_encoded(decomposed.untrusted_payload())
+        .wrap_err("malformed JWT payload")?;
+    let claims: Value =
+        serde_json::from_slice(payload.as_slice()).wrap_err("malformed JWT payload")?;
+
+    Ok(json!({
+        "header": decomposed.untrusted_header(),
+        "claims": claims,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_header_and_claims() -> Result<()> {
+        let token = JwtRef::from_str(concat!(
+            "eyJhbGciOiJIUzI1NiIsImtpZCI6InRlc3Qga2V5In0.",
+            "eyJzdWIiOiJBbGlyaSIsImF1ZCI6Im15X2FwaSIsImlzcyI6ImF1dGhvcml0eSJ9.",
+            "yKDd4Ba3fdedqRKHrSUUMuF01-ctdXzEKM9oyWjSx9A"
+        ));
+
+        let decoded = decode(token)?;
+
+        assert_eq!(
+            decoded,
+            json!({
+                "header": { "alg": "HS256", "kid": "test key" },
+                "claims": { "sub": "Aliri", "aud": "my_api", "iss": "authority" },
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_malformed_token() {
+        assert!(decode(JwtRef::from_str("not-a-jwt")).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use aliri::{jwa, jwk, jws, Jwk};
+use clap::{Args, ValueEnum};
+use color_eyre::{eyre::bail, Result};
+
+use crate::{input, keys::with_metadata};
+
+#[derive(Debug, Args)]
+pub struct Opts {
+    /// The algorithm the key will be used with, such as `RS256`, `ES256`, or `HS256`
+    #[arg(value_parser = input::parse_algorithm)]
+    algorithm: jwa::Algorithm,
+
+    /// The key ID to assign to the key; defaults to the key's thumbprint
+    #[arg(long)]
+    kid: Option<jwk::KeyId>,
+
+    /// The output format
+    #[arg(short, long, value_enum, default_value_t = Format::Jwk)]
+    format: Format,
+
+    /// Only output the public portion of the key
+    #[arg(long)]
+    public: bool,
+}
+
+/// The format in which to output a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// JSON Web Key
+    Jwk,
+
+    /// PEM-encoded PKCS#1 or PKCS#8
+    Pem,
+}
+
+impl Opts {
+    pub fn run(self) -> Result<String> {
+        let key = generate(self.algorithm)?;
+
+        match self.format {
+            Format::Jwk => {
+                let jwk =
+                    with_metadata(Jwk::from(key), Some(self.algorithm), self.kid, self.public);
+                Ok(serde_json::to_string_pretty(&jwk)?)
+            }
+            Format::Pem => key.to_pem(self.public),
+        }
+    }
+}
+
+/// A freshly generated key
+#[derive(Debug)]
+pub enum GeneratedKey {
+    Rsa(jwa::rsa::PrivateKey),
+    EllipticCurve(jwa::ec::PrivateKey),
+    Hmac(jwa::Hmac),
+}
+
+impl GeneratedKey {
+    fn to_pem(&self, public: bool) -> Result<String> {
+        let pem = match self {
+            Self::Rsa(k) if public => k.public_key().to_pem()?,
+            Self::Rsa(k) => k.to_pem(),
+            Self::EllipticCurve(k) if public => k.public_key().to_pem(),
+            Self::EllipticCurve(k) => k.to_pem()?,
+            Self::Hmac(_) => bail!("HMAC keys cannot be exported as PEM"),
+        };
+
+        Ok(pem)
+    }
+}
+
+impl From<GeneratedKey> for Jwk {
+    fn from(key: GeneratedKey) -> Self {
+        match key {
+            GeneratedKey::Rsa(k) => Jwk::from(k),
+            GeneratedKey::EllipticCurve(k) => Jwk::from(k),
+            GeneratedKey::Hmac(k) => Jwk::from(k),
+        }
+    }
+}
+
+/// Generates a new key suitable for the given algorithm
+pub fn generate(alg: jwa::Algorithm) -> Result<GeneratedKey> {
+    let key = match alg {
+        jwa::Algorithm::Signing(jws::Algorithm::Rsa(_)) => {
+            GeneratedKey::Rsa(jwa::rsa::PrivateKey::generate()?)
+        }
+        jwa::Algorithm::Signing(jws::Algorithm::EllipticCurve(alg)) => {
+            GeneratedKey::EllipticCurve(jwa::ec::PrivateKey::generate(alg.into())?)
+        }
+        jwa::Algorithm::Signing(jws::Algorithm::Hmac(alg)) => {
+            GeneratedKey::Hmac(jwa::Hmac::generate(alg)?)
+        }
+        _ => bail!("unable to generate keys for algorithm {}", alg),
+    };
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_keys_for_each_family() -> Result<()> {
+        for alg in [
+            jwa::Algorithm::RS256,
+            jwa::Algorithm::ES384,
+            jwa::Algorithm::HS512,
+        ] {
+            let jwk = with_metadata(Jwk::from(generate(alg)?), Some(alg), None, false);
+            assert_eq!(jwk.algorithm(), Some(alg));
+            assert_eq!(jwk.key_id().unwrap().as_str(), jwk.thumbprint().to_string());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn public_keys_keep_the_thumbprint_of_the_private_key() -> Result<()> {
+        let jwk = Jwk::from(generate(jwa::Algorithm::ES256)?);
+        let private = with_metadata(jwk.clone(), None, None, false);
+        let public = with_metadata(jwk, None, None, true);
+        assert_eq!(private.key_id(), public.key_id());
+        assert_ne!(private, public);
+        Ok(())
+    }
+
+    #[test]
+    fn hmac_keys_cannot_be_pem_encoded() -> Result<()> {
+        let key = generate(jwa::Algorithm::HS256)?;
+        assert!(key.to_pem(false).is_err());
+        assert!(generate(jwa::Algorithm::RS256)?.to_pem(true).is_ok());
+        Ok(())
+    }
+}
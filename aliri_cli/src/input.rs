@@ -0,0 +1,35 @@
+use std::{fs, io::Read};
+
+use aliri::jwa;
+use color_eyre::{eyre::WrapErr, Result};
+
+/// The argument value used to read from standard input
+pub const STDIN: &str = "-";
+
+/// Reads the contents of a file, or standard input if the path is `-`
+pub fn read_file(path: &str) -> Result<String> {
+    if path == STDIN {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .wrap_err("unable to read from standard input")?;
+        Ok(buf)
+    } else {
+        fs::read_to_string(path).wrap_err_with(|| format!("unable to read `{}`", path))
+    }
+}
+
+/// Reads a token given on the command line, or from standard input if it is `-`
+pub fn read_token(token: &str) -> Result<String> {
+    if token == STDIN {
+        Ok(read_file(STDIN)?.trim().to_owned())
+    } else {
+        Ok(token.to_owned())
+    }
+}
+
+/// Parses an algorithm name, such as `RS256`
+pub fn parse_algorithm(alg: &str) -> Result<jwa::Algorithm, String> {
+    serde_json::from_value(serde_json::Value::String(alg.to_owned()))
+        .map_err(|_| format!("unsupported algorithm `{}`", alg))
+}
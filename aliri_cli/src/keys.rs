@@ -0,0 +1,162 @@
+use aliri::{jwa, jwk, Jwk, Jwks};
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+
+use crate::input;
+
+const PEM_PREFIX: &str = "-----BEGIN";
+
+/// A single key or a key set, as read from a file
+#[derive(Debug)]
+pub enum Keys {
+    Single(Jwk),
+    Set(Jwks),
+}
+
+impl Keys {
+    /// Parses a JWK, JWKS, or PEM-encoded key
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.starts_with(PEM_PREFIX) {
+            return Ok(Self::Single(jwk_from_pem(text)?));
+        }
+
+        let value: serde_json::Value =
+            serde_json::from_str(text).wrap_err("expected a JWK, JWKS, or PEM-encoded key")?;
+
+        if value.get("keys").is_some() {
+            Ok(Self::Set(
+                serde_json::from_str(text).wrap_err("invalid JWKS")?,
+            ))
+        } else {
+            Ok(Self::Single(
+                serde_json::from_str(text).wrap_err("invalid JWK")?,
+            ))
+        }
+    }
+
+    /// Reads a JWK, JWKS, or PEM-encoded key from a file or standard input
+    pub fn read(path: &str) -> Result<Self> {
+        Self::parse(&input::read_file(path)?)
+    }
+
+    /// Reads a JWKS from a URL, or from a file or standard input
+    ///
+    /// A single key is treated as a key set containing only that key.
+    pub fn read_set(source: &str) -> Result<Jwks> {
+        let text = if source.starts_with("https://") || source.starts_with("http://") {
+            reqwest::blocking::get(source)
+                .and_then(|resp| resp.error_for_status())
+                .and_then(|resp| resp.text())
+                .wrap_err_with(|| format!("unable to fetch JWKS from `{}`", source))?
+        } else {
+            input::read_file(source)?
+        };
+
+        match Self::parse(&text)? {
+            Self::Set(jwks) => Ok(jwks),
+            Self::Single(jwk) => {
+                let mut jwks = Jwks::default();
+                jwks.add_key(jwk);
+                Ok(jwks)
+            }
+        }
+    }
+
+    /// Reads a single key from a file or standard input
+    pub fn read_single(path: &str) -> Result<Jwk> {
+        match Self::read(path)? {
+            Self::Single(jwk) => Ok(jwk),
+            Self::Set(_) => bail!("expected a single key, but found a key set"),
+        }
+    }
+}
+
+/// Converts a PEM-encoded RSA or ECC key, public or private, into a JWK
+pub fn jwk_from_pem(pem: &str) -> Result<Jwk> {
+    if let Ok(key) = jwa::rsa::PrivateKey::from_pem(pem) {
+        return Ok(Jwk::from(key));
+    }
+
+    if let Ok(key) = jwa::ec::PrivateKey::from_pem(pem) {
+        return Ok(Jwk::from(key));
+    }
+
+    if let Ok(key) = jwa::rsa::PublicKey::from_pem(pem) {
+        return Ok(Jwk::from(key));
+    }
+
+    if let Ok(key) = jwa::ec::PublicKey::from_pem(pem) {
+        return Ok(Jwk::from(key));
+    }
+
+    bail!("PEM does not contain a supported RSA or ECC key")
+}
+
+/// Assigns the algorithm and key ID to a key, defaulting the key ID to the key's thumbprint
+pub fn with_metadata(
+    jwk: Jwk,
+    alg: Option<jwa::Algorithm>,
+    kid: Option<jwk::KeyId>,
+    public: bool,
+) -> Jwk {
+    let jwk = if public { jwk.public_only() } else { jwk };
+    let jwk = match alg {
+        Some(alg) => jwk.with_algorithm(alg),
+        None => jwk,
+    };
+
+    let kid = kid.unwrap_or_else(|| jwk::KeyId::new(jwk.thumbprint().to_string()));
+    jwk.with_key_id(kid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_generated_pems() -> Result<()> {
+        let rsa = jwa::rsa::PrivateKey::generate()?;
+        let jwk = jwk_from_pem(&rsa.to_pem())?;
+        assert_eq!(jwk, Jwk::from(rsa.clone()));
+
+        let jwk = jwk_from_pem(&rsa.public_key().to_pem()?)?;
+        assert_eq!(jwk, Jwk::from(rsa.into_public_key()));
+
+        let ec = jwa::ec::PrivateKey::generate(jwa::ec::Curve::P256)?;
+        let jwk = jwk_from_pem(&ec.to_pem()?)?;
+        assert_eq!(jwk.thumbprint(), Jwk::from(ec.clone()).thumbprint());
+
+        let jwk = jwk_from_pem(&ec.public_key().to_pem())?;
+        assert_eq!(jwk, Jwk::from(ec.into_public_key()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_garbage_pem() {
+        assert!(
+            jwk_from_pem("-----BEGIN PUBLIC KEY-----\nAAAA\n-----END PUBLIC KEY-----").is_err()
+        );
+    }
+
+    #[test]
+    fn parses_single_keys_and_key_sets() -> Result<()> {
+        let jwk = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?);
+        let mut jwks = Jwks::default();
+        jwks.add_key(jwk.clone());
+
+        assert!(matches!(
+            Keys::parse(&serde_json::to_string(&jwk)?)?,
+            Keys::Single(_)
+        ));
+        assert!(matches!(
+            Keys::parse(&serde_json::to_string(&jwks)?)?,
+            Keys::Set(_)
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//! A command-line tool for working with JWTs and JWKs
+//!
+//! The `aliri` binary uses the same parsing and validation code as the
+//! `aliri` crate, making it suitable for inspecting and verifying tokens
+//! offline, without pasting them into third-party websites.
+
+#![warn(
+    missing_docs,
+    unused_import_braces,
+    unused_imports,
+    unused_qualifications
+)]
+#![deny(
+    missing_debug_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unused_must_use
+)]
+
+use clap::{Parser, Subcommand};
+
+mod convert;
+mod decode;
+mod generate;
+mod input;
+mod keys;
+mod sign;
+mod thumbprint;
+mod verify;
+
+/// Decode, verify, and sign JWTs and manage JWKs
+#[derive(Debug, Parser)]
+#[command(name = "aliri", version)]
+struct Opts {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Decodes a token and shows its header and claims without verifying it
+    Decode(decode::Opts),
+
+    /// Verifies a token against a JWKS
+    Verify(verify::Opts),
+
+    /// Signs a set of claims, producing a token
+    Sign(sign::Opts),
+
+    /// Generates a new key
+    Generate(generate::Opts),
+
+    /// Converts a PEM-encoded key into a JWK
+    PemToJwk(convert::Opts),
+
+    /// Computes the RFC7638 thumbprints of keys
+    Thumbprint(thumbprint::Opts),
+}
+
+fn main() -> color_eyre::Result<()> {
+    color_eyre::install()?;
+
+    let opts = Opts::parse();
+
+    let output = match opts.command {
+        Command::Decode(opts) => opts.run()?,
+        Command::Verify(opts) => opts.run()?,
+        Command::Sign(opts) => opts.run()?,
+        Command::Generate(opts) => opts.run()?,
+        Command::PemToJwk(opts) => opts.run()?,
+        Command::Thumbprint(opts) => opts.run()?,
+    };
+
+    println!("{}", output);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn command_line_is_well_formed() {
+        Opts::command().debug_assert();
+    }
+}
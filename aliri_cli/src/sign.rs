@@ -0,0 +1,159 @@
+use aliri::{jwa, jwk, jwt, Jwk, Jwt};
+use aliri_clock::{Clock, System};
+use clap::Args;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use serde_json::{Map, Value};
+
+use crate::{input, keys::Keys};
+
+#[derive(Debug, Args)]
+pub struct Opts {
+    /// A file containing the claims as a JSON object, or `-` to read them from standard input
+    claims: String,
+
+    /// The private key to sign with, as a JWK or PEM file
+    #[arg(short, long)]
+    key: String,
+
+    /// The signing algorithm, if the key does not specify one
+    #[arg(short, long = "alg", value_parser = input::parse_algorithm)]
+    algorithm: Option<jwa::Algorithm>,
+
+    /// The key ID to place in the header, if different from the key's ID
+    #[arg(long)]
+    kid: Option<jwk::KeyId>,
+
+    /// Sets `iat` to the current time and `exp` to this many seconds in the future
+    #[arg(short, long, value_name = "SECONDS")]
+    expires_in: Option<u64>,
+}
+
+impl Opts {
+    pub fn run(self) -> Result<String> {
+        let claims: Map<String, Value> = serde_json::from_str(&input::read_file(&self.claims)?)
+            .wrap_err("claims must be a JSON object")?;
+        let key = Keys::read_single(&self.key)?;
+
+        let token = sign(
+            claims,
+            &key,
+            self.algorithm,
+            self.kid,
+            self.expires_in,
+            &System,
+        )?;
+        Ok(token.take())
+    }
+}
+
+/// Signs the claims with the given key
+pub fn sign<C: Clock>(
+    mut claims: Map<String, Value>,
+    key: &Jwk,
+    alg: Option<jwa::Algorithm>,
+    kid: Option<jwk::KeyId>,
+    expires_in: Option<u64>,
+    clock: &C,
+) -> Result<Jwt> {
+    let alg = match (alg, key.algorithm()) {
+        (Some(alg), Some(key_alg)) if alg != key_alg => bail!(
+            "requested algorithm {} does not match the key's algorithm {}",
+            alg,
+            key_alg
+        ),
+        (Some(alg), _) | (None, Some(alg)) => alg,
+        (None, None) => bail!("the key does not specify an algorithm; use `--alg` to choose one"),
+    };
+
+    let kid = kid.or_else(|| key.key_id().map(ToOwned::to_owned));
+    let headers = match kid {
+        Some(kid) => jwt::BasicHeaders::with_key_id(alg, kid),
+        None => jwt::BasicHeaders::new(alg),
+    };
+
+    if let Some(secs) = expires_in {
+        let now = clock.now();
+        claims.insert("iat".into(), now.0.into());
+        claims.insert("exp".into(), (now.0 + secs).into());
+    }
+
+    Jwt::try_from_parts_with_signature(&headers, &claims, key).wrap_err("unable to sign claims")
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri_clock::{TestClock, UnixTime};
+    use serde_json::json;
+
+    use super::*;
+
+    fn claims() -> Map<String, Value> {
+        match json!({ "sub": "me", "custom": true }) {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn signs_with_key_algorithm_and_id() -> Result<()> {
+        let key = Jwk::from(jwa::rsa::PrivateKey::generate()?)
+            .with_algorithm(jwa::Algorithm::PS256)
+            .with_key_id(jwk::KeyId::from_static("signer"));
+
+        let clock = TestClock::new(UnixTime(1000));
+        let token = sign(claims(), &key, None, None, Some(60), &clock)?;
+
+        let decoded = crate::decode::decode(&token)?;
+        assert_eq!(
+            decoded["header"],
+            json!({ "alg": "PS256", "kid": "signer" })
+        );
+        assert_eq!(
+            decoded["claims"],
+            json!({ "sub": "me", "custom": true, "iat": 1000, "exp": 1060 })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn requires_an_algorithm() -> Result<()> {
+        let key = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?);
+
+        assert!(sign(claims(), &key, None, None, None, &System).is_err());
+
+        let token = sign(
+            claims(),
+            &key,
+            Some(jwa::Algorithm::HS256),
+            None,
+            None,
+            &System,
+        )?;
+        let decomposed: jwt::Decomposed = token.decompose()?;
+        assert_eq!(
+            decomposed.untrusted_header(),
+            &jwt::BasicHeaders::new(jwa::Algorithm::HS256)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_mismatched_algorithm() -> Result<()> {
+        let key = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?)
+            .with_algorithm(jwa::Algorithm::HS256);
+
+        assert!(sign(
+            claims(),
+            &key,
+            Some(jwa::Algorithm::HS512),
+            None,
+            None,
+            &System
+        )
+        .is_err());
+        Ok(())
+    }
+}
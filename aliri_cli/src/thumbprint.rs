@@ -0,0 +1,58 @@
+use clap::Args;
+use color_eyre::Result;
+
+use crate::keys::Keys;
+
+#[derive(Debug, Args)]
+pub struct Opts {
+    /// A file containing a JWK, JWKS, or PEM-encoded key, or `-` to read it from standard input
+    keys: String,
+}
+
+impl Opts {
+    pub fn run(self) -> Result<String> {
+        Ok(thumbprints(&Keys::read(&self.keys)?))
+    }
+}
+
+/// Lists the thumbprint of each key
+///
+/// For key sets, each thumbprint is followed by the ID of the key, if it has one.
+pub fn thumbprints(keys: &Keys) -> String {
+    match keys {
+        Keys::Single(jwk) => jwk.thumbprint().to_string(),
+        Keys::Set(jwks) => jwks
+            .keys()
+            .iter()
+            .map(|jwk| match jwk.key_id() {
+                Some(kid) => format!("{}\t{}", jwk.thumbprint(), kid),
+                None => jwk.thumbprint().to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri::{jwa, jwk, Jwk, Jwks};
+
+    use super::*;
+
+    #[test]
+    fn lists_thumbprints_of_key_set() -> Result<()> {
+        let a = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?)
+            .with_key_id(jwk::KeyId::from_static("a"));
+        let b = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?);
+
+        let mut jwks = Jwks::default();
+        jwks.add_key(a.clone());
+        jwks.add_key(b.clone());
+
+        assert_eq!(
+            thumbprints(&Keys::Set(jwks)),
+            format!("{}\ta\n{}", a.thumbprint(), b.thumbprint())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use aliri::{
+    jwa,
+    jwt::{self, CoreHeaders, HasAlgorithm},
+    Jwks, JwtRef,
+};
+use clap::Args;
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::{input, keys::Keys};
+
+#[derive(Debug, Args)]
+pub struct Opts {
+    /// The token to verify, or `-` to read it from standard input
+    token: String,
+
+    /// A JWKS file or `http(s)` URL containing the keys to verify against
+    #[arg(short = 'k', long)]
+    jwks: String,
+
+    /// An algorithm to accept; may be repeated
+    #[arg(long = "alg", required = true, value_parser = input::parse_algorithm)]
+    algorithms: Vec<jwa::Algorithm>,
+
+    /// An audience to accept; may be repeated
+    #[arg(short, long = "aud")]
+    audiences: Vec<jwt::Audience>,
+
+    /// The issuer that the token must have
+    #[arg(short, long = "iss")]
+    issuer: Option<jwt::Issuer>,
+
+    /// A regular expression that the subject must match
+    #[arg(short, long = "sub")]
+    subject: Option<Regex>,
+
+    /// The grace period, in seconds, allowed when checking `exp` and `nbf`
+    #[arg(short, long, default_value_t = 0)]
+    leeway: u64,
+
+    /// Skips checking whether the token has expired
+    #[arg(long)]
+    ignore_expiration: bool,
+
+    /// Checks that the token is not being used before its `nbf` time
+    #[arg(long)]
+    check_not_before: bool,
+}
+
+impl Opts {
+    pub fn run(self) -> Result<String> {
+        let token = input::read_token(&self.token)?;
+        let jwks = Keys::read_set(&self.jwks)?;
+        let validator = self.validator();
+
+        let verified = verify(JwtRef::from_str(&token), &jwks, &validator)?;
+        Ok(serde_json::to_string_pretty(&verified)?)
+    }
+
+    fn validator(&self) -> jwt::CoreValidator {
+        let mut validator = jwt::CoreValidator::default()
+            .with_leeway_secs(self.leeway)
+            .extend_approved_algorithms(self.algorithms.iter().copied())
+            .extend_allowed_audiences(self.audiences.iter().cloned());
+
+        if let Some(issuer) = &self.issuer {
+            validator = validator.require_issuer(issuer.clone());
+        }
+
+        if let Some(subject) = &self.subject {
+            validator = validator.check_subject(subject.clone());
+        }
+
+        if self.ignore_expiration {
+            validator = validator.ignore_expiration();
+        }
+
+        if self.check_not_before {
+            validator = validator.check_not_before();
+        }
+
+        validator
+    }
+}
+
+/// Verifies a token against a key set, returning its header and claims
+pub fn verify(token: &JwtRef, jwks: &Jwks, validator: &jwt::CoreValidator) -> Result<Value> {
+    let decomposed: jwt::Decomposed = token.decompose().wrap_err("malformed JWT")?;
+
+    let key = jwks
+        .get_key_by_opt(decomposed.kid(), decomposed.alg())
+        .ok_or_else(|| eyre!("no matching key found to verify JWT"))?;
+
+    let validated: jwt::Validated<jwt::DynamicClaims> = decomposed
+        .verify(key, validator)
+        .wrap_err("JWT failed verification")?;
+
+    let (header, claims) = validated.extract();
+    Ok(json!({
+        "header": header,
+        "claims": claims,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri::{jwk, Jwk};
+
+    use super::*;
+
+    fn setup() -> Result<(Jwk, Jwks)> {
+        let key = Jwk::from(jwa::Hmac::generate(jwa::hmac::SigningAlgorithm::HS256)?)
+            .with_algorithm(jwa::Algorithm::HS256)
+            .with_key_id(jwk::KeyId::from_static("test"));
+
+        let mut jwks = Jwks::default();
+        jwks.add_key(key.clone());
+
+        Ok((key, jwks))
+    }
+
+    #[test]
+    fn verifies_valid_token() -> Result<()> {
+        let (key, jwks) = setup()?;
+        let token = jwt::BasicClaims::new()
+            .with_audience(jwt::Audience::from_static("api"))
+            .with_future_expiration(60)
+            .sign(
+                &key,
+                &jwt::BasicHeaders::with_key_id(jwa::Algorithm::HS256, "test"),
+            )?;
+
+        let validator = jwt::CoreValidator::default()
+            .add_approved_algorithm(jwa::Algorithm::HS256)
+            .add_allowed_audience(jwt::Audience::from_static("api"));
+
+        let verified = verify(&token, &jwks, &validator)?;
+        assert_eq!(verified["claims"]["aud"], "api");
+        assert_eq!(verified["header"]["kid"], "test");
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_wrong_audience() -> Result<()> {
+        let (key, jwks) = setup()?;
+        let token = jwt::BasicClaims::new()
+            .with_audience(jwt::Audience::from_static("other"))
+            .with_future_expiration(60)
+            .sign(
+                &key,
+                &jwt::BasicHeaders::with_key_id(jwa::Algorithm::HS256, "test"),
+            )?;
+
+        let validator = jwt::CoreValidator::default()
+            .add_approved_algorithm(jwa::Algorithm::HS256)
+            .add_allowed_audience(jwt::Audience::from_static("api"));
+
+        assert!(verify(&token, &jwks, &validator).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unknown_key() -> Result<()> {
+        let (key, jwks) = setup()?;
+        let token = jwt::BasicClaims::new().with_future_expiration(60).sign(
+            &key,
+            &jwt::BasicHeaders::with_key_id(jwa::Algorithm::HS256, "other"),
+        )?;
+
+        let validator = jwt::CoreValidator::default().add_approved_algorithm(jwa::Algorithm::HS256);
+
+        let err = verify(&token, &jwks, &validator).unwrap_err();
+        assert_eq!(err.to_string(), "no matching key found to verify JWT");
+        Ok(())
+    }
+}
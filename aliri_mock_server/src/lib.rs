@@ -0,0 +1,528 @@
+//! A local mock OAuth2/OpenID Connect authorization server for tests
+//!
+//! [`MockServer`] runs an HTTP server on a loopback port within the current
+//! Tokio runtime and serves the following endpoints:
+//!
+//! * `/.well-known/openid-configuration`, an OpenID Connect discovery document,
+//!   also served as RFC8414 metadata at `/.well-known/oauth-authorization-server`
+//! * `/jwks.json`, the issuer's public keys, with an `ETag` for conditional requests
+//! * `/token`, supporting the `client_credentials` and `refresh_token` grants
+//! * `/introspect`, an RFC7662 introspection endpoint for opaque tokens, authenticating
+//!   clients with either HTTP Basic authentication or form fields
+//!
+//! Tokens are signed by an [`aliri::test_util::MockIssuer`]. Each endpoint
+//! can be scripted to fail with a `500 Internal Server Error` or to respond
+//! slowly, and the issuer's keys can be rotated in the middle of a test.
+//!
+//! ```
+//! use aliri::{jwa, test_util::MockIssuer};
+//! use aliri_mock_server::{Endpoint, MockServer};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = MockServer::start(MockIssuer::new(jwa::Algorithm::RS256)?)?;
+//! server.register_client("my_client", "my_secret");
+//!
+//! // The next request to the token endpoint will fail
+//! server.fail_next(Endpoint::Token, 1);
+//!
+//! println!("token endpoint: {}", server.token_url());
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(
+    missing_docs,
+    unused_import_braces,
+    unused_imports,
+    unused_qualifications
+)]
+#![deny(
+    missing_debug_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unused_must_use
+)]
+
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use aliri::{error, jwt, test_util::MockIssuer, Jwks};
+use serde_json::{Map, Value};
+use tokio::sync::oneshot;
+
+mod routes;
+
+/// An endpoint served by the mock server
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// The OpenID Connect discovery document or RFC8414 metadata
+    Discovery,
+    /// The JSON Web Key Set
+    Jwks,
+    /// The token endpoint
+    Token,
+    /// The token introspection endpoint
+    Introspection,
+}
+
+/// Scripted behavior and statistics for a single endpoint
+#[derive(Debug, Default)]
+struct Script {
+    failures: usize,
+    delay: Duration,
+    requests: usize,
+}
+
+#[derive(Debug)]
+struct Inner {
+    issuer: MockIssuer,
+    jwks_generation: u64,
+    jwks_max_age: Option<Duration>,
+    clients: HashMap<String, String>,
+    refresh_tokens: HashMap<String, String>,
+    next_refresh_token: u64,
+    opaque_tokens: HashMap<String, Map<String, Value>>,
+    next_opaque_token: u64,
+    scripts: HashMap<Endpoint, Script>,
+}
+
+#[derive(Clone, Debug)]
+struct Shared {
+    base_url: Arc<str>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A mock OAuth2/OpenID Connect authorization server
+///
+/// The server shuts down when this handle is dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    shared: Shared,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts a server on a random loopback port which signs tokens using
+    /// the given issuer
+    ///
+    /// The issuer's `iss` value is replaced by the URL of the server.
+    ///
+    /// # Errors
+    ///
+    /// Unable to bind to a local port.
+    ///
+    /// # Panics
+    ///
+    /// Panics if not called from within a Tokio runtime.
+    pub fn start(issuer: MockIssuer) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let base_url: Arc<str> = format!("http://{}", addr).into();
+        let issuer = issuer.with_issuer(jwt::Issuer::new(base_url.to_string()));
+
+        let shared = Shared {
+            base_url,
+            inner: Arc::new(Mutex::new(Inner {
+                issuer,
+                jwks_generation: 1,
+                jwks_max_age: None,
+                clients: HashMap::new(),
+                refresh_tokens: HashMap::new(),
+                next_refresh_token: 1,
+                opaque_tokens: HashMap::new(),
+                next_opaque_token: 1,
+                scripts: HashMap::new(),
+            })),
+        };
+
+        let server = axum::Server::from_tcp(listener)
+            .map_err(io::Error::other)?
+            .serve(routes::router(shared.clone()).into_make_service());
+
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let server = server.with_graceful_shutdown(async {
+            let _ = signal.await;
+        });
+
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                let error: &dyn std::error::Error = &err;
+                tracing::warn!(error, "mock authorization server failed");
+            }
+        });
+
+        tracing::debug!(%addr, "mock authorization server started");
+
+        Ok(Self {
+            addr,
+            shared,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// The address on which the server is listening
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The base URL of the server, which is also the issuer
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.shared.base_url
+    }
+
+    /// The URL of the OpenID Connect discovery document
+    #[must_use]
+    pub fn discovery_url(&self) -> String {
+        format!("{}/.well-known/openid-configuration", self.url())
+    }
+
+    /// The URL of the JSON Web Key Set
+    #[must_use]
+    pub fn jwks_url(&self) -> String {
+        format!("{}/jwks.json", self.url())
+    }
+
+    /// The URL of the token endpoint
+    #[must_use]
+    pub fn token_url(&self) -> String {
+        format!("{}/token", self.url())
+    }
+
+    /// The URL of the token introspection endpoint
+    #[must_use]
+    pub fn introspection_url(&self) -> String {
+        format!("{}/introspect", self.url())
+    }
+
+    /// A snapshot of the issuer used to sign tokens
+    ///
+    /// The snapshot can mint tokens directly, but does not observe later key
+    /// rotations.
+    pub fn issuer(&self) -> MockIssuer {
+        self.shared.lock().issuer.clone()
+    }
+
+    /// The keys currently published by the server
+    #[must_use]
+    pub fn jwks(&self) -> Jwks {
+        self.shared.lock().issuer.jwks().clone()
+    }
+
+    /// A validator that accepts valid tokens issued by the server
+    pub fn validator(&self) -> jwt::CoreValidator {
+        self.shared.lock().issuer.validator()
+    }
+
+    /// Registers a confidential client that may use the `client_credentials`
+    /// grant
+    pub fn register_client(&self, client_id: impl Into<String>, client_secret: impl Into<String>) {
+        self.shared
+            .lock()
+            .clients
+            .insert(client_id.into(), client_secret.into());
+    }
+
+    /// Issues a refresh token for the given client
+    ///
+    /// Refresh tokens are single-use; each successful `refresh_token` grant
+    /// returns a new refresh token to use in the next request.
+    #[must_use]
+    pub fn issue_refresh_token(&self, client_id: impl Into<String>) -> String {
+        self.shared.lock().issue_refresh_token(client_id.into())
+    }
+
+    /// Issues an opaque access token that can be introspected
+    ///
+    /// The token carries the default claims of the issuer, to which the
+    /// additional claims are added, replacing any that share the same name.
+    /// The introspection endpoint reports the token as active until it is
+    /// revoked or its `exp` claim has passed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the additional claims are not a JSON object.
+    #[must_use]
+    pub fn issue_opaque_token(&self, extra: Value) -> String {
+        let extra = match extra {
+            Value::Object(extra) => extra,
+            _ => panic!("additional claims must be a JSON object"),
+        };
+
+        let mut inner = self.shared.lock();
+        let mut claims = match serde_json::to_value(inner.issuer.claims()) {
+            Ok(Value::Object(claims)) => claims,
+            _ => Map::new(),
+        };
+        claims.extend(extra);
+
+        let token = format!("opaque-{}", inner.next_opaque_token);
+        inner.next_opaque_token += 1;
+        inner.opaque_tokens.insert(token.clone(), claims);
+        token
+    }
+
+    /// Revokes an opaque access token, so that it is no longer reported as
+    /// active
+    pub fn revoke_token(&self, token: &str) {
+        self.shared.lock().opaque_tokens.remove(token);
+    }
+
+    /// Replaces the signing key with a new key, publishing it alongside the
+    /// previous keys
+    ///
+    /// # Errors
+    ///
+    /// Unable to generate a key.
+    pub fn rotate_keys(&self) -> Result<(), error::Unexpected> {
+        let mut inner = self.shared.lock();
+        inner.issuer.rotate_key()?;
+        inner.jwks_generation += 1;
+        Ok(())
+    }
+
+    /// Stops publishing all keys other than the current signing key
+    pub fn retire_previous_keys(&self) {
+        let mut inner = self.shared.lock();
+        inner.issuer.retire_previous_keys();
+        inner.jwks_generation += 1;
+    }
+
+    /// Sets the `Cache-Control: max-age` sent with the JWKS
+    ///
+    /// By default, no caching headers are sent.
+    pub fn set_jwks_max_age(&self, max_age: Option<Duration>) {
+        self.shared.lock().jwks_max_age = max_age;
+    }
+
+    /// Causes the next `count` requests to the endpoint to fail with a
+    /// `500 Internal Server Error`
+    pub fn fail_next(&self, endpoint: Endpoint, count: usize) {
+        self.shared.lock().script(endpoint).failures = count;
+    }
+
+    /// Delays every response from the endpoint by the given duration
+    ///
+    /// A delay of zero restores immediate responses.
+    pub fn set_delay(&self, endpoint: Endpoint, delay: Duration) {
+        self.shared.lock().script(endpoint).delay = delay;
+    }
+
+    /// The number of requests the endpoint has received, including failed
+    /// requests
+    #[must_use]
+    pub fn request_count(&self, endpoint: Endpoint) -> usize {
+        self.shared.lock().script(endpoint).requests
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl Inner {
+    fn script(&mut self, endpoint: Endpoint) -> &mut Script {
+        self.scripts.entry(endpoint).or_default()
+    }
+
+    fn issue_refresh_token(&mut self, client_id: String) -> String {
+        let refresh_token = format!("refresh-{}", self.next_refresh_token);
+        self.next_refresh_token += 1;
+        self.refresh_tokens.insert(refresh_token.clone(), client_id);
+        refresh_token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri::{jwa, JwtRef};
+    use aliri_oauth2::{oauth2::BasicClaimsWithScope, Authority, ScopePolicy};
+    use aliri_tokens::{
+        sources::{
+            oauth2::{dto, ClientCredentialsTokenSource, RefreshTokenSource, TokenRequestError},
+            AsyncTokenSource,
+        },
+        ClientId, ClientSecret, RefreshToken, TokenLifetimeConfig, TokenWithLifetime,
+    };
+    use color_eyre::Result;
+    use serde_json::Value;
+
+    use super::*;
+
+    fn start() -> Result<MockServer> {
+        let server = MockServer::start(MockIssuer::new(jwa::Algorithm::RS256)?)?;
+        server.register_client("client", "secret");
+        Ok(server)
+    }
+
+    fn client_credentials(
+        server: &MockServer,
+        secret: &'static str,
+    ) -> Result<ClientCredentialsTokenSource<aliri_clock::System>> {
+        let credentials = dto::ClientCredentialsWithAudience {
+            credentials: Arc::new(dto::ClientCredentials {
+                client_id: ClientId::from_static("client"),
+                client_secret: ClientSecret::from_static(secret),
+            }),
+            audience: server.issuer().audience().to_owned(),
+        };
+
+        Ok(ClientCredentialsTokenSource::new(
+            reqwest::Client::new(),
+            server.token_url().parse()?,
+            credentials,
+            TokenLifetimeConfig::default(),
+        ))
+    }
+
+    fn refresh(
+        server: &MockServer,
+        refresh_token: String,
+    ) -> Result<RefreshTokenSource<aliri_clock::System>> {
+        let credentials = dto::RefreshTokenCredentialsSource {
+            client_id: ClientId::from_static("client"),
+            client_secret: None,
+            refresh_token: RefreshToken::new(refresh_token).into_boxed_ref(),
+        };
+
+        Ok(RefreshTokenSource::new(
+            reqwest::Client::new(),
+            server.token_url().parse()?,
+            credentials,
+            TokenLifetimeConfig::default(),
+        ))
+    }
+
+    fn verify(authority: &Authority, token: &TokenWithLifetime) -> bool {
+        authority
+            .verify_token::<BasicClaimsWithScope>(
+                JwtRef::from_str(token.access_token().as_str()),
+                &ScopePolicy::allow_any(),
+            )
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn serves_discovery_document() -> Result<()> {
+        let server = start()?;
+
+        let doc: Value = reqwest::get(server.discovery_url())
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        assert_eq!(doc["issuer"], server.url());
+        assert_eq!(doc["jwks_uri"], server.jwks_url());
+        assert_eq!(doc["token_endpoint"], server.token_url());
+        assert_eq!(doc["introspection_endpoint"], server.introspection_url());
+        assert_eq!(doc["id_token_signing_alg_values_supported"][0], "RS256");
+        assert_eq!(server.request_count(Endpoint::Discovery), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn client_credentials_tokens_are_accepted_by_authority() -> Result<()> {
+        let server = start()?;
+        let authority = Authority::new_from_url(server.jwks_url(), server.validator()).await?;
+
+        let token = client_credentials(&server, "secret")?
+            .request_token()
+            .await?;
+        assert!(verify(&authority, &token));
+
+        let result = client_credentials(&server, "wrong")?.request_token().await;
+        assert!(matches!(
+            result,
+            Err(TokenRequestError::ErrorWithBody { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refresh_tokens_are_rotated() -> Result<()> {
+        let server = start()?;
+        let initial = server.issue_refresh_token("client");
+
+        let mut source = refresh(&server, initial.clone())?;
+        source.request_token().await?;
+        source.request_token().await?;
+
+        let result = refresh(&server, initial)?.request_token().await;
+        assert!(matches!(
+            result,
+            Err(TokenRequestError::ErrorWithBody { .. })
+        ));
+        assert_eq!(server.request_count(Endpoint::Token), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn authority_refresh_follows_key_rotation() -> Result<()> {
+        let server = start()?;
+        let authority = Authority::new_from_url(server.jwks_url(), server.validator()).await?;
+
+        authority.refresh().await?;
+        assert_eq!(server.request_count(Endpoint::Jwks), 2);
+
+        server.rotate_keys()?;
+        let mut source = client_credentials(&server, "secret")?;
+        let token = source.request_token().await?;
+        assert!(!verify(&authority, &token));
+
+        server.fail_next(Endpoint::Jwks, 1);
+        assert!(authority.refresh().await.is_err());
+        assert!(!verify(&authority, &token));
+
+        authority.refresh().await?;
+        assert!(verify(&authority, &token));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn token_endpoint_failures_can_be_scripted() -> Result<()> {
+        let server = start()?;
+        let mut source = client_credentials(&server, "secret")?;
+
+        server.fail_next(Endpoint::Token, 1);
+        let result = source.request_token().await;
+        assert!(matches!(
+            result,
+            Err(TokenRequestError::ErrorWithBody { .. })
+        ));
+        source.request_token().await?;
+
+        server.set_delay(Endpoint::Token, Duration::from_millis(500));
+        let impatient = reqwest::Client::builder()
+            .timeout(Duration::from_millis(50))
+            .build()?;
+        let result = impatient
+            .post(server.token_url())
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await;
+        assert!(result.map_err(|err| err.is_timeout()).unwrap_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aliri_base64::Base64;
+use axum::{
+    extract::{Form, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::{Endpoint, Shared};
+
+pub(crate) fn router(shared: Shared) -> Router {
+    Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/.well-known/oauth-authorization-server", get(discovery))
+        .route("/jwks.json", get(jwks))
+        .route("/token", post(token))
+        .route("/introspect", post(introspect))
+        .with_state(shared)
+}
+
+/// Records the request and applies any scripted delay or failure
+async fn scripted(shared: &Shared, endpoint: Endpoint) -> Result<(), Response> {
+    let (delay, fail) = {
+        let mut inner = shared.lock();
+        let script = inner.script(endpoint);
+        script.requests += 1;
+
+        let fail = script.failures > 0;
+        if fail {
+            script.failures -= 1;
+        }
+
+        (script.delay, fail)
+    };
+
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+
+    if fail {
+        tracing::debug!(?endpoint, "responding with scripted failure");
+        Err((StatusCode::INTERNAL_SERVER_ERROR, "scripted failure").into_response())
+    } else {
+        Ok(())
+    }
+}
+
+async fn discovery(State(shared): State<Shared>) -> Response {
+    if let Err(resp) = scripted(&shared, Endpoint::Discovery).await {
+        return resp;
+    }
+
+    let alg = shared.lock().issuer.algorithm();
+    let base = &shared.base_url;
+
+    Json(json!({
+        "issuer": base.as_ref(),
+        "jwks_uri": format!("{}/jwks.json", base),
+        "token_endpoint": format!("{}/token", base),
+        "introspection_endpoint": format!("{}/introspect", base),
+        "grant_types_supported": ["client_credentials", "refresh_token"],
+        "response_types_supported": ["token"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": [alg],
+        "token_endpoint_auth_methods_supported": ["client_secret_post"],
+        "introspection_endpoint_auth_methods_supported": ["client_secret_basic", "client_secret_post"],
+    }))
+    .into_response()
+}
+
+async fn jwks(State(shared): State<Shared>, headers: HeaderMap) -> Response {
+    if let Err(resp) = scripted(&shared, Endpoint::Jwks).await {
+        return resp;
+    }
+
+    let (jwks, generation, max_age) = {
+        let inner = shared.lock();
+        (
+            inner.issuer.jwks().clone(),
+            inner.jwks_generation,
+            inner.jwks_max_age,
+        )
+    };
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        header::ETAG,
+        HeaderValue::try_from(format!("\"{}\"", generation))
+            .expect("generated ETag is a valid header value"),
+    );
+    if let Some(max_age) = max_age {
+        response_headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::try_from(format!("max-age={}", max_age.as_secs()))
+                .expect("generated Cache-Control is a valid header value"),
+        );
+    }
+
+    if headers.get(header::IF_NONE_MATCH) == response_headers.get(header::ETAG) {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+
+    (response_headers, Json(jwks)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenRequest {
+    grant_type: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    audience: Option<String>,
+    refresh_token: Option<String>,
+    scope: Option<String>,
+}
+
+fn token_error(status: StatusCode, error: &str) -> Response {
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+async fn token(State(shared): State<Shared>, Form(request): Form<TokenRequest>) -> Response {
+    if let Err(resp) = scripted(&shared, Endpoint::Token).await {
+        return resp;
+    }
+
+    let mut inner = shared.lock();
+
+    let client_id = match request.client_id {
+        Some(client_id) => client_id,
+        None => return token_error(StatusCode::BAD_REQUEST, "invalid_request"),
+    };
+
+    if let Some(secret) = &request.client_secret {
+        if inner.clients.get(&client_id) != Some(secret) {
+            return token_error(StatusCode::UNAUTHORIZED, "invalid_client");
+        }
+    }
+
+    let mut claims = Map::new();
+    claims.insert("sub".into(), client_id.clone().into());
+    claims.insert("client_id".into(), client_id.clone().into());
+    if let Some(scope) = &request.scope {
+        claims.insert("scope".into(), scope.clone().into());
+    }
+
+    let refresh_token = match request.grant_type.as_str() {
+        "client_credentials" => {
+            if request.client_secret.is_none() {
+                return token_error(StatusCode::UNAUTHORIZED, "invalid_client");
+            }
+
+            if let Some(audience) = request.audience {
+                claims.insert("aud".into(), audience.into());
+            }
+
+            None
+        }
+        "refresh_token" => {
+            let presented = match &request.refresh_token {
+                Some(refresh_token) => refresh_token,
+                None => return token_error(StatusCode::BAD_REQUEST, "invalid_request"),
+            };
+
+            match inner.refresh_tokens.remove(presented) {
+                Some(owner) if owner == client_id => {}
+                Some(owner) => {
+                    inner.refresh_tokens.insert(presented.clone(), owner);
+                    return token_error(StatusCode::BAD_REQUEST, "invalid_grant");
+                }
+                None => return token_error(StatusCode::BAD_REQUEST, "invalid_grant"),
+            }
+
+            Some(inner.issue_refresh_token(client_id))
+        }
+        _ => return token_error(StatusCode::BAD_REQUEST, "unsupported_grant_type"),
+    };
+
+    let access_token = inner.issuer.token_with(Value::Object(claims));
+    let expires_in = inner.issuer.lifetime_secs();
+    drop(inner);
+
+    let mut body = json!({
+        "access_token": access_token,
+        "token_type": "Bearer",
+        "expires_in": expires_in,
+    });
+
+    if let Some(refresh_token) = refresh_token {
+        body["refresh_token"] = refresh_token.into();
+    }
+
+    if let Some(scope) = request.scope {
+        body["scope"] = scope.into();
+    }
+
+    Json(body).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct IntrospectionRequest {
+    token: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// Reads client credentials sent with HTTP Basic authentication
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let encoded = value.strip_prefix("Basic ")?;
+    let decoded = Base64::from_encoded(encoded).ok()?.into_inner();
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (client_id, secret) = decoded.split_once(':')?;
+    Some((client_id.to_owned(), secret.to_owned()))
+}
+
+async fn introspect(
+    State(shared): State<Shared>,
+    headers: HeaderMap,
+    Form(request): Form<IntrospectionRequest>,
+) -> Response {
+    if let Err(resp) = scripted(&shared, Endpoint::Introspection).await {
+        return resp;
+    }
+
+    let inner = shared.lock();
+
+    let credentials = basic_credentials(&headers)
+        .or_else(|| request.client_id.clone().zip(request.client_secret.clone()));
+    let authenticated = match credentials {
+        Some((client_id, secret)) => inner.clients.get(&client_id) == Some(&secret),
+        None => false,
+    };
+    if !authenticated {
+        return token_error(StatusCode::UNAUTHORIZED, "invalid_client");
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    #[allow(clippy::unnecessary_map_or)]
+    let claims = inner.opaque_tokens.get(&request.token).filter(|claims| {
+        claims
+            .get("exp")
+            .and_then(Value::as_u64)
+            .map_or(true, |exp| exp > now)
+    });
+
+    match claims {
+        Some(claims) => {
+            let mut body = claims.clone();
+            body.insert("active".into(), true.into());
+            body.insert("token_type".into(), "Bearer".into());
+            Json(Value::Object(body)).into_response()
+        }
+        None => Json(json!({ "active": false })).into_response(),
+    }
+}
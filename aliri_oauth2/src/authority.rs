@@ -1,11 +1,26 @@
-use std::{sync::Arc, time::Duration};
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+#[cfg(feature = "reqwest")]
+use std::{
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::SystemTime,
+};
 
+#[cfg(feature = "reqwest")]
+use aliri::jwa;
 use aliri::{
+    jwk,
     jwt::{self, CoreHeaders, HasAlgorithm},
     Jwks, JwtRef,
 };
+use aliri_clock::{Clock, System};
 use aliri_traits::Policy;
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 #[cfg(feature = "reqwest")]
 use reqwest::{
     header::{self, HeaderValue},
@@ -14,16 +29,55 @@ use reqwest::{
 use serde::Deserialize;
 use thiserror::Error;
 
-use crate::{oauth2::HasScope, ScopePolicy};
+#[cfg(any(feature = "reqwest", feature = "file"))]
+use crate::metrics::RefreshOutcome;
+use crate::{
+    metrics::{MetricsRecorder, VerifyOutcome},
+    oauth2::HasScope,
+    ScopePolicy,
+};
+
+#[cfg(feature = "tokio")]
+mod changes;
+#[cfg(feature = "file")]
+mod file;
+mod overrides;
+#[cfg(all(feature = "reqwest", feature = "file"))]
+mod persist;
+#[cfg(feature = "tokio")]
+mod refresher;
+mod sources;
+mod token_cache;
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub use changes::{JwksChange, JwksSubscriber};
+#[cfg(feature = "file")]
+#[cfg_attr(docsrs, doc(cfg(feature = "file")))]
+pub use file::{JwksFile, JwksFileError};
+pub use overrides::VerifyOverrides;
+#[cfg(all(feature = "reqwest", feature = "file"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "file"))))]
+pub use persist::JwksCacheFile;
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub use refresher::{RefreshConfig, RefreshHandle};
+#[cfg(feature = "reqwest")]
+use sources::AdditionalJwks;
+use sources::KeySources;
+use token_cache::TokenCache;
 
 /// Indicates the requester held insufficient scopes to be granted access
 /// to a controlled resource
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum AuthorityError {
     /// Indicates that the authority cannot verify the JWT because it cannot
     /// find a key which matches the specifications in the token header
     #[error("no matching key found to validate JWT")]
     UnknownKeyId,
+    /// Indicates that no authority is available for the issuer of the JWT
+    #[error("no authority found for JWT issuer")]
+    UnknownIssuer,
     /// Indicates that the JWT was malformed or otherwise defective
     #[error("invalid JWT")]
     JwtVerifyError(#[from] aliri::error::JwtVerifyError),
@@ -31,8 +85,217 @@ pub enum AuthorityError {
     /// level of authorization requested.
     #[error("access denied by policy")]
     PolicyDenial(#[from] crate::InsufficientScope),
+    /// Indicates that the authority reported that the token is no longer
+    /// active, such as when it has been revoked
+    #[error("token is not active")]
+    Inactive,
+    /// Indicates that the authority was unable to determine whether the token
+    /// is valid, such as when a remote service could not be reached
+    #[error("unable to verify token")]
+    Unavailable(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// The future returned by the asynchronous [`aliri_traits::Authority`]
+/// implementations in this crate
+pub(crate) type VerifyFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, AuthorityError>> + Send + 'a>>;
+
+/// An error while discovering an authority from its issuer
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    /// Unable to fetch the authorization server metadata from either
+    /// well-known location
+    #[error("unable to fetch authorization server metadata")]
+    Metadata(#[source] reqwest::Error),
+    /// The issuer in the metadata does not exactly match the requested issuer
+    #[error("authorization server metadata is for issuer `{actual}`, expected `{expected}`")]
+    IssuerMismatch {
+        /// The requested issuer
+        expected: jwt::Issuer,
+        /// The issuer named in the metadata
+        actual: jwt::Issuer,
+    },
+    /// Unable to fetch the JWKS named in the metadata
+    #[error("unable to fetch JWKS")]
+    Jwks(#[source] reqwest::Error),
+}
+
+/// The subset of OpenID Connect discovery and RFC8414 authorization server
+/// metadata used to configure an authority
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Deserialize)]
+struct ServerMetadata {
+    issuer: jwt::Issuer,
+    jwks_uri: String,
+    #[serde(default)]
+    id_token_signing_alg_values_supported: Vec<String>,
+}
+
+#[cfg(feature = "reqwest")]
+impl ServerMetadata {
+    /// The advertised signing algorithms, skipping any that are not supported
+    fn algorithms(&self) -> impl Iterator<Item = jwa::Algorithm> + '_ {
+        use serde::de::{value, IntoDeserializer};
+
+        self.id_token_signing_alg_values_supported
+            .iter()
+            .filter_map(|alg| {
+                let de: value::StrDeserializer<value::Error> = alg.as_str().into_deserializer();
+                jwa::Algorithm::deserialize(de).ok()
+            })
+    }
+}
+
+/// The well-known metadata locations for an issuer
+///
+/// OpenID Connect discovery appends the well-known suffix to the issuer,
+/// while RFC8414 inserts the well-known prefix between the host and the path.
+#[cfg(feature = "reqwest")]
+fn metadata_urls(issuer: &jwt::IssuerRef) -> [String; 2] {
+    let issuer = issuer.as_str().trim_end_matches('/');
+    let authority_start = issuer.find("://").map_or(0, |idx| idx + 3);
+    let (origin, path) = match issuer[authority_start..].find('/') {
+        Some(idx) => issuer.split_at(authority_start + idx),
+        None => (issuer, ""),
+    };
+
+    [
+        format!("{}/.well-known/openid-configuration", issuer),
+        format!("{}/.well-known/oauth-authorization-server{}", origin, path),
+    ]
+}
+
+/// Determines how long a response may be considered fresh from its
+/// `Cache-Control: max-age` or `Expires` headers
+///
+/// `max-age` takes precedence over `Expires`, as in RFC7234. `no-cache` and
+/// `no-store` indicate that the response is immediately stale.
+#[cfg(feature = "reqwest")]
+fn freshness_lifetime(headers: &header::HeaderMap, now: SystemTime) -> Option<Duration> {
+    let cache_control = headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim);
+
+    let mut max_age = None;
+    for directive in cache_control {
+        let directive = directive.to_ascii_lowercase();
+        if directive == "no-cache" || directive == "no-store" {
+            return Some(Duration::ZERO);
+        }
+
+        if let Some(secs) = directive.strip_prefix("max-age=") {
+            max_age = secs.trim_matches('"').parse().ok().map(Duration::from_secs);
+        }
+    }
+
+    if max_age.is_some() {
+        return max_age;
+    }
+
+    let http_date = |name| {
+        headers
+            .get(name)
+            .and_then(|value: &HeaderValue| value.to_str().ok())
+            .and_then(|value| httpdate::parse_http_date(value).ok())
+    };
+
+    let expires = http_date(header::EXPIRES)?;
+    let date = http_date(header::DATE).unwrap_or(now);
+    Some(expires.duration_since(date).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(feature = "reqwest")]
+async fn fetch_metadata(client: &Client, url: &str) -> Result<ServerMetadata, reqwest::Error> {
+    let response = client.get(url).send().await?;
+    response.error_for_status_ref()?;
+    response.json().await
+}
+
+/// Limits on refetching the JWKS when a token names an unknown key
+///
+/// Used by [`Authority::verify_token_async()`]. At most one refetch is
+/// started per `min_interval`, and a key that is still missing after a
+/// successful refetch does not trigger another refetch until `negative_ttl`
+/// has passed.
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct RefetchPolicy {
+    min_interval: Duration,
+    negative_ttl: Duration,
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+impl Default for RefetchPolicy {
+    /// Refetches at most once every 10 seconds, and remembers missing keys
+    /// for 5 minutes
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(10),
+            negative_ttl: Duration::from_secs(300),
+        }
+    }
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+impl RefetchPolicy {
+    /// Sets the minimum time between refetches
+    pub fn with_min_interval(self, min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            ..self
+        }
+    }
+
+    /// Sets how long a key that is missing after a successful refetch is
+    /// remembered
+    pub fn with_negative_ttl(self, negative_ttl: Duration) -> Self {
+        Self {
+            negative_ttl,
+            ..self
+        }
+    }
+
+    /// The minimum time between refetches
+    #[must_use]
+    pub fn min_interval(&self) -> Duration {
+        self.min_interval
+    }
+
+    /// How long a key that is missing after a refetch is remembered
+    #[must_use]
+    pub fn negative_ttl(&self) -> Duration {
+        self.negative_ttl
+    }
+}
+
+/// Shared state for refetching the JWKS on unknown keys
+///
+/// The mutex ensures that concurrent misses share a single refetch. The
+/// generation is bumped after each refetch so that requests that waited on
+/// the mutex can tell that a refetch happened while they were waiting.
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[derive(Debug, Default)]
+struct Refetch {
+    generation: AtomicU64,
+    state: tokio::sync::Mutex<RefetchState>,
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[derive(Debug, Default)]
+struct RefetchState {
+    last_refetch: Option<Instant>,
+    missing: HashMap<Option<jwk::KeyId>, Instant>,
 }
 
+/// The keys from a single key source, along with the validators for
+/// conditional requests to refresh them
 #[derive(Debug)]
 struct VolatileData {
     jwks: Jwks,
@@ -57,16 +320,67 @@ impl VolatileData {
 #[derive(Debug)]
 #[cfg(feature = "reqwest")]
 struct RemoteOptions {
-    jwks_url: String,
+    jwks_url: ArcSwap<String>,
     client: Client,
+    discovery: Option<Discovery>,
+    #[cfg(feature = "file")]
+    cache_file: Option<JwksCacheFile>,
+}
+
+/// Configuration for re-reading authorization server metadata on refresh
+#[derive(Debug)]
+#[cfg(feature = "reqwest")]
+struct Discovery {
+    metadata_url: String,
+    issuer: jwt::Issuer,
+    seed: Mutex<Seed>,
+}
+
+/// The validator provided by the caller, and the algorithms advertised in the
+/// authorization server metadata
+#[derive(Debug)]
+#[cfg(feature = "reqwest")]
+struct Seed {
+    base_validator: jwt::CoreValidator,
+    algorithms: Vec<jwa::Algorithm>,
+}
+
+#[cfg(feature = "reqwest")]
+impl Discovery {
+    /// Serializes updates to the seed, so that the validator is always seeded
+    /// from the latest base validator and metadata
+    fn seed(&self) -> MutexGuard<'_, Seed> {
+        self.seed.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Seeds the validator with the issuer and advertised algorithms
+    fn seeded(&self, seed: &Seed) -> jwt::CoreValidator {
+        seed.base_validator
+            .clone()
+            .require_issuer(self.issuer.clone())
+            .extend_approved_algorithms(seed.algorithms.iter().copied())
+    }
 }
 
 #[derive(Debug)]
 struct Inner {
+    /// The keys used for verification, merged from all key sources
+    keys: ArcSwap<Jwks>,
+    /// The keys from the primary key source
     data: ArcSwap<VolatileData>,
+    sources: KeySources,
     #[cfg(feature = "reqwest")]
     remote: Option<RemoteOptions>,
-    validator: jwt::CoreValidator,
+    validator: ArcSwap<jwt::CoreValidator>,
+    key_policy: ArcSwapOption<jwk::KeyPolicy>,
+    token_cache: ArcSwapOption<TokenCache>,
+    metrics: ArcSwapOption<Box<dyn MetricsRecorder>>,
+    #[cfg(feature = "tokio")]
+    changes: tokio::sync::watch::Sender<Arc<Jwks>>,
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    refetch_policy: ArcSwap<RefetchPolicy>,
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    refetch: Refetch,
 }
 
 /// An authority backed by a potentially dynamic JSON Web Key Set (JWKS)
@@ -80,18 +394,49 @@ pub struct Authority {
 impl Authority {
     /// Constructs a new JWKS authority from an existing JWKS
     pub fn new(jwks: Jwks, validator: jwt::CoreValidator) -> Self {
-        let data = VolatileData::new(jwks);
+        let keys = Arc::new(jwks.clone());
 
         Self {
             inner: Arc::new(Inner {
-                data: ArcSwap::from_pointee(data),
+                #[cfg(feature = "tokio")]
+                changes: tokio::sync::watch::channel(Arc::clone(&keys)).0,
+                keys: ArcSwap::new(keys),
+                data: ArcSwap::from_pointee(VolatileData::new(jwks)),
+                sources: KeySources::default(),
                 #[cfg(feature = "reqwest")]
                 remote: None,
-                validator,
+                validator: ArcSwap::from_pointee(validator),
+                key_policy: ArcSwapOption::empty(),
+                token_cache: ArcSwapOption::empty(),
+                metrics: ArcSwapOption::empty(),
+                #[cfg(all(feature = "reqwest", feature = "tokio"))]
+                refetch_policy: ArcSwap::from_pointee(RefetchPolicy::default()),
+                #[cfg(all(feature = "reqwest", feature = "tokio"))]
+                refetch: Refetch::default(),
             }),
         }
     }
 
+    /// Constructs a new JWKS authority from a local file, or from a directory
+    /// of JWK files
+    ///
+    /// Use [`spawn_file_reload()`][Authority::spawn_file_reload()] to pick up
+    /// changes to the keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no valid keys can be loaded.
+    #[cfg(feature = "file")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "file")))]
+    pub async fn new_from_file(
+        file: &JwksFile,
+        validator: jwt::CoreValidator,
+    ) -> Result<Self, JwksFileError> {
+        let jwks = file.load().await?;
+        tracing::info!(path = %file.path().display(), "JWKS loaded");
+        Ok(Self::new(jwks, validator))
+    }
+
     /// Constructs a new JWKS authority from a URL
     #[cfg(feature = "reqwest")]
     #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
@@ -99,11 +444,167 @@ impl Authority {
         jwks_url: String,
         validator: jwt::CoreValidator,
     ) -> Result<Self, reqwest::Error> {
-        let client = Client::builder()
+        let client = Self::client()?;
+        let data = Self::fetch_jwks(&client, &jwks_url).await?;
+
+        Ok(Self::from_remote(
+            data,
+            RemoteOptions {
+                jwks_url: ArcSwap::from_pointee(jwks_url),
+                client,
+                discovery: None,
+                #[cfg(feature = "file")]
+                cache_file: None,
+            },
+            validator,
+        ))
+    }
+
+    /// Constructs a new JWKS authority from a URL, persisting the JWKS to a
+    /// local file
+    ///
+    /// Each JWKS successfully fetched from the URL, whether now or on a later
+    /// refresh, is written to the file along with its `ETag` and
+    /// `Last-Modified` validators. If the JWKS cannot be fetched now, the
+    /// persisted JWKS is used instead, provided that it was fetched from the
+    /// same URL and is within the maximum staleness of the cache file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from fetching the JWKS if it cannot be fetched and no
+    /// usable JWKS has been persisted.
+    #[cfg(all(feature = "reqwest", feature = "file"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "file"))))]
+    pub async fn new_from_url_with_cache(
+        jwks_url: String,
+        validator: jwt::CoreValidator,
+        cache_file: JwksCacheFile,
+    ) -> Result<Self, reqwest::Error> {
+        let client = Self::client()?;
+        let data = match Self::fetch_jwks(&client, &jwks_url).await {
+            Ok(data) => {
+                cache_file.store(&jwks_url, &data).await;
+                data
+            }
+            Err(err) => match cache_file.load(&jwks_url).await {
+                Some(data) => {
+                    let error: &dyn std::error::Error = &err;
+                    tracing::warn!(
+                        error,
+                        jwks.url = %jwks_url,
+                        path = %cache_file.path().display(),
+                        "unable to fetch JWKS; using persisted JWKS"
+                    );
+                    data
+                }
+                None => return Err(err),
+            },
+        };
+
+        Ok(Self::from_remote(
+            data,
+            RemoteOptions {
+                jwks_url: ArcSwap::from_pointee(jwks_url),
+                client,
+                discovery: None,
+                cache_file: Some(cache_file),
+            },
+            validator,
+        ))
+    }
+
+    /// Constructs a new JWKS authority by discovering the metadata of an issuer
+    ///
+    /// The metadata is fetched from the OpenID Connect discovery document at
+    /// `/.well-known/openid-configuration`, falling back to the RFC8414
+    /// `/.well-known/oauth-authorization-server` location. The `issuer` in the
+    /// metadata must exactly match the requested issuer. The JWKS is fetched
+    /// from the `jwks_uri` in the metadata.
+    ///
+    /// The validator is seeded with the issuer as a required issuer, and with
+    /// any supported algorithms in `id_token_signing_alg_values_supported` as
+    /// approved algorithms. Other validations, such as the allowed audiences,
+    /// are taken from the provided validator.
+    ///
+    /// Refreshing the authority re-reads the metadata, picking up any changes
+    /// to the `jwks_uri` or the advertised algorithms.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metadata cannot be fetched, names a different
+    /// issuer, or if the JWKS cannot be fetched.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub async fn from_issuer(
+        issuer: jwt::Issuer,
+        validator: jwt::CoreValidator,
+    ) -> Result<Self, DiscoveryError> {
+        let client = Self::client().map_err(DiscoveryError::Metadata)?;
+
+        let [oidc_url, rfc8414_url] = metadata_urls(&issuer);
+        let (metadata_url, metadata) = match fetch_metadata(&client, &oidc_url).await {
+            Ok(metadata) => (oidc_url, metadata),
+            Err(err) => {
+                {
+                    let error: &dyn std::error::Error = &err;
+                    tracing::debug!(
+                        error,
+                        metadata.url = %oidc_url,
+                        "OpenID Connect discovery failed; trying RFC8414 metadata"
+                    );
+                }
+                let metadata = fetch_metadata(&client, &rfc8414_url)
+                    .await
+                    .map_err(DiscoveryError::Metadata)?;
+                (rfc8414_url, metadata)
+            }
+        };
+
+        if metadata.issuer != issuer {
+            return Err(DiscoveryError::IssuerMismatch {
+                expected: issuer,
+                actual: metadata.issuer,
+            });
+        }
+
+        tracing::info!(%issuer, metadata.url = %metadata_url, "authorization server metadata discovered");
+
+        let data = Self::fetch_jwks(&client, &metadata.jwks_uri)
+            .await
+            .map_err(DiscoveryError::Jwks)?;
+        let discovery = Discovery {
+            metadata_url,
+            issuer,
+            seed: Mutex::new(Seed {
+                base_validator: validator,
+                algorithms: metadata.algorithms().collect(),
+            }),
+        };
+        let seeded = discovery.seeded(&discovery.seed());
+
+        Ok(Self::from_remote(
+            data,
+            RemoteOptions {
+                jwks_url: ArcSwap::from_pointee(metadata.jwks_uri),
+                client,
+                discovery: Some(discovery),
+                #[cfg(feature = "file")]
+                cache_file: None,
+            },
+            seeded,
+        ))
+    }
+
+    #[cfg(feature = "reqwest")]
+    fn client() -> Result<Client, reqwest::Error> {
+        Client::builder()
             .user_agent(concat!("aliri_oauth2/", env!("CARGO_PKG_VERSION")))
-            .build()?;
+            .build()
+    }
 
-        let response = client.get(&jwks_url).send().await?;
+    #[cfg(feature = "reqwest")]
+    async fn fetch_jwks(client: &Client, jwks_url: &str) -> Result<VolatileData, reqwest::Error> {
+        let response = client.get(jwks_url).send().await?;
         response.error_for_status_ref()?;
 
         let etag = response.headers().get(header::ETAG).map(ToOwned::to_owned);
@@ -113,25 +614,51 @@ impl Authority {
             .map(ToOwned::to_owned);
         let jwks = response.json::<Jwks>().await?;
 
-        let data = VolatileData {
+        tracing::info!(jwks.url = %jwks_url, "JWKS refreshed");
+
+        Ok(VolatileData {
             jwks,
             etag,
             last_modified,
-        };
+        })
+    }
 
-        tracing::info!(jwks.url = %jwks_url, "JWKS refreshed");
+    #[cfg(feature = "reqwest")]
+    fn from_remote(
+        data: VolatileData,
+        remote: RemoteOptions,
+        validator: jwt::CoreValidator,
+    ) -> Self {
+        let keys = Arc::new(data.jwks.clone());
 
-        Ok(Self {
+        Self {
             inner: Arc::new(Inner {
+                #[cfg(feature = "tokio")]
+                changes: tokio::sync::watch::channel(Arc::clone(&keys)).0,
+                keys: ArcSwap::new(keys),
                 data: ArcSwap::from_pointee(data),
-                remote: Some(RemoteOptions { jwks_url, client }),
-                validator,
+                sources: KeySources::default(),
+                remote: Some(remote),
+                validator: ArcSwap::from_pointee(validator),
+                key_policy: ArcSwapOption::empty(),
+                token_cache: ArcSwapOption::empty(),
+                metrics: ArcSwapOption::empty(),
+                #[cfg(all(feature = "reqwest", feature = "tokio"))]
+                refetch_policy: ArcSwap::from_pointee(RefetchPolicy::default()),
+                #[cfg(all(feature = "reqwest", feature = "tokio"))]
+                refetch: Refetch::default(),
             }),
-        })
+        }
     }
 
     /// A non-terminating future that will automatically refresh the JWKS
     /// using the configured interval
+    ///
+    /// The first refresh happens after the first interval has elapsed. Failed
+    /// refreshes are not retried until the next interval. Use
+    /// [`spawn_refresh_with()`][Authority::spawn_refresh_with()] to retry
+    /// failed refreshes with backoff, to schedule refreshes according to the
+    /// caching headers on the JWKS response, or to monitor and stop the task.
     #[cfg(feature = "tokio")]
     #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
     pub fn spawn_refresh(&self, interval: Duration) {
@@ -149,68 +676,252 @@ impl Authority {
         });
     }
 
+    /// Spawns a background task that refreshes the JWKS, scheduling refreshes
+    /// according to the caching headers on the JWKS response
+    ///
+    /// The first refresh happens immediately. The returned handle can be used
+    /// to monitor and stop the task. Use [`RefreshConfig::fixed()`] to refresh
+    /// on a fixed interval, retrying failed refreshes with backoff.
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub fn spawn_refresh_with(&self, config: RefreshConfig) -> RefreshHandle {
+        RefreshHandle::spawn(self.clone(), config)
+    }
+
+    /// Spawns a background task that reloads the JWKS from a local file, or
+    /// from a directory of JWK files
+    ///
+    /// The first reload happens immediately, and the file or directory is
+    /// then polled at the interval configured on the [`JwksFile`]. If the keys
+    /// cannot be loaded, the current JWKS continues to be used. The returned
+    /// handle can be used to monitor and stop the task.
+    #[cfg(feature = "file")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "file")))]
+    pub fn spawn_file_reload(&self, file: JwksFile) -> RefreshHandle {
+        let authority = self.clone();
+        RefreshHandle::spawn_task(|shared| file::run(authority, file, shared))
+    }
+
     /// Refreshes the JWKS from the remote URL
     ///
     /// No retries are attempted. If the attempt to refresh the JWKS from
     /// the remote URL fails, no change is made to the internal JWKS.
+    ///
+    /// For an authority constructed with [`from_issuer()`][Authority::from_issuer()],
+    /// the authorization server metadata is re-read first. If the metadata
+    /// now names a different issuer, it is ignored and the previously
+    /// discovered configuration continues to be used.
     #[cfg(feature = "reqwest")]
     #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
-    #[tracing::instrument(skip(self), fields(jwks.url = tracing::field::Empty))]
     pub async fn refresh(&self) -> Result<(), reqwest::Error> {
+        self.refresh_with_lifetime().await.map(drop)
+    }
+
+    /// Refreshes the JWKS, returning how long the response may be considered
+    /// fresh according to its caching headers
+    ///
+    /// Each additional JWKS URL is refreshed even if refreshing another key
+    /// source fails. A failure to refresh an additional JWKS URL is logged and
+    /// recorded, but only a failure to refresh the primary key source is
+    /// returned, so that it does not affect how the primary key source is
+    /// refreshed. The shortest lifetime of any refreshed key source is
+    /// returned.
+    #[cfg(feature = "reqwest")]
+    async fn refresh_with_lifetime(&self) -> Result<Option<Duration>, reqwest::Error> {
+        let record = |result: &Result<(RefreshOutcome, Option<Duration>), reqwest::Error>| {
+            self.record_refresh(
+                result
+                    .as_ref()
+                    .map_or(RefreshOutcome::Failed, |(outcome, _)| *outcome),
+            );
+        };
+
+        let mut lifetime = Ok(None);
+
         if let Some(remote) = &self.inner.remote {
-            let span = tracing::Span::current();
-            span.record("jwks.url", &remote.jwks_url);
-            tracing::debug!("refreshing JWKS");
-            let mut request = remote.client.get(&remote.jwks_url);
-
-            {
-                let data = self.inner.data.load();
-                if let Some(etag) = &data.etag {
-                    request = request.header(header::IF_NONE_MATCH, etag)
-                } else if let Some(last_modified) = &data.last_modified {
-                    request = request.header(header::IF_MODIFIED_SINCE, last_modified)
+            let result = self.refresh_remote(remote).await;
+            record(&result);
+            lifetime = result.map(|(_, lifetime)| lifetime);
+        }
+
+        for source in self.inner.sources.additional.load().iter() {
+            let result = self.refresh_additional(source).await;
+            record(&result);
+            match (&mut lifetime, result) {
+                (Ok(Some(a)), Ok((_, Some(b)))) => *a = (*a).min(b),
+                (Ok(a), Ok((_, b))) => *a = a.or(b),
+                (_, Err(err)) => {
+                    let error: &dyn std::error::Error = &err;
+                    tracing::warn!(
+                        error,
+                        jwks.url = %source.url,
+                        "additional JWKS refresh failed; keeping its current keys"
+                    );
                 }
+                (Err(_), Ok(_)) => {}
             }
+        }
 
-            let response = request.send().await?;
+        lifetime
+    }
 
-            if response.status() == StatusCode::NOT_MODIFIED {
-                tracing::debug!("JWKS not modified");
-                return Ok(());
-            } else if let Err(err) = response.error_for_status_ref() {
-                let error: &dyn std::error::Error = &err;
-                tracing::warn!(
-                    error,
-                    http.status_code = response.status().as_u16(),
-                    "JWKS refresh failed; unexpected response status",
-                );
-                return Err(err);
-            }
+    #[cfg(feature = "reqwest")]
+    #[tracing::instrument(skip(self, remote), fields(jwks.url = tracing::field::Empty))]
+    async fn refresh_remote(
+        &self,
+        remote: &RemoteOptions,
+    ) -> Result<(RefreshOutcome, Option<Duration>), reqwest::Error> {
+        if let Some(discovery) = &remote.discovery {
+            self.refresh_metadata(remote, discovery).await?;
+        }
+
+        let jwks_url = remote.jwks_url.load();
+        let span = tracing::Span::current();
+        span.record("jwks.url", jwks_url.as_str());
+        tracing::debug!("refreshing JWKS");
+
+        let (fetched, lifetime) = self
+            .fetch_if_modified(&remote.client, &jwks_url, &self.inner.data.load())
+            .await?;
+
+        match fetched {
+            Some(data) => {
+                let data = Arc::new(data);
 
-            let etag = response.headers().get(header::ETAG).map(ToOwned::to_owned);
-            let last_modified = response
-                .headers()
-                .get(header::LAST_MODIFIED)
-                .map(ToOwned::to_owned);
-            match response.json::<Jwks>().await {
-                Ok(jwks) => {
-                    let data = Arc::new(VolatileData {
-                        jwks,
-                        etag,
-                        last_modified,
-                    });
-
-                    self.inner.data.store(data);
-                    tracing::info!("JWKS refreshed");
+                #[cfg(feature = "file")]
+                if let Some(cache_file) = &remote.cache_file {
+                    cache_file.store(&jwks_url, &data).await;
                 }
-                Err(err) => {
-                    let error: &dyn std::error::Error = &err;
-                    tracing::warn!(error, "JWKS refresh failed; unexpected error");
-                    return Err(err);
+
+                self.replace_data(data);
+                tracing::info!("JWKS refreshed");
+                Ok((RefreshOutcome::Updated, lifetime))
+            }
+            None => {
+                #[cfg(feature = "file")]
+                if let Some(cache_file) = &remote.cache_file {
+                    cache_file.store(&jwks_url, &self.inner.data.load()).await;
                 }
+                Ok((RefreshOutcome::NotModified, lifetime))
+            }
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tracing::instrument(skip(self, source), fields(jwks.url = %source.url))]
+    async fn refresh_additional(
+        &self,
+        source: &AdditionalJwks,
+    ) -> Result<(RefreshOutcome, Option<Duration>), reqwest::Error> {
+        tracing::debug!("refreshing JWKS");
+
+        let (fetched, lifetime) = self
+            .fetch_if_modified(&source.client, &source.url, &source.data.load())
+            .await?;
+
+        match fetched {
+            Some(data) => {
+                self.update_sources(|| source.data.store(Arc::new(data)));
+                tracing::info!("JWKS refreshed");
+                Ok((RefreshOutcome::Updated, lifetime))
+            }
+            None => Ok((RefreshOutcome::NotModified, lifetime)),
+        }
+    }
+
+    /// Fetches the JWKS unless it is unchanged since `current` was fetched,
+    /// returning how long the response may be considered fresh
+    #[cfg(feature = "reqwest")]
+    async fn fetch_if_modified(
+        &self,
+        client: &Client,
+        jwks_url: &str,
+        current: &VolatileData,
+    ) -> Result<(Option<VolatileData>, Option<Duration>), reqwest::Error> {
+        let mut request = client.get(jwks_url);
+
+        if let Some(etag) = &current.etag {
+            request = request.header(header::IF_NONE_MATCH, etag)
+        } else if let Some(last_modified) = &current.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified)
+        }
+
+        let response = request.send().await?;
+        let lifetime = freshness_lifetime(response.headers(), SystemTime::now());
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            tracing::debug!("JWKS not modified");
+            return Ok((None, lifetime));
+        } else if let Err(err) = response.error_for_status_ref() {
+            let error: &dyn std::error::Error = &err;
+            tracing::warn!(
+                error,
+                http.status_code = response.status().as_u16(),
+                "JWKS refresh failed; unexpected response status",
+            );
+            return Err(err);
+        }
+
+        let etag = response.headers().get(header::ETAG).map(ToOwned::to_owned);
+        let last_modified = response
+            .headers()
+            .get(header::LAST_MODIFIED)
+            .map(ToOwned::to_owned);
+        match response.json::<Jwks>().await {
+            Ok(jwks) => {
+                let data = VolatileData {
+                    jwks,
+                    etag,
+                    last_modified,
+                };
+                Ok((Some(data), lifetime))
+            }
+            Err(err) => {
+                let error: &dyn std::error::Error = &err;
+                tracing::warn!(error, "JWKS refresh failed; unexpected error");
+                Err(err)
+            }
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    async fn refresh_metadata(
+        &self,
+        remote: &RemoteOptions,
+        discovery: &Discovery,
+    ) -> Result<(), reqwest::Error> {
+        tracing::debug!(metadata.url = %discovery.metadata_url, "refreshing authorization server metadata");
+        let metadata = match fetch_metadata(&remote.client, &discovery.metadata_url).await {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                let error: &dyn std::error::Error = &err;
+                tracing::warn!(error, "authorization server metadata refresh failed");
+                return Err(err);
             }
+        };
+
+        if metadata.issuer != discovery.issuer {
+            tracing::warn!(
+                expected = %discovery.issuer,
+                actual = %metadata.issuer,
+                "authorization server metadata names a different issuer; ignoring"
+            );
+            return Ok(());
+        }
+
+        if **remote.jwks_url.load() != metadata.jwks_uri {
+            tracing::info!(jwks.url = %metadata.jwks_uri, "JWKS URL changed");
+            let jwks = self.inner.data.load().jwks.clone();
+            self.replace_data(Arc::new(VolatileData::new(jwks)));
+            remote.jwks_url.store(Arc::new(metadata.jwks_uri.clone()));
         }
 
+        let mut seed = discovery.seed();
+        seed.algorithms = metadata.algorithms().collect();
+        self.inner
+            .validator
+            .store(Arc::new(discovery.seeded(&seed)));
+
         Ok(())
     }
 
@@ -225,62 +936,515 @@ impl Authority {
         Ok(())
     }
 
-    /// Updates the JWKS associated with the internal state
-    pub fn set_jwks(&self, jwks: Jwks) {
-        let data = Arc::new(VolatileData::new(jwks));
-        self.inner.data.store(data);
+    #[cfg(all(not(feature = "reqwest"), feature = "tokio"))]
+    async fn refresh_with_lifetime(&self) -> Result<Option<Duration>, std::convert::Infallible> {
+        Ok(None)
     }
 
-    /// Authenticates the token and checks access according to the policy
+    /// Replaces the validator used to validate the claims of tokens
     ///
-    /// # Errors
-    ///
-    /// Returns an error if the token is invalid or is not authorized by the policy
-    pub fn verify_token<T>(&self, token: &JwtRef, policy: &ScopePolicy) -> Result<T, AuthorityError>
-    where
-        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
-    {
-        let decomposed = token.decompose()?;
-
-        let validated: jwt::Validated<T>;
+    /// The new validator applies to all later verifications, including those
+    /// of cached tokens. For an authority constructed with
+    /// [`from_issuer()`][Authority::from_issuer()], the validator is seeded
+    /// with the issuer and advertised algorithms, as when it was constructed.
+    pub fn set_validator(&self, validator: jwt::CoreValidator) {
+        #[cfg(feature = "reqwest")]
+        if let Some(discovery) = self
+            .inner
+            .remote
+            .as_ref()
+            .and_then(|remote| remote.discovery.as_ref())
         {
-            let guard = self.inner.data.load();
-
-            let key = {
-                let kid = decomposed.kid();
-                let alg = decomposed.alg();
+            let mut seed = discovery.seed();
+            seed.base_validator = validator;
+            self.inner
+                .validator
+                .store(Arc::new(discovery.seeded(&seed)));
+            return;
+        }
 
-                guard.jwks.get_key_by_opt(kid, alg).ok_or_else(|| {
-                    if let Some(kid) = kid {
-                        tracing::debug!(%kid, %alg, "unable to find matching key");
-                    } else {
-                        tracing::debug!(%alg, "unable to find matching key");
-                    }
-                    AuthorityError::UnknownKeyId
-                })?
-            };
+        self.inner.validator.store(Arc::new(validator));
+    }
 
-            validated = decomposed.verify(key, &self.inner.validator)?;
-        }
+    /// Updates the policy used to reject weak keys
+    ///
+    /// The policy applies to the keys from every key source, including the
+    /// keys already loaded, which are filtered again as soon as the policy is
+    /// set. Rejected keys are kept by their key source, so they are used
+    /// again if a later policy accepts them.
+    ///
+    /// Until a policy is set, keys are only checked against the minimums
+    /// enforced when a JWKS is deserialized.
+    pub fn set_key_policy(&self, policy: jwk::KeyPolicy) {
+        self.update_sources(|| self.inner.key_policy.store(Some(Arc::new(policy))));
+    }
 
-        policy.evaluate(validated.claims().scope())?;
+    /// Updates the limits on refetching the JWKS when a token names an
+    /// unknown key
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+    pub fn set_refetch_policy(&self, policy: RefetchPolicy) {
+        self.inner.refetch_policy.store(Arc::new(policy));
+    }
 
-        let (_, validated_claims) = validated.extract();
+    /// Caches verified tokens, holding at most `capacity` tokens
+    ///
+    /// Tokens are cached until they expire, allowing repeated uses of the
+    /// same token to skip signature verification. The claims of a cached token
+    /// are still validated on each use, so `exp` and `nbf` are checked against
+    /// the current time and the scope policy is evaluated. The cache is
+    /// emptied whenever the JWKS changes.
+    ///
+    /// Enabling the cache again replaces any existing cache.
+    pub fn enable_token_cache(&self, capacity: usize) {
+        self.enable_token_cache_with_clock(capacity, System);
+    }
 
-        Ok(validated_claims)
+    /// Caches verified tokens, holding at most `capacity` tokens and checking
+    /// the times in cached tokens against the given clock
+    ///
+    /// Tokens verified before being cached are still checked against the
+    /// system clock.
+    ///
+    /// Enabling the cache again replaces any existing cache.
+    pub fn enable_token_cache_with_clock<C>(&self, capacity: usize, clock: C)
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        self.inner
+            .token_cache
+            .store(Some(Arc::new(TokenCache::new(capacity, clock))));
     }
-}
 
-#[cfg(test)]
-#[cfg(never)]
-mod tests {
-    use std::time::Duration;
+    /// Stops caching verified tokens, discarding any cached tokens
+    pub fn disable_token_cache(&self) {
+        self.inner.token_cache.store(None);
+    }
 
-    use aliri::{jwk, jws, jwt, Jwk};
-    use color_eyre::Result;
+    /// Reports token verifications and JWKS refreshes to a metrics recorder
+    ///
+    /// The current number of keys is reported immediately, and again whenever
+    /// the JWKS changes. Setting a recorder replaces any existing recorder.
+    pub fn set_metrics_recorder<R: MetricsRecorder>(&self, recorder: R) {
+        recorder.record_key_count(self.inner.keys.load().keys().len());
+        self.inner.metrics.store(Some(Arc::new(Box::new(recorder))));
+    }
 
-    use super::*;
-    use crate::Scopes;
+    /// Stops reporting metrics
+    pub fn clear_metrics_recorder(&self) {
+        self.inner.metrics.store(None);
+    }
+
+    /// Subscribes to changes to the JWKS
+    ///
+    /// Changes are reported whether the JWKS is refreshed from the remote
+    /// URL or replaced through [`set_jwks()`][Authority::set_jwks()]. This can
+    /// be used to invalidate downstream caches or to audit key rotations.
+    ///
+    /// A change is only reported once the authority verifies tokens against
+    /// the new JWKS, so a subscriber may verify tokens in response to it.
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub fn subscribe(&self) -> JwksSubscriber {
+        JwksSubscriber::new(self.inner.changes.subscribe())
+    }
+
+    /// Updates the JWKS associated with the internal state
+    ///
+    /// This replaces the keys from the primary key source. Any pinned keys
+    /// and keys from additional JWKS URLs are kept.
+    pub fn set_jwks(&self, jwks: Jwks) {
+        let data = Arc::new(VolatileData::new(jwks));
+        self.replace_data(data);
+    }
+
+    /// Pins keys that are trusted alongside the keys from the primary key
+    /// source, such as an HMAC key for internal service tokens
+    ///
+    /// Pinned keys are kept when the JWKS is refreshed or replaced, and take
+    /// precedence over the keys from all other key sources: a key from
+    /// another key source is ignored if a pinned key has the same key ID.
+    /// Setting the pinned keys replaces any previously pinned keys.
+    pub fn set_pinned_keys(&self, jwks: Jwks) {
+        self.update_sources(|| self.inner.sources.pinned.store(Arc::new(jwks)));
+    }
+
+    /// Adds a remote JWKS whose keys are trusted alongside the keys from the
+    /// primary key source
+    ///
+    /// The JWKS is fetched now, and is refreshed whenever the authority is
+    /// refreshed. Keys from additional JWKS URLs have the lowest precedence: a
+    /// key is ignored if a pinned key, a key from the primary key source, or a
+    /// key from a JWKS URL added earlier has the same key ID.
+    ///
+    /// If a later refresh of this JWKS fails, its last good keys continue to
+    /// be used. The failure is logged and recorded by the metrics recorder,
+    /// but does not cause [`refresh()`][Authority::refresh()] to fail.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JWKS cannot be fetched, in which case the URL
+    /// is not added.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub async fn add_jwks_url(&self, jwks_url: String) -> Result<(), reqwest::Error> {
+        let client = match &self.inner.remote {
+            Some(remote) => remote.client.clone(),
+            None => Self::client()?,
+        };
+
+        let empty = VolatileData::new(Jwks::default());
+        let (data, _) = self.fetch_if_modified(&client, &jwks_url, &empty).await?;
+        tracing::info!(jwks.url = %jwks_url, "additional JWKS added");
+
+        let source = Arc::new(AdditionalJwks {
+            url: jwks_url,
+            client,
+            data: ArcSwap::from_pointee(data.unwrap_or(empty)),
+        });
+
+        self.update_sources(|| {
+            let mut additional = Vec::clone(&self.inner.sources.additional.load());
+            additional.push(source);
+            self.inner.sources.additional.store(Arc::new(additional));
+        });
+
+        Ok(())
+    }
+
+    /// Replaces the keys from the primary key source
+    fn replace_data(&self, data: Arc<VolatileData>) {
+        self.update_sources(|| self.inner.data.store(data));
+    }
+
+    /// Updates a key source, then merges the key sources into the JWKS used
+    /// for verification, discarding tokens verified against the previous JWKS
+    fn update_sources(&self, update: impl FnOnce()) {
+        let _merging = self.inner.sources.lock();
+        update();
+
+        let data = self.inner.data.load();
+        let pinned = self.inner.sources.pinned.load();
+        #[cfg_attr(not(feature = "reqwest"), allow(unused_mut))]
+        let mut sets = vec![&**pinned, &data.jwks];
+
+        #[cfg(feature = "reqwest")]
+        let additional: Vec<_> = self
+            .inner
+            .sources
+            .additional
+            .load()
+            .iter()
+            .map(|source| source.data.load_full())
+            .collect();
+        #[cfg(feature = "reqwest")]
+        sets.extend(additional.iter().map(|data| &data.jwks));
+
+        let mut keys = sources::merge(sets);
+        if let Some(policy) = &*self.inner.key_policy.load() {
+            keys.retain_acceptable(policy);
+        }
+        let keys = Arc::new(keys);
+
+        if let Some(metrics) = &*self.inner.metrics.load() {
+            metrics.record_key_count(keys.keys().len());
+        }
+
+        self.inner.keys.store(Arc::clone(&keys));
+        if let Some(cache) = &*self.inner.token_cache.load() {
+            cache.clear();
+        }
+
+        // Subscribers are notified last, so that they see the new keys if they
+        // verify tokens in response to the change
+        #[cfg(feature = "tokio")]
+        self.inner.changes.send_replace(keys);
+    }
+
+    #[cfg(any(feature = "reqwest", feature = "file"))]
+    fn record_refresh(&self, outcome: RefreshOutcome) {
+        if let Some(metrics) = &*self.inner.metrics.load() {
+            metrics.record_refresh(outcome);
+        }
+    }
+
+    fn record_verification<T>(&self, result: &Result<T, AuthorityError>) {
+        if let Some(metrics) = &*self.inner.metrics.load() {
+            metrics.record_verification(VerifyOutcome::of(result));
+        }
+    }
+
+    /// Authenticates the token and checks access according to the policy
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is invalid or is not authorized by the policy
+    pub fn verify_token<T>(&self, token: &JwtRef, policy: &ScopePolicy) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        self.verify_token_with(token, policy, &VerifyOverrides::default())
+    }
+
+    /// Authenticates the token and checks access according to the policy,
+    /// applying additional validations for this verification only
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is invalid, is rejected by the
+    /// overrides, or is not authorized by the policy
+    pub fn verify_token_with<T>(
+        &self,
+        token: &JwtRef,
+        policy: &ScopePolicy,
+        overrides: &VerifyOverrides,
+    ) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let result = self
+            .verify_cached(token, overrides, policy)
+            .unwrap_or_else(|| {
+                let decomposed = token.decompose()?;
+                self.verify_decomposed(token, decomposed, overrides, policy)
+            });
+
+        self.record_verification(&result);
+        result
+    }
+
+    /// Authenticates the token and checks access according to the policy,
+    /// refetching the JWKS if the token names an unknown key
+    ///
+    /// Concurrent requests that name unknown keys share a single refetch.
+    /// Refetches are limited by the [`RefetchPolicy`], so a token naming a
+    /// key that does not exist cannot be used to flood the remote source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is invalid or is not authorized by the policy
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+    pub async fn verify_token_async<T>(
+        &self,
+        token: &JwtRef,
+        policy: &ScopePolicy,
+    ) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        self.verify_token_with_async(token, policy, &VerifyOverrides::default())
+            .await
+    }
+
+    /// Authenticates the token and checks access according to the policy,
+    /// applying additional validations for this verification only, and
+    /// refetching the JWKS if the token names an unknown key
+    ///
+    /// Refetches are limited as for
+    /// [`verify_token_async()`][Authority::verify_token_async()].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is invalid, is rejected by the
+    /// overrides, or is not authorized by the policy
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+    pub async fn verify_token_with_async<T>(
+        &self,
+        token: &JwtRef,
+        policy: &ScopePolicy,
+        overrides: &VerifyOverrides,
+    ) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let result = match self.verify_cached(token, overrides, policy) {
+            Some(result) => result,
+            None => self.verify_refetching(token, overrides, policy).await,
+        };
+
+        self.record_verification(&result);
+        result
+    }
+
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    async fn verify_refetching<T>(
+        &self,
+        token: &JwtRef,
+        overrides: &VerifyOverrides,
+        policy: &ScopePolicy,
+    ) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let decomposed = token.decompose()?;
+
+        if !self.has_key_for(&decomposed) {
+            self.refetch_for_unknown_key(&decomposed).await;
+        }
+
+        self.verify_decomposed(token, decomposed, overrides, policy)
+    }
+
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    fn has_key_for(&self, decomposed: &jwt::Decomposed) -> bool {
+        self.inner
+            .keys
+            .load()
+            .get_key_by_opt(decomposed.kid(), decomposed.alg())
+            .is_some()
+    }
+
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    async fn refetch_for_unknown_key(&self, decomposed: &jwt::Decomposed<'_>) {
+        let refetch = &self.inner.refetch;
+        let observed = refetch.generation.load(Ordering::Acquire);
+        let mut state = refetch.state.lock().await;
+
+        if refetch.generation.load(Ordering::Acquire) != observed {
+            tracing::trace!("JWKS refetched while waiting; not refetching again");
+            return;
+        }
+
+        let policy = **self.inner.refetch_policy.load();
+        let now = Instant::now();
+        let kid = decomposed.kid().map(ToOwned::to_owned);
+
+        state
+            .missing
+            .retain(|_, missing_since| now.duration_since(*missing_since) < policy.negative_ttl);
+
+        if state.missing.contains_key(&kid) {
+            tracing::debug!("key was missing after a recent refetch; not refetching");
+            return;
+        }
+
+        if let Some(last_refetch) = state.last_refetch {
+            if now.duration_since(last_refetch) < policy.min_interval {
+                tracing::debug!("JWKS was refetched recently; not refetching");
+                return;
+            }
+        }
+
+        tracing::debug!("refetching JWKS for unknown key");
+        state.last_refetch = Some(now);
+
+        // Errors are logged by `refresh`; the verification will fail with an unknown key,
+        // but the key is only remembered as missing if the JWKS was actually refetched
+        let refreshed = self.refresh().await.is_ok();
+        refetch.generation.fetch_add(1, Ordering::Release);
+
+        if refreshed && !self.has_key_for(decomposed) {
+            state.missing.insert(kid, Instant::now());
+        }
+    }
+
+    fn verify_cached<T>(
+        &self,
+        token: &JwtRef,
+        overrides: &VerifyOverrides,
+        policy: &ScopePolicy,
+    ) -> Option<Result<T, AuthorityError>>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let cache = self.inner.token_cache.load();
+        let cache = cache.as_ref()?;
+        cache.verify(token, &self.inner.validator.load(), overrides, policy)
+    }
+
+    fn verify_decomposed<T>(
+        &self,
+        token: &JwtRef,
+        decomposed: jwt::Decomposed,
+        overrides: &VerifyOverrides,
+        policy: &ScopePolicy,
+    ) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let cache = self.inner.token_cache.load_full();
+        let validated: jwt::Validated<T>;
+        {
+            let guard = self.inner.keys.load();
+
+            let key = {
+                let kid = decomposed.kid();
+                let alg = decomposed.alg();
+
+                guard.get_key_by_opt(kid, alg).ok_or_else(|| {
+                    if let Some(kid) = kid {
+                        tracing::debug!(%kid, %alg, "unable to find matching key");
+                    } else {
+                        tracing::debug!(%alg, "unable to find matching key");
+                    }
+                    AuthorityError::UnknownKeyId
+                })?
+            };
+
+            let cacheable = cache.as_ref().map(|_| {
+                (
+                    decomposed.untrusted_header().clone(),
+                    decomposed.untrusted_payload(),
+                )
+            });
+
+            validated = decomposed.verify(key, &self.inner.validator.load())?;
+
+            if let (Some(cache), Some((header, payload))) = (&cache, cacheable) {
+                cache.insert(token, header, payload, validated.claims().exp(), || {
+                    Arc::ptr_eq(&guard, &self.inner.keys.load())
+                });
+            }
+        }
+
+        overrides
+            .validate(validated.claims())
+            .map_err(aliri::error::JwtVerifyError::from)?;
+        policy.evaluate(validated.claims().scope())?;
+
+        let (_, validated_claims) = validated.extract();
+
+        Ok(validated_claims)
+    }
+}
+
+/// Verifies tokens with [`verify_token_async()`][Authority::verify_token_async()]
+/// when the `reqwest` and `tokio` features are enabled, and with
+/// [`verify_token()`][Authority::verify_token()] otherwise
+impl<'a, T> aliri_traits::Authority<'a, T> for Authority
+where
+    T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims + Send + 'a,
+{
+    type Policy = &'a ScopePolicy;
+    type Token = &'a JwtRef;
+    type VerifyFuture = VerifyFuture<'a, T>;
+    type VerifyError = AuthorityError;
+
+    fn verify(&'a self, token: Self::Token, policy: Self::Policy) -> Self::VerifyFuture {
+        #[cfg(all(feature = "reqwest", feature = "tokio"))]
+        {
+            Box::pin(self.verify_token_async(token, policy))
+        }
+
+        #[cfg(not(all(feature = "reqwest", feature = "tokio")))]
+        {
+            Box::pin(std::future::ready(self.verify_token(token, policy)))
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(never)]
+mod tests {
+    use std::time::Duration;
+
+    use aliri::{jwk, jws, jwt, Jwk};
+    use color_eyre::Result;
+
+    use super::*;
+    use crate::Scopes;
 
     #[tokio::test]
     #[ignore = "disabled private key management"]
@@ -411,3 +1575,1060 @@ mod tests {
         Ok(())
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "tokio")]
+mod test_util {
+    use std::time::Duration;
+
+    /// Polls the condition until it holds, panicking if it does not hold
+    /// within five seconds
+    pub(super) async fn wait_until(description: &str, mut condition: impl FnMut() -> bool) {
+        let poll = async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        };
+
+        if tokio::time::timeout(Duration::from_secs(5), poll)
+            .await
+            .is_err()
+        {
+            panic!("timed out waiting until {}", description);
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "rsa")]
+mod mock_issuer_tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    use aliri::{jwa, jwt::CoreClaims, test_util::MockIssuer};
+    use aliri_clock::{DurationSecs, TestClock};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{metrics::RefreshOutcome, oauth2::BasicClaimsWithScope};
+
+    #[derive(Debug, Default)]
+    pub(super) struct TestRecorder {
+        pub(super) verifications: Mutex<Vec<VerifyOutcome>>,
+        pub(super) refreshes: Mutex<Vec<RefreshOutcome>>,
+        pub(super) key_count: AtomicUsize,
+    }
+
+    impl MetricsRecorder for Arc<TestRecorder> {
+        fn record_verification(&self, outcome: VerifyOutcome) {
+            self.verifications.lock().unwrap().push(outcome);
+        }
+
+        fn record_refresh(&self, outcome: RefreshOutcome) {
+            self.refreshes.lock().unwrap().push(outcome);
+        }
+
+        fn record_key_count(&self, count: usize) {
+            self.key_count.store(count, Ordering::SeqCst);
+        }
+    }
+
+    fn setup() -> (MockIssuer, Authority) {
+        let issuer = MockIssuer::new(jwa::Algorithm::RS256).unwrap();
+        let authority = Authority::new(issuer.jwks().clone(), issuer.validator());
+        (issuer, authority)
+    }
+
+    #[test]
+    fn accepts_tokens_with_sufficient_scope() {
+        let (issuer, authority) = setup();
+        let token = issuer.token_with(json!({ "scope": "read write" }));
+        let policy = ScopePolicy::allow_one_from_static("read");
+
+        let claims: BasicClaimsWithScope = authority.verify_token(&token, &policy).unwrap();
+        assert_eq!(claims.iss(), Some(issuer.issuer()));
+    }
+
+    #[test]
+    fn rejects_tokens_with_insufficient_scope() {
+        let (issuer, authority) = setup();
+        let token = issuer.token_with(json!({ "scope": "read" }));
+        let policy = ScopePolicy::allow_one_from_static("write");
+
+        let result = authority.verify_token::<BasicClaimsWithScope>(&token, &policy);
+        assert!(matches!(result, Err(AuthorityError::PolicyDenial(_))));
+    }
+
+    #[test]
+    fn rejects_invalid_tokens() {
+        let (issuer, authority) = setup();
+        let policy = ScopePolicy::allow_any();
+
+        for token in [
+            issuer.expired_token(),
+            issuer.wrong_audience_token(),
+            issuer.bad_signature_token(),
+        ] {
+            let result = authority.verify_token::<BasicClaimsWithScope>(&token, &policy);
+            assert!(matches!(result, Err(AuthorityError::JwtVerifyError(_))));
+        }
+    }
+
+    #[test]
+    fn rejects_tokens_from_other_issuers() {
+        let (_, authority) = setup();
+        let other = MockIssuer::new(jwa::Algorithm::RS256).unwrap();
+        let policy = ScopePolicy::allow_any();
+
+        let result = authority.verify_token::<BasicClaimsWithScope>(&other.token(), &policy);
+        assert!(matches!(result, Err(AuthorityError::UnknownKeyId)));
+    }
+
+    #[test]
+    fn cached_tokens_are_still_checked_against_policy_and_clock() {
+        let issuer = MockIssuer::new(jwa::Algorithm::RS256)
+            .unwrap()
+            .with_lifetime_secs(60);
+        let authority = Authority::new(issuer.jwks().clone(), issuer.validator());
+        let clock = TestClock::new(System.now());
+        authority.enable_token_cache_with_clock(10, clock.clone());
+
+        let token = issuer.token_with(json!({ "scope": "read" }));
+        let read = ScopePolicy::allow_one_from_static("read");
+        let write = ScopePolicy::allow_one_from_static("write");
+
+        for _ in 0..2 {
+            let result = authority.verify_token::<BasicClaimsWithScope>(&token, &read);
+            assert!(result.is_ok());
+        }
+
+        let result = authority.verify_token::<BasicClaimsWithScope>(&token, &write);
+        assert!(matches!(result, Err(AuthorityError::PolicyDenial(_))));
+
+        clock.advance(DurationSecs(120));
+
+        let result = authority.verify_token::<BasicClaimsWithScope>(&token, &read);
+        assert!(matches!(result, Err(AuthorityError::JwtVerifyError(_))));
+    }
+
+    #[test]
+    fn cached_tokens_are_discarded_when_jwks_changes() {
+        let (issuer, authority) = setup();
+        authority.enable_token_cache(10);
+        let token = issuer.token();
+        let policy = ScopePolicy::allow_any();
+
+        let result = authority.verify_token::<BasicClaimsWithScope>(&token, &policy);
+        assert!(result.is_ok());
+
+        authority.set_jwks(Jwks::default());
+
+        let result = authority.verify_token::<BasicClaimsWithScope>(&token, &policy);
+        assert!(matches!(result, Err(AuthorityError::UnknownKeyId)));
+    }
+
+    #[test]
+    fn records_verification_outcomes_and_key_count() {
+        let (issuer, authority) = setup();
+        let recorder = Arc::new(TestRecorder::default());
+        authority.set_metrics_recorder(Arc::clone(&recorder));
+        assert_eq!(recorder.key_count.load(Ordering::SeqCst), 1);
+
+        let other = MockIssuer::new(jwa::Algorithm::RS256).unwrap();
+        let write = ScopePolicy::allow_one_from_static("write");
+        for (token, policy) in [
+            (issuer.token(), ScopePolicy::allow_any()),
+            (issuer.token(), write),
+            (issuer.expired_token(), ScopePolicy::allow_any()),
+            (issuer.wrong_audience_token(), ScopePolicy::allow_any()),
+            (issuer.bad_signature_token(), ScopePolicy::allow_any()),
+            (other.token(), ScopePolicy::allow_any()),
+        ] {
+            let _ = authority.verify_token::<BasicClaimsWithScope>(&token, &policy);
+        }
+
+        assert_eq!(
+            *recorder.verifications.lock().unwrap(),
+            [
+                VerifyOutcome::Success,
+                VerifyOutcome::PolicyDenied,
+                VerifyOutcome::TokenExpired,
+                VerifyOutcome::InvalidAudience,
+                VerifyOutcome::SignatureMismatch,
+                VerifyOutcome::UnknownKeyId,
+            ]
+        );
+
+        authority.set_jwks(Jwks::default());
+        assert_eq!(recorder.key_count.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn reports_jwks_changes_to_subscribers() {
+        let (mut issuer, authority) = setup();
+        let mut subscriber = authority.subscribe();
+        assert_eq!(subscriber.jwks(), issuer.jwks());
+
+        let original = issuer.key_id().to_owned();
+        issuer.rotate_key().unwrap();
+        let rotated = issuer.key_id().to_owned();
+        issuer.retire_previous_keys();
+
+        authority.set_jwks(issuer.jwks().clone());
+        authority.set_jwks(issuer.jwks().clone());
+
+        let change = subscriber.changed().await.unwrap();
+        assert!(authority
+            .verify_token::<BasicClaimsWithScope>(&issuer.token(), &ScopePolicy::allow_any())
+            .is_ok());
+        assert_eq!(change.added(), [rotated]);
+        assert_eq!(change.removed(), [original]);
+        assert_eq!(change.jwks(), issuer.jwks());
+
+        authority.set_jwks(issuer.jwks().clone());
+        drop(authority);
+        assert!(subscriber.changed().await.is_none());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn ignores_reordered_keys_and_reports_each_key_id_once() {
+        let (mut issuer, authority) = setup();
+        issuer.rotate_key().unwrap();
+        authority.set_jwks(issuer.jwks().clone());
+        let mut subscriber = authority.subscribe();
+
+        let mut reordered = Jwks::default();
+        for key in issuer.jwks().keys().iter().rev() {
+            reordered.add_key(key.clone());
+        }
+        reordered.add_key(issuer.jwks().keys()[0].clone());
+        authority.set_jwks(reordered);
+
+        let changed = tokio::time::timeout(Duration::ZERO, subscriber.changed()).await;
+        assert!(changed.is_err());
+
+        let shared = jwk::KeyId::from_static("shared");
+        let mut duplicated = issuer.jwks().clone();
+        for _ in 0..2 {
+            let other = MockIssuer::new(jwa::Algorithm::RS256).unwrap();
+            duplicated.add_key(other.jwks().keys()[0].clone().with_key_id(shared.clone()));
+        }
+        authority.set_jwks(duplicated);
+
+        let change = subscriber.changed().await.unwrap();
+        assert_eq!(change.added(), [shared]);
+        assert!(change.removed().is_empty());
+    }
+
+    #[test]
+    fn pinned_keys_are_kept_and_take_precedence() {
+        let (issuer, authority) = setup();
+        let internal = MockIssuer::new(jwa::Algorithm::RS256).unwrap();
+        let policy = ScopePolicy::allow_any();
+        let accepts = |token: &JwtRef| {
+            authority
+                .verify_token::<BasicClaimsWithScope>(token, &policy)
+                .is_ok()
+        };
+
+        authority.set_pinned_keys(internal.jwks().clone());
+        authority.set_jwks(issuer.jwks().clone());
+        assert!(accepts(&issuer.token()));
+        assert!(accepts(&internal.token()));
+
+        let mut shadowing = Jwks::default();
+        shadowing.add_key(
+            internal
+                .signing_key()
+                .clone()
+                .public_only()
+                .with_key_id(issuer.key_id().to_owned()),
+        );
+        authority.set_pinned_keys(shadowing);
+        assert!(!accepts(&issuer.token()));
+        assert!(!accepts(&internal.token()));
+
+        authority.set_pinned_keys(Jwks::default());
+        assert!(accepts(&issuer.token()));
+    }
+
+    #[test]
+    fn applies_updated_validator_and_per_call_overrides() {
+        let (issuer, authority) = setup();
+        authority.enable_token_cache(10);
+        let policy = ScopePolicy::allow_any();
+        let audience = jwt::Audience::from_static("https://other.example.com/");
+        let other = issuer.clone().with_audience(audience.clone());
+        let overrides = VerifyOverrides::new().require_audience(audience.clone());
+        let invalid_audience = |result: Result<BasicClaimsWithScope, AuthorityError>| {
+            matches!(
+                result,
+                Err(AuthorityError::JwtVerifyError(
+                    aliri::error::JwtVerifyError::ClaimsRejected(
+                        aliri::error::ClaimsRejected::InvalidAudience
+                    )
+                ))
+            )
+        };
+
+        let token = issuer.token();
+        assert!(invalid_audience(
+            authority.verify_token(&other.token(), &policy)
+        ));
+        assert!(authority
+            .verify_token::<BasicClaimsWithScope>(&token, &policy)
+            .is_ok());
+        assert!(invalid_audience(
+            authority.verify_token_with(&token, &policy, &overrides)
+        ));
+
+        authority.set_validator(issuer.validator().add_allowed_audience(audience));
+        assert!(authority
+            .verify_token_with::<BasicClaimsWithScope>(&other.token(), &policy, &overrides)
+            .is_ok());
+
+        authority.set_validator(issuer.validator());
+        assert!(invalid_audience(authority.verify_token_with(
+            &other.token(),
+            &policy,
+            &overrides
+        )));
+    }
+
+    #[test]
+    fn applies_key_policy_to_loaded_keys() {
+        let (issuer, authority) = setup();
+        let policy = ScopePolicy::allow_any();
+        let accepts = || {
+            authority
+                .verify_token::<BasicClaimsWithScope>(&issuer.token(), &policy)
+                .is_ok()
+        };
+        assert!(accepts());
+
+        authority.set_key_policy(jwk::KeyPolicy::default().with_min_rsa_modulus_bits(8192));
+        assert!(!accepts());
+        authority.set_jwks(issuer.jwks().clone());
+        authority.set_pinned_keys(issuer.jwks().clone());
+        assert!(!accepts());
+
+        authority.set_key_policy(jwk::KeyPolicy::default());
+        assert!(accepts());
+    }
+
+    #[test]
+    fn cached_tokens_are_not_shared_between_tokens() {
+        let (issuer, authority) = setup();
+        authority.enable_token_cache(10);
+        let policy = ScopePolicy::allow_any();
+
+        let token = issuer.token();
+        let result = authority.verify_token::<BasicClaimsWithScope>(&token, &policy);
+        assert!(result.is_ok());
+
+        let result =
+            authority.verify_token::<BasicClaimsWithScope>(&issuer.bad_signature_token(), &policy);
+        assert!(matches!(result, Err(AuthorityError::JwtVerifyError(_))));
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "file", feature = "rsa"))]
+mod file_tests {
+    use std::path::PathBuf;
+
+    use aliri::{jwa, test_util::MockIssuer};
+
+    use super::*;
+    use crate::{authority::test_util::wait_until, oauth2::BasicClaimsWithScope};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("aliri_oauth2_jwks_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn issuer() -> MockIssuer {
+        MockIssuer::new(jwa::Algorithm::RS256)
+            .unwrap()
+            .with_issuer("https://issuer.example.com/")
+    }
+
+    fn accepts(authority: &Authority, token: &JwtRef) -> bool {
+        authority
+            .verify_token::<BasicClaimsWithScope>(token, &ScopePolicy::allow_any())
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn merges_json_files_in_directory() {
+        let dir = temp_dir("directory");
+        let (first, second, ignored) = (issuer(), issuer(), issuer());
+
+        std::fs::write(
+            dir.join("first.json"),
+            serde_json::to_vec(&first.jwks().keys()[0]).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("second.json"),
+            serde_json::to_vec(second.jwks()).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join(".hidden.json"),
+            serde_json::to_vec(ignored.jwks()).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("README"), "not a key").unwrap();
+
+        let authority = Authority::new_from_file(&JwksFile::new(&dir), first.validator())
+            .await
+            .unwrap();
+
+        assert!(accepts(&authority, &first.token()));
+        assert!(accepts(&authority, &second.token()));
+        assert!(!accepts(&authority, &ignored.token()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_empty_or_invalid_files() {
+        let dir = temp_dir("invalid");
+        let file = JwksFile::new(dir.join("jwks.json"));
+
+        assert!(matches!(file.load().await, Err(JwksFileError::Io { .. })));
+
+        std::fs::write(file.path(), r#"{"keys":[]}"#).unwrap();
+        assert!(matches!(
+            file.load().await,
+            Err(JwksFileError::Empty { .. })
+        ));
+
+        std::fs::write(file.path(), "{").unwrap();
+        assert!(matches!(
+            file.load().await,
+            Err(JwksFileError::Invalid { .. })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reloads_changes_and_keeps_last_good_jwks() {
+        let dir = temp_dir("reload");
+        let path = dir.join("jwks.json");
+        let mut issuer = issuer();
+        std::fs::write(&path, serde_json::to_vec(issuer.jwks()).unwrap()).unwrap();
+
+        let file = JwksFile::new(&path).with_poll_interval(Duration::from_millis(20));
+        let authority = Authority::new_from_file(&file, issuer.validator())
+            .await
+            .unwrap();
+        let handle = authority.spawn_file_reload(file);
+
+        issuer.rotate_key().unwrap();
+        issuer.retire_previous_keys();
+        let token = issuer.token();
+        assert!(!accepts(&authority, &token));
+
+        std::fs::write(&path, serde_json::to_vec(issuer.jwks()).unwrap()).unwrap();
+        wait_until("the rotated key has been reloaded", || {
+            accepts(&authority, &token)
+        })
+        .await;
+        assert!(handle.last_error().is_none());
+
+        std::fs::write(&path, "not json").unwrap();
+        wait_until("the invalid file has been reported", || {
+            handle.last_error().is_some()
+        })
+        .await;
+        assert!(accepts(&authority, &token));
+
+        handle.stop().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "reqwest", feature = "rsa"))]
+mod discovery_tests {
+    use aliri::{jwa, test_util::MockIssuer};
+    use aliri_mock_server::{Endpoint, MockServer};
+
+    use super::*;
+    use crate::oauth2::BasicClaimsWithScope;
+
+    fn start() -> MockServer {
+        MockServer::start(MockIssuer::new(jwa::Algorithm::RS256).unwrap()).unwrap()
+    }
+
+    fn accepts(authority: &Authority, token: &JwtRef) -> bool {
+        authority
+            .verify_token::<BasicClaimsWithScope>(token, &ScopePolicy::allow_any())
+            .is_ok()
+    }
+
+    #[test]
+    fn builds_well_known_urls() {
+        let [oidc, rfc8414] = metadata_urls(&jwt::Issuer::from_static("https://auth.example.com/"));
+        assert_eq!(
+            oidc,
+            "https://auth.example.com/.well-known/openid-configuration"
+        );
+        assert_eq!(
+            rfc8414,
+            "https://auth.example.com/.well-known/oauth-authorization-server"
+        );
+
+        let [oidc, rfc8414] = metadata_urls(&jwt::Issuer::from_static(
+            "https://auth.example.com/tenant/1",
+        ));
+        assert_eq!(
+            oidc,
+            "https://auth.example.com/tenant/1/.well-known/openid-configuration"
+        );
+        assert_eq!(
+            rfc8414,
+            "https://auth.example.com/.well-known/oauth-authorization-server/tenant/1"
+        );
+    }
+
+    #[tokio::test]
+    async fn seeds_issuer_and_algorithms_from_metadata() {
+        let server = start();
+        let issuer = server.issuer();
+        let validator =
+            jwt::CoreValidator::default().add_allowed_audience(issuer.audience().to_owned());
+
+        let authority =
+            Authority::from_issuer(jwt::Issuer::new(server.url().to_owned()), validator)
+                .await
+                .unwrap();
+
+        assert!(accepts(&authority, &issuer.token()));
+        assert!(!accepts(
+            &authority,
+            &issuer
+                .clone()
+                .with_issuer("https://impostor.example.com/")
+                .token()
+        ));
+        assert!(!accepts(&authority, &issuer.wrong_audience_token()));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_rfc8414_metadata() {
+        let server = start();
+        server.fail_next(Endpoint::Discovery, 1);
+
+        let authority = Authority::from_issuer(
+            jwt::Issuer::new(server.url().to_owned()),
+            jwt::CoreValidator::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(server.request_count(Endpoint::Discovery), 2);
+        assert!(accepts(&authority, &server.issuer().token()));
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_issuer() {
+        let server = start();
+        let issuer = jwt::Issuer::new(format!("{}/", server.url()));
+
+        let result = Authority::from_issuer(issuer, jwt::CoreValidator::default()).await;
+
+        assert!(matches!(result, Err(DiscoveryError::IssuerMismatch { .. })));
+        assert_eq!(server.request_count(Endpoint::Jwks), 0);
+    }
+
+    #[tokio::test]
+    async fn refresh_rereads_metadata() {
+        let server = start();
+        let authority = Authority::from_issuer(
+            jwt::Issuer::new(server.url().to_owned()),
+            jwt::CoreValidator::default(),
+        )
+        .await
+        .unwrap();
+
+        server.rotate_keys().unwrap();
+        let token = server.issuer().token();
+        assert!(!accepts(&authority, &token));
+
+        authority.refresh().await.unwrap();
+        assert_eq!(server.request_count(Endpoint::Discovery), 2);
+        assert!(accepts(&authority, &token));
+
+        server.fail_next(Endpoint::Discovery, 1);
+        assert!(authority.refresh().await.is_err());
+        assert_eq!(server.request_count(Endpoint::Jwks), 2);
+    }
+
+    #[tokio::test]
+    async fn seeds_updated_validator_from_metadata() {
+        let server = start();
+        let issuer = server.issuer();
+        let authority = Authority::from_issuer(
+            jwt::Issuer::new(server.url().to_owned()),
+            jwt::CoreValidator::default(),
+        )
+        .await
+        .unwrap();
+        assert!(accepts(&authority, &issuer.wrong_audience_token()));
+
+        authority.set_validator(
+            jwt::CoreValidator::default().add_allowed_audience(issuer.audience().to_owned()),
+        );
+        authority.refresh().await.unwrap();
+
+        assert!(accepts(&authority, &issuer.token()));
+        assert!(!accepts(&authority, &issuer.wrong_audience_token()));
+        assert!(!accepts(
+            &authority,
+            &issuer
+                .clone()
+                .with_issuer("https://impostor.example.com/")
+                .token()
+        ));
+    }
+
+    #[tokio::test]
+    async fn records_refresh_outcomes() {
+        use std::sync::atomic::Ordering;
+
+        use super::mock_issuer_tests::TestRecorder;
+
+        let server = start();
+        let authority = Authority::new_from_url(server.jwks_url(), server.validator())
+            .await
+            .unwrap();
+        let recorder = Arc::new(TestRecorder::default());
+        authority.set_metrics_recorder(Arc::clone(&recorder));
+
+        authority.refresh().await.unwrap();
+        server.rotate_keys().unwrap();
+        authority.refresh().await.unwrap();
+        server.fail_next(Endpoint::Jwks, 1);
+        assert!(authority.refresh().await.is_err());
+
+        assert_eq!(
+            *recorder.refreshes.lock().unwrap(),
+            [
+                RefreshOutcome::NotModified,
+                RefreshOutcome::Updated,
+                RefreshOutcome::Failed,
+            ]
+        );
+        assert_eq!(recorder.key_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn merges_additional_jwks_urls() {
+        let primary = start();
+        let additional = start();
+        let authority = Authority::new_from_url(primary.jwks_url(), primary.validator())
+            .await
+            .unwrap();
+        let other = || {
+            additional
+                .issuer()
+                .with_issuer(primary.issuer().issuer().to_owned())
+                .token()
+        };
+
+        assert!(!accepts(&authority, &other()));
+
+        additional.fail_next(Endpoint::Jwks, 1);
+        assert!(authority.add_jwks_url(additional.jwks_url()).await.is_err());
+        assert!(!accepts(&authority, &other()));
+
+        authority.add_jwks_url(additional.jwks_url()).await.unwrap();
+        assert!(accepts(&authority, &other()));
+        assert!(accepts(&authority, &primary.issuer().token()));
+
+        additional.rotate_keys().unwrap();
+        additional.retire_previous_keys();
+        additional.fail_next(Endpoint::Jwks, 1);
+        authority.refresh().await.unwrap();
+        assert!(!accepts(&authority, &other()));
+
+        authority.refresh().await.unwrap();
+        assert!(accepts(&authority, &other()));
+        assert_eq!(primary.request_count(Endpoint::Jwks), 3);
+    }
+
+    #[cfg(feature = "tokio")]
+    mod refetch {
+        use std::time::Duration;
+
+        use super::*;
+
+        async fn authority(server: &MockServer, policy: RefetchPolicy) -> Authority {
+            let authority = Authority::new_from_url(server.jwks_url(), server.validator())
+                .await
+                .unwrap();
+            authority.set_refetch_policy(policy);
+            authority
+        }
+
+        async fn accepts_async(authority: &Authority, token: &JwtRef) -> bool {
+            authority
+                .verify_token_async::<BasicClaimsWithScope>(token, &ScopePolicy::allow_any())
+                .await
+                .is_ok()
+        }
+
+        #[tokio::test]
+        async fn refetches_on_unknown_key() {
+            let server = start();
+            let authority = authority(&server, RefetchPolicy::default()).await;
+
+            server.rotate_keys().unwrap();
+            let token = server.issuer().token();
+
+            assert!(!accepts(&authority, &token));
+            assert!(accepts_async(&authority, &token).await);
+            assert!(accepts(&authority, &token));
+            assert_eq!(server.request_count(Endpoint::Jwks), 2);
+        }
+
+        #[tokio::test]
+        async fn concurrent_misses_share_one_refetch() {
+            let server = start();
+            let authority = authority(&server, RefetchPolicy::default()).await;
+
+            server.rotate_keys().unwrap();
+            server.set_delay(Endpoint::Jwks, Duration::from_millis(100));
+            let token = server.issuer().token();
+
+            let tasks: Vec<_> = (0..10)
+                .map(|_| {
+                    let authority = authority.clone();
+                    let token = token.clone();
+                    tokio::spawn(async move { accepts_async(&authority, &token).await })
+                })
+                .collect();
+
+            for task in tasks {
+                assert!(task.await.unwrap());
+            }
+            assert_eq!(server.request_count(Endpoint::Jwks), 2);
+        }
+
+        #[tokio::test]
+        async fn remembers_keys_missing_after_refetch() {
+            let server = start();
+            let policy = RefetchPolicy::default().with_min_interval(Duration::ZERO);
+            let authority = authority(&server, policy).await;
+
+            let other = MockIssuer::new(jwa::Algorithm::RS256)
+                .unwrap()
+                .with_issuer(server.url().to_owned());
+            let token = other.token();
+
+            assert!(!accepts_async(&authority, &token).await);
+            assert!(!accepts_async(&authority, &token).await);
+            assert_eq!(server.request_count(Endpoint::Jwks), 2);
+
+            server.rotate_keys().unwrap();
+            assert!(accepts_async(&authority, &server.issuer().token()).await);
+            assert_eq!(server.request_count(Endpoint::Jwks), 3);
+        }
+
+        #[tokio::test]
+        async fn does_not_remember_keys_after_failed_refetch() {
+            let server = start();
+            let policy = RefetchPolicy::default().with_min_interval(Duration::ZERO);
+            let authority = authority(&server, policy).await;
+
+            server.rotate_keys().unwrap();
+            let token = server.issuer().token();
+
+            server.fail_next(Endpoint::Jwks, 1);
+            assert!(!accepts_async(&authority, &token).await);
+            assert!(accepts_async(&authority, &token).await);
+            assert_eq!(server.request_count(Endpoint::Jwks), 3);
+        }
+
+        #[tokio::test]
+        async fn limits_refetch_rate() {
+            let server = start();
+            let policy = RefetchPolicy::default()
+                .with_min_interval(Duration::from_secs(60))
+                .with_negative_ttl(Duration::ZERO);
+            let authority = authority(&server, policy).await;
+
+            server.rotate_keys().unwrap();
+            assert!(accepts_async(&authority, &server.issuer().token()).await);
+
+            server.rotate_keys().unwrap();
+            assert!(!accepts_async(&authority, &server.issuer().token()).await);
+            assert_eq!(server.request_count(Endpoint::Jwks), 2);
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    mod refresher {
+        use std::time::Duration;
+
+        use aliri_tokens::backoff::ErrorBackoffConfig;
+
+        use super::*;
+        use crate::authority::test_util::wait_until;
+
+        async fn authority(server: &MockServer) -> Authority {
+            Authority::new_from_url(server.jwks_url(), server.validator())
+                .await
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn honors_max_age_within_bounds() {
+            let server = start();
+            server.set_jwks_max_age(Some(Duration::ZERO));
+            let authority = authority(&server).await;
+
+            let config = RefreshConfig::default().with_min_interval(Duration::from_millis(50));
+            let handle = authority.spawn_refresh_with(config);
+
+            wait_until("the JWKS has been refreshed three times", || {
+                server.request_count(Endpoint::Jwks) >= 4
+            })
+            .await;
+            assert!(handle.last_success().is_some());
+            assert!(handle.last_error().is_none());
+
+            handle.stop().await;
+            let stopped_at = server.request_count(Endpoint::Jwks);
+            tokio::task::yield_now().await;
+            assert_eq!(server.request_count(Endpoint::Jwks), stopped_at);
+        }
+
+        #[tokio::test]
+        async fn uses_default_interval_without_caching_headers() {
+            let server = start();
+            let authority = authority(&server).await;
+
+            let config = RefreshConfig::default().with_min_interval(Duration::from_millis(50));
+            let handle = authority.spawn_refresh_with(config);
+
+            wait_until("the JWKS has been refreshed", || {
+                handle.last_success().is_some()
+            })
+            .await;
+
+            // Long enough for several refreshes at the minimum interval
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            assert_eq!(server.request_count(Endpoint::Jwks), 2);
+            handle.stop().await;
+        }
+
+        #[tokio::test]
+        async fn backs_off_and_reports_failures() {
+            let server = start();
+            let authority = authority(&server).await;
+
+            server.fail_next(Endpoint::Jwks, usize::MAX);
+            let config = RefreshConfig::fixed(Duration::from_secs(60)).with_backoff(
+                ErrorBackoffConfig::new(Duration::from_millis(10), Duration::from_millis(20), 2),
+            );
+            let handle = authority.spawn_refresh_with(config);
+
+            wait_until("the refresh has failed twice", || {
+                handle.consecutive_failures() >= 2
+            })
+            .await;
+            assert!(handle.last_success().is_none());
+            assert!(handle.last_error().is_some());
+
+            server.fail_next(Endpoint::Jwks, 0);
+            wait_until("the JWKS has been refreshed", || {
+                handle.last_success().is_some()
+            })
+            .await;
+            assert!(handle.last_error().is_none());
+            assert_eq!(handle.consecutive_failures(), 0);
+
+            handle.stop().await;
+        }
+    }
+
+    #[cfg(feature = "file")]
+    mod persist {
+        use std::path::PathBuf;
+
+        use serde_json::Value;
+
+        use super::*;
+
+        fn cache_path(name: &str) -> PathBuf {
+            let path = std::env::temp_dir().join(format!(
+                "aliri_oauth2_{}_{}.json",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            path
+        }
+
+        async fn authority(
+            server: &MockServer,
+            path: &PathBuf,
+        ) -> Result<Authority, reqwest::Error> {
+            Authority::new_from_url_with_cache(
+                server.jwks_url(),
+                server.validator(),
+                JwksCacheFile::new(path),
+            )
+            .await
+        }
+
+        fn persisted(path: &PathBuf) -> Value {
+            serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+        }
+
+        #[tokio::test]
+        async fn falls_back_to_persisted_jwks() {
+            let server = start();
+            let path = cache_path("falls_back");
+
+            assert!(authority(&server, &path).await.is_ok());
+            assert_eq!(persisted(&path)["jwks_url"], server.jwks_url());
+            assert_eq!(persisted(&path)["etag"], "\"1\"");
+
+            server.fail_next(Endpoint::Jwks, 1);
+            let authority = authority(&server, &path).await.unwrap();
+            assert!(accepts(&authority, &server.issuer().token()));
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[tokio::test]
+        async fn fails_without_persisted_jwks() {
+            let server = start();
+            let path = cache_path("missing");
+
+            server.fail_next(Endpoint::Jwks, 1);
+            assert!(authority(&server, &path).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn ignores_stale_persisted_jwks() {
+            let server = start();
+            let path = cache_path("stale");
+
+            assert!(authority(&server, &path).await.is_ok());
+            let mut stale = persisted(&path);
+            stale["fetched_at"] = 0.into();
+            std::fs::write(&path, serde_json::to_vec(&stale).unwrap()).unwrap();
+
+            server.fail_next(Endpoint::Jwks, 1);
+            assert!(authority(&server, &path).await.is_err());
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[tokio::test]
+        async fn ignores_jwks_persisted_from_another_url() {
+            let server = start();
+            let path = cache_path("other_url");
+
+            assert!(authority(&server, &path).await.is_ok());
+
+            server.fail_next(Endpoint::Jwks, 1);
+            let result = Authority::new_from_url_with_cache(
+                format!("{}?tenant=other", server.jwks_url()),
+                server.validator(),
+                JwksCacheFile::new(&path),
+            )
+            .await;
+            assert!(result.is_err());
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[tokio::test]
+        async fn persists_refreshed_jwks() {
+            let server = start();
+            let path = cache_path("refreshed");
+            let authority = authority(&server, &path).await.unwrap();
+
+            server.rotate_keys().unwrap();
+            authority.refresh().await.unwrap();
+
+            let persisted = persisted(&path);
+            assert_eq!(persisted["jwks"]["keys"].as_array().unwrap().len(), 2);
+            assert_eq!(persisted["etag"], "\"2\"");
+
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn computes_freshness_lifetime() {
+        use reqwest::header::HeaderMap;
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let headers = |pairs: &[(header::HeaderName, &'static str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.append(name, HeaderValue::from_static(value));
+            }
+            headers
+        };
+
+        assert_eq!(freshness_lifetime(&headers(&[]), now), None);
+        assert_eq!(
+            freshness_lifetime(
+                &headers(&[(header::CACHE_CONTROL, "public, max-age=600")]),
+                now
+            ),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(
+            freshness_lifetime(
+                &headers(&[(header::CACHE_CONTROL, "no-cache, max-age=600")]),
+                now
+            ),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            freshness_lifetime(
+                &headers(&[
+                    (header::DATE, "Sun, 06 Nov 1994 08:49:37 GMT"),
+                    (header::EXPIRES, "Sun, 06 Nov 1994 09:49:37 GMT"),
+                ]),
+                now
+            ),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(
+            freshness_lifetime(
+                &headers(&[
+                    (header::CACHE_CONTROL, "max-age=60"),
+                    (header::EXPIRES, "Sun, 06 Nov 1994 09:49:37 GMT"),
+                ]),
+                now
+            ),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            freshness_lifetime(
+                &headers(&[(header::EXPIRES, "Sun, 06 Nov 1994 09:49:37 GMT")]),
+                now
+            ),
+            Some(Duration::ZERO)
+        );
+    }
+}
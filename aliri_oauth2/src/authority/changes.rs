@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use aliri::{jwk, Jwks};
+use tokio::sync::watch;
+
+/// A change to the JWKS of an [`Authority`][super::Authority]
+///
+/// Keys are identified by their key ID, and each key ID is reported at most
+/// once. A key that is replaced by a different key with the same key ID is
+/// reported as both removed and added. Keys without a key ID are not reported
+/// individually, but changes to them are still reported through the new key
+/// set.
+#[derive(Clone, Debug)]
+pub struct JwksChange {
+    added: Vec<jwk::KeyId>,
+    removed: Vec<jwk::KeyId>,
+    jwks: Arc<Jwks>,
+}
+
+impl JwksChange {
+    fn between(previous: &Jwks, jwks: Arc<Jwks>) -> Self {
+        let differences = |from: &Jwks, other: &Jwks| {
+            let mut kids = Vec::<jwk::KeyId>::new();
+            for key in from.keys() {
+                if let Some(kid) = key.key_id() {
+                    if !other.keys().contains(key) && !kids.iter().any(|k| k == kid) {
+                        kids.push(kid.to_owned());
+                    }
+                }
+            }
+            kids
+        };
+
+        let added = differences(&jwks, previous);
+        let removed = differences(previous, &jwks);
+
+        Self {
+            added,
+            removed,
+            jwks,
+        }
+    }
+
+    /// The key IDs of keys that were added
+    #[must_use]
+    pub fn added(&self) -> &[jwk::KeyId] {
+        &self.added
+    }
+
+    /// The key IDs of keys that were removed
+    #[must_use]
+    pub fn removed(&self) -> &[jwk::KeyId] {
+        &self.removed
+    }
+
+    /// The new key set
+    #[must_use]
+    pub fn jwks(&self) -> &Jwks {
+        &self.jwks
+    }
+}
+
+/// A subscription to changes to the JWKS of an [`Authority`][super::Authority]
+///
+/// Changes are reported relative to the key set that the subscriber last
+/// observed, so no keys are missed if the JWKS changes several times before
+/// the subscriber is polled.
+#[derive(Debug)]
+pub struct JwksSubscriber {
+    receiver: watch::Receiver<Arc<Jwks>>,
+    current: Arc<Jwks>,
+}
+
+impl JwksSubscriber {
+    pub(super) fn new(mut receiver: watch::Receiver<Arc<Jwks>>) -> Self {
+        let current = Arc::clone(&receiver.borrow_and_update());
+        Self { receiver, current }
+    }
+
+    /// The key set last observed by this subscriber
+    #[must_use]
+    pub fn jwks(&self) -> &Jwks {
+        &self.current
+    }
+
+    /// Waits for the JWKS to change
+    ///
+    /// Replacing the JWKS with the same keys, even in a different order or
+    /// with duplicates, is not reported as a change. Returns `None` once the
+    /// authority has been dropped.
+    pub async fn changed(&mut self) -> Option<JwksChange> {
+        loop {
+            self.receiver.changed().await.ok()?;
+
+            let jwks = Arc::clone(&self.receiver.borrow_and_update());
+            if !same_keys(&jwks, &self.current) {
+                let previous = std::mem::replace(&mut self.current, Arc::clone(&jwks));
+                return Some(JwksChange::between(&previous, jwks));
+            }
+        }
+    }
+}
+
+/// Whether the key sets contain the same keys, regardless of order or
+/// duplicates
+fn same_keys(a: &Jwks, b: &Jwks) -> bool {
+    let contains_all = |a: &Jwks, b: &Jwks| a.keys().iter().all(|key| b.keys().contains(key));
+    contains_all(a, b) && contains_all(b, a)
+}
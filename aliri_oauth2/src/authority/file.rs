@@ -0,0 +1,209 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use aliri::{Jwk, Jwks};
+use serde::{de::IgnoredAny, Deserialize};
+use thiserror::Error;
+
+use super::{refresher::Shared, Authority};
+use crate::metrics::RefreshOutcome;
+
+/// An error loading a JWKS from a local file or directory
+#[derive(Debug, Error)]
+pub enum JwksFileError {
+    /// Unable to read the file or directory
+    #[error("unable to read `{}`", path.display())]
+    Io {
+        /// The path that could not be read
+        path: PathBuf,
+        /// The underlying error
+        #[source]
+        source: io::Error,
+    },
+
+    /// The file does not hold a valid JWKS or JWK
+    #[error("`{}` is not a valid JWKS or JWK", path.display())]
+    Invalid {
+        /// The path of the invalid file
+        path: PathBuf,
+        /// The underlying error
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// No keys were found
+    #[error("no keys found in `{}`", path.display())]
+    Empty {
+        /// The path of the file or directory
+        path: PathBuf,
+    },
+}
+
+/// A JWKS held in a local file, or in a directory of JWK files
+///
+/// A file may hold either a JWKS or a single JWK. For a directory, the keys
+/// from every `*.json` file in the directory are merged, in file name order.
+/// Hidden files are skipped, so a Kubernetes ConfigMap or Secret can be
+/// mounted as the directory.
+///
+/// The JWKS is reloaded by polling, and is only replaced when the keys
+/// change. If the file or directory cannot be read, holds an invalid key, or
+/// holds no keys at all, the last good JWKS continues to be used.
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct JwksFile {
+    path: PathBuf,
+    poll_interval: Duration,
+}
+
+impl JwksFile {
+    /// Loads the JWKS from the given file or directory
+    ///
+    /// By default, the file or directory is polled for changes every
+    /// 10 seconds.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            poll_interval: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets how often the file or directory is polled for changes
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// The path to the file or directory
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How often the file or directory is polled for changes
+    #[must_use]
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Reads the JWKS from the file or directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file or directory cannot be read, if any file
+    /// holds an invalid JWKS or JWK, or if no keys are found.
+    pub async fn load(&self) -> Result<Jwks, JwksFileError> {
+        let io_error = |source| JwksFileError::Io {
+            path: self.path.clone(),
+            source,
+        };
+
+        let metadata = tokio::fs::metadata(&self.path).await.map_err(io_error)?;
+
+        let mut jwks = Jwks::default();
+        if metadata.is_dir() {
+            for path in json_files(&self.path).await.map_err(io_error)? {
+                load_file(&path, &mut jwks).await?;
+            }
+        } else {
+            load_file(&self.path, &mut jwks).await?;
+        }
+
+        if jwks.keys().is_empty() {
+            return Err(JwksFileError::Empty {
+                path: self.path.clone(),
+            });
+        }
+
+        Ok(jwks)
+    }
+}
+
+/// The non-hidden `*.json` files in the directory, sorted by file name
+///
+/// Symbolic links are followed, as used by Kubernetes volume mounts.
+async fn json_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let mut files = Vec::new();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_json = path.extension().is_some_and(|ext| ext == "json");
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+        if is_json && !is_hidden && tokio::fs::metadata(&path).await?.is_file() {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+async fn load_file(path: &Path, jwks: &mut Jwks) -> Result<(), JwksFileError> {
+    let contents = tokio::fs::read(path)
+        .await
+        .map_err(|source| JwksFileError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+
+    let invalid = |source| JwksFileError::Invalid {
+        path: path.to_owned(),
+        source,
+    };
+
+    #[derive(Deserialize)]
+    struct Shape {
+        keys: Option<IgnoredAny>,
+    }
+
+    let shape: Shape = serde_json::from_slice(&contents).map_err(invalid)?;
+    if shape.keys.is_some() {
+        let set: Jwks = serde_json::from_slice(&contents).map_err(invalid)?;
+        for key in set.keys() {
+            jwks.add_key(key.clone());
+        }
+    } else {
+        jwks.add_key(serde_json::from_slice::<Jwk>(&contents).map_err(invalid)?);
+    }
+
+    Ok(())
+}
+
+pub(super) async fn run(authority: Authority, file: JwksFile, shared: Arc<Shared>) {
+    loop {
+        let outcome = match file.load().await {
+            Ok(jwks) => {
+                shared.record_success();
+
+                if jwks == authority.inner.data.load().jwks {
+                    RefreshOutcome::NotModified
+                } else {
+                    authority.set_jwks(jwks);
+                    tracing::info!(path = %file.path.display(), "JWKS reloaded");
+                    RefreshOutcome::Updated
+                }
+            }
+            Err(err) => {
+                let error: &dyn std::error::Error = &err;
+                tracing::warn!(error, "JWKS reload failed; keeping the current JWKS");
+                shared.record_failure(&err);
+                RefreshOutcome::Failed
+            }
+        };
+
+        authority.record_refresh(outcome);
+
+        if shared.stopped_within(file.poll_interval).await {
+            tracing::debug!("JWKS reloader stopped");
+            break;
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use aliri::{error::ClaimsRejected, jwt};
+
+/// Additional validations applied to a single verification
+///
+/// Overrides are checked along with the validator of the
+/// [`Authority`][super::Authority], and can only make verification stricter.
+/// This allows a single authority to serve several APIs that each require
+/// their own audience.
+#[derive(Clone, Debug, Default)]
+#[must_use]
+pub struct VerifyOverrides {
+    required_audiences: Vec<jwt::Audience>,
+}
+
+impl VerifyOverrides {
+    /// Constructs overrides that apply no additional validations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires that the token be intended for the given audience
+    ///
+    /// The token must name this audience in its `aud` claim, in addition to
+    /// satisfying the allowed audiences of the validator. If called more than
+    /// once, the token must name every required audience.
+    pub fn require_audience(self, audience: jwt::Audience) -> Self {
+        let mut this = self;
+        this.required_audiences.push(audience);
+        this
+    }
+
+    /// The audiences that the token must name
+    #[must_use]
+    pub fn required_audiences(&self) -> &[jwt::Audience] {
+        &self.required_audiences
+    }
+
+    pub(super) fn validate<T: jwt::CoreClaims>(&self, claims: &T) -> Result<(), ClaimsRejected> {
+        if self.required_audiences.is_empty() {
+            return Ok(());
+        }
+
+        if claims.aud().is_empty() {
+            return Err(ClaimsRejected::MissingRequiredClaim("aud"));
+        }
+
+        let names = |required: &jwt::Audience| claims.aud().iter().any(|aud| aud == required);
+        if !self.required_audiences.iter().all(names) {
+            return Err(ClaimsRejected::InvalidAudience);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use aliri::Jwks;
+use reqwest::header::HeaderValue;
+use serde::{Deserialize, Serialize};
+
+use super::VolatileData;
+
+/// A local file holding the last JWKS successfully fetched from the remote URL
+///
+/// Persisting the JWKS allows an authority to start even when the identity
+/// provider is unreachable, so long as the persisted JWKS is not older than
+/// the configured maximum staleness. The staleness is measured from the last
+/// time the remote URL confirmed the JWKS, including responses indicating
+/// that the JWKS was not modified.
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct JwksCacheFile {
+    path: PathBuf,
+    max_staleness: Duration,
+}
+
+impl JwksCacheFile {
+    /// Persists the JWKS to the given path
+    ///
+    /// By default, a persisted JWKS is used for up to one day.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_staleness: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Sets the maximum age of a persisted JWKS that will be used on startup
+    pub fn with_max_staleness(self, max_staleness: Duration) -> Self {
+        Self {
+            max_staleness,
+            ..self
+        }
+    }
+
+    /// The path to the file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The maximum age of a persisted JWKS that will be used on startup
+    #[must_use]
+    pub fn max_staleness(&self) -> Duration {
+        self.max_staleness
+    }
+
+    /// Writes the JWKS to the file, logging rather than failing on errors
+    pub(super) async fn store(&self, jwks_url: &str, data: &VolatileData) {
+        if let Err(err) = self.write(jwks_url, data).await {
+            let error: &dyn std::error::Error = &err;
+            tracing::warn!(error, path = %self.path.display(), "unable to persist JWKS");
+        }
+    }
+
+    /// Reads the JWKS from the file if it was fetched from the same URL and
+    /// is fresh enough to use
+    pub(super) async fn load(&self, jwks_url: &str) -> Option<VolatileData> {
+        let persisted = match self.read().await {
+            Ok(persisted) => persisted,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                tracing::debug!(path = %self.path.display(), "no persisted JWKS found");
+                return None;
+            }
+            Err(err) => {
+                let error: &dyn std::error::Error = &err;
+                tracing::warn!(error, path = %self.path.display(), "unable to read persisted JWKS");
+                return None;
+            }
+        };
+
+        if persisted.jwks_url != jwks_url {
+            tracing::warn!(
+                path = %self.path.display(),
+                persisted.url = %persisted.jwks_url,
+                "persisted JWKS was fetched from a different URL; ignoring"
+            );
+            return None;
+        }
+
+        let fetched_at = UNIX_EPOCH + Duration::from_secs(persisted.fetched_at);
+        let age = SystemTime::now()
+            .duration_since(fetched_at)
+            .unwrap_or_default();
+        if age > self.max_staleness {
+            tracing::warn!(
+                path = %self.path.display(),
+                age.secs = age.as_secs(),
+                "persisted JWKS is too stale; ignoring"
+            );
+            return None;
+        }
+
+        Some(VolatileData {
+            jwks: persisted.jwks,
+            etag: persisted.etag.and_then(|v| HeaderValue::from_str(&v).ok()),
+            last_modified: persisted
+                .last_modified
+                .and_then(|v| HeaderValue::from_str(&v).ok()),
+        })
+    }
+
+    async fn read(&self) -> io::Result<Persisted> {
+        let data = tokio::fs::read(&self.path).await?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Writes to a temporary file first so that a partially written file is
+    /// never read
+    async fn write(&self, jwks_url: &str, data: &VolatileData) -> io::Result<()> {
+        let fetched_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let persisted = Persisted {
+            jwks_url: jwks_url.to_owned(),
+            fetched_at,
+            etag: header_string(data.etag.as_ref()),
+            last_modified: header_string(data.last_modified.as_ref()),
+            jwks: data.jwks.clone(),
+        };
+
+        let contents = serde_json::to_vec_pretty(&persisted)?;
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        tokio::fs::write(&tmp, contents).await?;
+        tokio::fs::rename(&tmp, &self.path).await
+    }
+}
+
+fn header_string(value: Option<&HeaderValue>) -> Option<String> {
+    value.and_then(|v| v.to_str().ok()).map(ToOwned::to_owned)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Persisted {
+    jwks_url: String,
+    fetched_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    jwks: Jwks,
+}
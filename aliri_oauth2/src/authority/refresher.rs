@@ -0,0 +1,230 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+use aliri_tokens::backoff::{ErrorBackoffConfig, ErrorBackoffHandler};
+use rand::Rng;
+use tokio::{sync::Notify, task::JoinHandle};
+
+use super::Authority;
+
+/// Configuration for refreshing the JWKS in the background
+///
+/// After a successful refresh, the next refresh is scheduled according to the
+/// `Cache-Control: max-age` or `Expires` headers on the JWKS response, bounded
+/// by the minimum and maximum intervals. If the response has no caching
+/// headers, the default interval is used.
+///
+/// After a failed refresh, the next attempt is delayed according to the error
+/// backoff configuration, with jitter applied so that many instances do not
+/// retry in lockstep.
+#[derive(Debug)]
+#[must_use]
+pub struct RefreshConfig {
+    min_interval: Duration,
+    max_interval: Duration,
+    default_interval: Duration,
+    backoff: ErrorBackoffConfig,
+}
+
+impl Default for RefreshConfig {
+    /// Default refresh configuration
+    ///
+    /// Refreshes at most once a minute and at least once a day, defaulting to
+    /// once an hour, with the default error backoff configuration.
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(60),
+            max_interval: Duration::from_secs(24 * 60 * 60),
+            default_interval: Duration::from_secs(60 * 60),
+            backoff: ErrorBackoffConfig::default(),
+        }
+    }
+}
+
+impl RefreshConfig {
+    /// Refreshes on a fixed interval, ignoring caching headers
+    ///
+    /// Failed refreshes are still retried with backoff, but never wait longer
+    /// than the interval.
+    pub fn fixed(interval: Duration) -> Self {
+        Self {
+            min_interval: interval,
+            max_interval: interval,
+            default_interval: interval,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the minimum interval between successful refreshes
+    pub fn with_min_interval(self, min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            ..self
+        }
+    }
+
+    /// Sets the maximum interval between refreshes
+    pub fn with_max_interval(self, max_interval: Duration) -> Self {
+        Self {
+            max_interval,
+            ..self
+        }
+    }
+
+    /// Sets the interval used when the JWKS response has no caching headers
+    pub fn with_default_interval(self, default_interval: Duration) -> Self {
+        Self {
+            default_interval,
+            ..self
+        }
+    }
+
+    /// Sets the backoff applied after failed refreshes
+    pub fn with_backoff(self, backoff: ErrorBackoffConfig) -> Self {
+        Self { backoff, ..self }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Status {
+    last_success: Option<SystemTime>,
+    last_error: Option<String>,
+    consecutive_failures: u32,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Shared {
+    status: Mutex<Status>,
+    stop: Notify,
+}
+
+impl Shared {
+    fn status(&self) -> MutexGuard<'_, Status> {
+        self.status.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(super) fn record_success(&self) {
+        let mut status = self.status();
+        status.last_success = Some(SystemTime::now());
+        status.last_error = None;
+        status.consecutive_failures = 0;
+    }
+
+    pub(super) fn record_failure(&self, err: &dyn fmt::Display) {
+        let mut status = self.status();
+        status.last_error = Some(err.to_string());
+        status.consecutive_failures += 1;
+    }
+
+    /// Waits for the delay to elapse, returning `true` if the task was
+    /// stopped in the meantime
+    pub(super) async fn stopped_within(&self, delay: Duration) -> bool {
+        tokio::time::timeout(delay, self.stop.notified())
+            .await
+            .is_ok()
+    }
+}
+
+/// A handle to a background task refreshing the JWKS of an [`Authority`]
+///
+/// The handle reports on the health of the refresher, which is useful for
+/// readiness probes that should fail when the keys are stale. Dropping the
+/// handle does not stop the refresher; use [`stop()`][RefreshHandle::stop()]
+/// to stop it.
+#[derive(Debug)]
+pub struct RefreshHandle {
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+impl RefreshHandle {
+    pub(super) fn spawn(authority: Authority, config: RefreshConfig) -> Self {
+        Self::spawn_task(|shared| run(authority, config, shared))
+    }
+
+    pub(super) fn spawn_task<F, Fut>(task: F) -> Self
+    where
+        F: FnOnce(Arc<Shared>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let shared = Arc::new(Shared::default());
+        let task = tokio::spawn(task(Arc::clone(&shared)));
+        Self { shared, task }
+    }
+
+    /// The time of the last successful refresh
+    #[must_use]
+    pub fn last_success(&self) -> Option<SystemTime> {
+        self.shared.status().last_success
+    }
+
+    /// The error from the last refresh, if it failed
+    ///
+    /// Cleared after the next successful refresh.
+    #[must_use]
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.status().last_error.clone()
+    }
+
+    /// The number of refreshes that have failed since the last success
+    #[must_use]
+    pub fn consecutive_failures(&self) -> u32 {
+        self.shared.status().consecutive_failures
+    }
+
+    /// Stops the refresher, waiting for any refresh in progress to complete
+    pub async fn stop(self) {
+        self.shared.stop.notify_one();
+        if let Err(err) = self.task.await {
+            if err.is_panic() {
+                tracing::error!("JWKS refresher panicked");
+            }
+        }
+    }
+}
+
+async fn run(authority: Authority, config: RefreshConfig, shared: Arc<Shared>) {
+    let RefreshConfig {
+        min_interval,
+        max_interval,
+        default_interval,
+        backoff,
+    } = config;
+    let mut backoff = ErrorBackoffHandler::new(backoff);
+
+    loop {
+        let delay = match authority.refresh_with_lifetime().await {
+            Ok(lifetime) => {
+                backoff.success();
+                shared.record_success();
+
+                lifetime
+                    .unwrap_or(default_interval)
+                    .max(min_interval)
+                    .min(max_interval)
+            }
+            Err(err) => {
+                shared.record_failure(&err);
+
+                jitter(backoff.error()).min(max_interval)
+            }
+        };
+
+        tracing::debug!(delay = ?delay, "next JWKS refresh scheduled");
+
+        if shared.stopped_within(delay).await {
+            tracing::debug!("JWKS refresher stopped");
+            break;
+        }
+    }
+}
+
+/// Applies "equal jitter", keeping at least half of the delay
+fn jitter(delay: Duration) -> Duration {
+    let half = delay / 2;
+    half + half.mul_f64(rand::thread_rng().gen::<f64>())
+}
@@ -0,0 +1,72 @@
+#[cfg(feature = "reqwest")]
+use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use aliri::{jwk, Jwks};
+use arc_swap::ArcSwap;
+#[cfg(feature = "reqwest")]
+use reqwest::Client;
+
+#[cfg(feature = "reqwest")]
+use super::VolatileData;
+
+/// The key sources merged with the primary source of an authority
+#[derive(Debug, Default)]
+pub(super) struct KeySources {
+    pub(super) pinned: ArcSwap<Jwks>,
+    #[cfg(feature = "reqwest")]
+    pub(super) additional: ArcSwap<Vec<Arc<AdditionalJwks>>>,
+    merging: Mutex<()>,
+}
+
+impl KeySources {
+    /// Serializes merges, so that a merge cannot overwrite a later change to
+    /// another source
+    pub(super) fn lock(&self) -> MutexGuard<'_, ()> {
+        self.merging.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// An additional remote JWKS, refreshed along with the primary source
+#[cfg(feature = "reqwest")]
+#[derive(Debug)]
+pub(super) struct AdditionalJwks {
+    pub(super) url: String,
+    pub(super) client: Client,
+    pub(super) data: ArcSwap<VolatileData>,
+}
+
+/// Merges key sets in order of precedence
+///
+/// A key ID in a key set shadows all keys with the same key ID in later key
+/// sets. Keys with the same key ID within a single key set are all kept, as a
+/// key may be listed once for each algorithm it supports. Keys without a key
+/// ID are kept unless an identical key has already been merged.
+pub(super) fn merge<'a>(sets: impl IntoIterator<Item = &'a Jwks>) -> Jwks {
+    let mut merged = Jwks::default();
+    let mut shadowed = HashSet::<jwk::KeyId>::new();
+
+    for set in sets {
+        for key in set.keys() {
+            match key.key_id() {
+                Some(kid) if shadowed.contains(kid) => {
+                    tracing::debug!(jwk.kid = %kid, "ignoring key shadowed by another key source");
+                }
+                None if merged.keys().contains(key) => {}
+                _ => merged.add_key(key.clone()),
+            }
+        }
+
+        shadowed.extend(
+            set.keys()
+                .iter()
+                .filter_map(|key| key.key_id())
+                .map(ToOwned::to_owned),
+        );
+    }
+
+    merged
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use aliri::{
+    error::{JwtVerifyError, MalformedJwtPayload},
+    jwt, JwtRef,
+};
+use aliri_base64::Base64Url;
+use aliri_clock::{Clock, UnixTime};
+use aliri_traits::Policy;
+use serde::Deserialize;
+
+use super::{AuthorityError, VerifyOverrides};
+use crate::{oauth2::HasScope, ScopePolicy};
+
+type TokenHash = [u8; 32];
+
+/// A bounded cache of tokens that have been verified against the current JWKS
+///
+/// Only the signature check is skipped on a hit. The cached claims are
+/// deserialized and validated again, so time-based claims such as `exp` are
+/// always checked against the clock, changes to the validator and per-call
+/// overrides are always honored, and the scope policy is always evaluated.
+#[derive(Debug)]
+pub(super) struct TokenCache {
+    capacity: usize,
+    clock: CacheClock,
+    entries: Mutex<HashMap<TokenHash, Arc<CachedToken>>>,
+}
+
+/// The clock used to check the times in cached tokens
+#[derive(Debug)]
+struct CacheClock(Box<dyn Clock + Send + Sync>);
+
+impl Clock for CacheClock {
+    #[inline]
+    fn now(&self) -> UnixTime {
+        self.0.now()
+    }
+}
+
+#[derive(Debug)]
+struct CachedToken {
+    header: jwt::BasicHeaders,
+    payload: Vec<u8>,
+    exp: Option<UnixTime>,
+}
+
+impl TokenCache {
+    pub(super) fn new<C>(capacity: usize, clock: C) -> Self
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        Self {
+            capacity,
+            clock: CacheClock(Box::new(clock)),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<TokenHash, Arc<CachedToken>>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Verifies the token from the cache, if present
+    pub(super) fn verify<T>(
+        &self,
+        token: &JwtRef,
+        validator: &jwt::CoreValidator,
+        overrides: &VerifyOverrides,
+        policy: &ScopePolicy,
+    ) -> Option<Result<T, AuthorityError>>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let cached = self.entries().get(&hash(token)).cloned()?;
+        tracing::trace!("using cached token verification");
+        Some(cached.verify(validator, &self.clock, overrides, policy))
+    }
+
+    /// Caches a token that has been verified
+    ///
+    /// The `is_current` check is made while holding the lock used by
+    /// [`clear()`][TokenCache::clear()], so that a token verified against a
+    /// JWKS that has since been replaced is never cached.
+    pub(super) fn insert(
+        &self,
+        token: &JwtRef,
+        header: jwt::BasicHeaders,
+        payload: &str,
+        exp: Option<UnixTime>,
+        is_current: impl FnOnce() -> bool,
+    ) {
+        let payload = match Base64Url::from_encoded(payload) {
+            Ok(payload) => payload.into_inner(),
+            Err(_) => return,
+        };
+
+        let mut entries = self.entries();
+        if !is_current() {
+            tracing::trace!("JWKS changed during verification; not caching token");
+            return;
+        }
+
+        if entries.len() >= self.capacity {
+            let now = self.clock.now();
+            #[allow(clippy::unnecessary_map_or)]
+            entries.retain(|_, entry| entry.exp.map_or(true, |exp| exp > now));
+        }
+        if entries.len() < self.capacity {
+            entries.insert(
+                hash(token),
+                Arc::new(CachedToken {
+                    header,
+                    payload,
+                    exp,
+                }),
+            );
+        }
+    }
+
+    /// Removes all cached tokens
+    pub(super) fn clear(&self) {
+        self.entries().clear();
+    }
+}
+
+impl CachedToken {
+    fn verify<T>(
+        &self,
+        validator: &jwt::CoreValidator,
+        clock: &CacheClock,
+        overrides: &VerifyOverrides,
+        policy: &ScopePolicy,
+    ) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let claims: T = serde_json::from_slice(&self.payload).map_err(|err| {
+            let source: Box<dyn std::error::Error + Send + Sync + 'static> = Box::new(err);
+            JwtVerifyError::from(MalformedJwtPayload::from(source))
+        })?;
+
+        validator
+            .validate_with_clock(&self.header, &claims, clock)
+            .map_err(JwtVerifyError::from)?;
+        overrides.validate(&claims).map_err(JwtVerifyError::from)?;
+
+        policy.evaluate(claims.scope())?;
+
+        Ok(claims)
+    }
+}
+
+fn hash(token: &JwtRef) -> TokenHash {
+    let digest = ring::digest::digest(&ring::digest::SHA256, token.as_str().as_bytes());
+    let mut hash = TokenHash::default();
+    hash.copy_from_slice(digest.as_ref());
+    hash
+}
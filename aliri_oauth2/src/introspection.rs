@@ -0,0 +1,669 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use aliri::{
+    error::{ClaimsRejected, JwtVerifyError},
+    jwt::{self, CoreClaims},
+    JwtRef,
+};
+use aliri_clock::{Clock, System};
+use aliri_traits::Policy;
+use reqwest::{header, Client};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::{
+    authority::VerifyFuture, oauth2::HasScope, AuthorityError, InsufficientScope, ScopePolicy,
+};
+
+/// An error while authorizing an opaque token through introspection
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+#[derive(Debug, Error)]
+pub enum IntrospectionError {
+    /// Unable to call the introspection endpoint
+    #[error("unable to introspect token")]
+    Request(#[from] reqwest::Error),
+    /// The introspection endpoint returned a response that could not be
+    /// understood or whose claims could not be deserialized
+    #[error("invalid introspection response")]
+    InvalidResponse(#[source] serde_json::Error),
+    /// The introspection endpoint reported that the token is not active
+    #[error("token is not active")]
+    Inactive,
+    /// The token was active, but its audience or issuer was rejected
+    #[error("token claims rejected")]
+    ClaimsRejected(#[from] ClaimsRejected),
+    /// Indicates that, while the token was active, it does not grant the
+    /// level of authorization requested.
+    #[error("access denied by policy")]
+    PolicyDenial(#[from] InsufficientScope),
+}
+
+impl From<IntrospectionError> for AuthorityError {
+    fn from(error: IntrospectionError) -> Self {
+        match error {
+            IntrospectionError::Request(err) => Self::Unavailable(err.into()),
+            IntrospectionError::InvalidResponse(err) => Self::Unavailable(err.into()),
+            IntrospectionError::Inactive => Self::Inactive,
+            IntrospectionError::ClaimsRejected(err) => {
+                Self::JwtVerifyError(JwtVerifyError::from(err))
+            }
+            IntrospectionError::PolicyDenial(err) => Self::PolicyDenial(err),
+        }
+    }
+}
+
+/// Configuration for an [`IntrospectionAuthority`]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+#[derive(Clone)]
+#[must_use]
+pub struct IntrospectionConfig {
+    endpoint: String,
+    client_id: String,
+    client_secret: String,
+    credentials_in_body: bool,
+    allowed_audiences: Vec<jwt::Audience>,
+    issuer: Option<jwt::Issuer>,
+    cache_ttl: Duration,
+    negative_ttl: Duration,
+    max_entries: usize,
+}
+
+impl fmt::Debug for IntrospectionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IntrospectionConfig")
+            .field("endpoint", &self.endpoint)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &format_args!("***CLIENT SECRET***"))
+            .field("credentials_in_body", &self.credentials_in_body)
+            .field("allowed_audiences", &self.allowed_audiences)
+            .field("issuer", &self.issuer)
+            .field("cache_ttl", &self.cache_ttl)
+            .field("negative_ttl", &self.negative_ttl)
+            .field("max_entries", &self.max_entries)
+            .finish()
+    }
+}
+
+impl IntrospectionConfig {
+    /// Introspects tokens at the given endpoint, authenticating with the
+    /// given client credentials
+    ///
+    /// The client credentials are sent using HTTP Basic authentication
+    /// (`client_secret_basic`) unless
+    /// [`with_credentials_in_body()`][IntrospectionConfig::with_credentials_in_body()]
+    /// is used.
+    ///
+    /// By default, active tokens are cached for up to five minutes, inactive
+    /// tokens are cached for ten seconds, and at most 10,000 tokens are
+    /// cached.
+    ///
+    /// No audience or issuer is required by default, so any token that the
+    /// endpoint reports as active is accepted. Resource servers sharing an
+    /// authorization server should set the audiences that they accept with
+    /// [`add_allowed_audience()`][IntrospectionConfig::add_allowed_audience()].
+    pub fn new(
+        endpoint: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            credentials_in_body: false,
+            allowed_audiences: Vec::new(),
+            issuer: None,
+            cache_ttl: Duration::from_secs(5 * 60),
+            negative_ttl: Duration::from_secs(10),
+            max_entries: 10_000,
+        }
+    }
+
+    /// Adds a single audience to the set of allowed audiences
+    ///
+    /// When any audiences are allowed, the `aud` in the introspection
+    /// response must name at least one of them.
+    pub fn add_allowed_audience(self, audience: jwt::Audience) -> Self {
+        let mut this = self;
+        this.allowed_audiences.push(audience);
+        this
+    }
+
+    /// Requires that the `iss` in the introspection response match the
+    /// given issuer
+    pub fn require_issuer(self, issuer: jwt::Issuer) -> Self {
+        Self {
+            issuer: Some(issuer),
+            ..self
+        }
+    }
+
+    /// Checks the audience and issuer of an active token
+    fn validate(&self, claims: &Map<String, Value>) -> Result<(), IntrospectionError> {
+        if self.allowed_audiences.is_empty() && self.issuer.is_none() {
+            return Ok(());
+        }
+
+        let claims =
+            jwt::BasicClaims::deserialize(claims).map_err(IntrospectionError::InvalidResponse)?;
+
+        if !self.allowed_audiences.is_empty() {
+            if claims.aud().is_empty() {
+                return Err(ClaimsRejected::MissingRequiredClaim("aud").into());
+            }
+
+            let found = claims
+                .aud()
+                .iter()
+                .any(|aud| self.allowed_audiences.iter().any(|allowed| aud == allowed));
+            if !found {
+                return Err(ClaimsRejected::InvalidAudience.into());
+            }
+        }
+
+        if let Some(issuer) = &self.issuer {
+            match claims.iss() {
+                Some(iss) if iss == issuer => {}
+                Some(_) => return Err(ClaimsRejected::InvalidIssuer.into()),
+                None => return Err(ClaimsRejected::MissingRequiredClaim("iss").into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the maximum time for which an active token is cached
+    ///
+    /// Active tokens are never cached beyond their `exp` claim.
+    pub fn with_cache_ttl(self, cache_ttl: Duration) -> Self {
+        Self { cache_ttl, ..self }
+    }
+
+    /// Sets the time for which an inactive token is cached
+    pub fn with_negative_ttl(self, negative_ttl: Duration) -> Self {
+        Self {
+            negative_ttl,
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of tokens to cache
+    pub fn with_max_entries(self, max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            ..self
+        }
+    }
+
+    /// Sends the client credentials as form fields in the request body
+    /// (`client_secret_post`) instead of using HTTP Basic authentication
+    ///
+    /// Only use this for authorization servers that do not support HTTP Basic
+    /// authentication at the introspection endpoint.
+    pub fn with_credentials_in_body(self) -> Self {
+        Self {
+            credentials_in_body: true,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    expires: Instant,
+    claims: Option<Arc<Map<String, Value>>>,
+}
+
+type TokenHash = [u8; 32];
+
+/// Cached results are keyed by a hash of the token, so that live tokens are
+/// not held in memory or exposed through `Debug`
+#[derive(Debug)]
+struct Inner {
+    config: IntrospectionConfig,
+    client: Client,
+    cache: Mutex<HashMap<TokenHash, CacheEntry>>,
+}
+
+impl Inner {
+    fn cache(&self) -> MutexGuard<'_, HashMap<TokenHash, CacheEntry>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn hash(token: &str) -> TokenHash {
+    let digest = ring::digest::digest(&ring::digest::SHA256, token.as_bytes());
+    let mut hash = TokenHash::default();
+    hash.copy_from_slice(digest.as_ref());
+    hash
+}
+
+/// An authority for opaque tokens that asks an OAuth2 token introspection
+/// endpoint whether a token is active
+///
+/// Tokens are introspected as described in
+/// [RFC 7662](https://datatracker.ietf.org/doc/html/rfc7662). The claims in
+/// the introspection response, such as `scope`, `exp`, `sub`, `aud`, and
+/// `client_id`, use the same names as JWT claims, so they can be
+/// deserialized into the same claims types, such as
+/// [`BasicClaimsWithScope`][crate::oauth2::BasicClaimsWithScope].
+///
+/// Introspection results are cached. Active tokens are cached until they
+/// expire or the cache TTL elapses, whichever is sooner. Inactive tokens are
+/// cached briefly so that repeated requests with a bad token do not flood
+/// the introspection endpoint. Failures to reach the endpoint are not cached.
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct IntrospectionAuthority {
+    inner: Arc<Inner>,
+}
+
+impl IntrospectionAuthority {
+    /// Constructs a new introspection authority
+    ///
+    /// # Errors
+    ///
+    /// Unable to construct the HTTP client.
+    pub fn new(config: IntrospectionConfig) -> Result<Self, reqwest::Error> {
+        let client = Client::builder()
+            .user_agent(concat!("aliri_oauth2/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                config,
+                client,
+                cache: Mutex::default(),
+            }),
+        })
+    }
+
+    /// Authorizes the token by introspection and checks access according to
+    /// the policy
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token cannot be introspected, is not active,
+    /// is not for an allowed audience or the required issuer, or is not
+    /// authorized by the policy
+    pub async fn verify_token<T>(
+        &self,
+        token: &str,
+        policy: &ScopePolicy,
+    ) -> Result<T, IntrospectionError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope,
+    {
+        let claims = match self.cached(token) {
+            Some(claims) => claims,
+            None => self.introspect(token).await?,
+        };
+
+        let claims = claims.ok_or(IntrospectionError::Inactive)?;
+        self.inner.config.validate(&claims)?;
+        let claims = T::deserialize(&*claims).map_err(IntrospectionError::InvalidResponse)?;
+
+        policy.evaluate(claims.scope())?;
+
+        Ok(claims)
+    }
+
+    /// Removes all cached introspection results
+    pub fn clear_cache(&self) {
+        self.inner.cache().clear();
+    }
+
+    fn cached(&self, token: &str) -> Option<Option<Arc<Map<String, Value>>>> {
+        let key = hash(token);
+        let mut cache = self.inner.cache();
+        match cache.get(&key) {
+            Some(entry) if entry.expires > Instant::now() => {
+                tracing::trace!("using cached introspection result");
+                Some(entry.claims.clone())
+            }
+            Some(_) => {
+                cache.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn introspect(
+        &self,
+        token: &str,
+    ) -> Result<Option<Arc<Map<String, Value>>>, IntrospectionError> {
+        let config = &self.inner.config;
+
+        tracing::debug!(introspection.url = %config.endpoint, "introspecting token");
+        let request = self
+            .inner
+            .client
+            .post(&config.endpoint)
+            .header(header::ACCEPT, "application/json");
+        let request = if config.credentials_in_body {
+            request.form(&[
+                ("token", token),
+                ("token_type_hint", "access_token"),
+                ("client_id", &config.client_id),
+                ("client_secret", &config.client_secret),
+            ])
+        } else {
+            request
+                .basic_auth(&config.client_id, Some(&config.client_secret))
+                .form(&[("token", token), ("token_type_hint", "access_token")])
+        };
+
+        let response = request.send().await?.error_for_status()?;
+
+        let body = response.bytes().await?;
+        let mut claims: Map<String, Value> =
+            serde_json::from_slice(&body).map_err(IntrospectionError::InvalidResponse)?;
+
+        let active = matches!(claims.remove("active"), Some(Value::Bool(true)));
+        let now = Instant::now();
+
+        let (claims, expires) = if active {
+            let ttl = match claims.get("exp").and_then(Value::as_u64) {
+                Some(exp) => {
+                    Duration::from_secs(exp.saturating_sub(System.now().0)).min(config.cache_ttl)
+                }
+                None => config.cache_ttl,
+            };
+            (Some(Arc::new(claims)), now + ttl)
+        } else {
+            tracing::debug!("token is not active");
+            (None, now + config.negative_ttl)
+        };
+
+        let mut cache = self.inner.cache();
+        if cache.len() >= config.max_entries {
+            cache.retain(|_, entry| entry.expires > now);
+        }
+        if cache.len() < config.max_entries {
+            cache.insert(
+                hash(token),
+                CacheEntry {
+                    expires,
+                    claims: claims.clone(),
+                },
+            );
+        }
+
+        Ok(claims)
+    }
+}
+
+/// Accepts bearer tokens as a [`JwtRef`], as extracted by the framework
+/// integrations, but treats them as opaque
+impl<'a, T> aliri_traits::Authority<'a, T> for IntrospectionAuthority
+where
+    T: for<'de> Deserialize<'de> + HasScope + Send + 'a,
+{
+    type Policy = &'a ScopePolicy;
+    type Token = &'a JwtRef;
+    type VerifyFuture = VerifyFuture<'a, T>;
+    type VerifyError = AuthorityError;
+
+    fn verify(&'a self, token: Self::Token, policy: Self::Policy) -> Self::VerifyFuture {
+        Box::pin(async move {
+            self.verify_token(token.as_str(), policy)
+                .await
+                .map_err(AuthorityError::from)
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "rsa")]
+mod tests {
+    use aliri::{jwa, jwt::CoreClaims, test_util::MockIssuer};
+    use aliri_mock_server::{Endpoint, MockServer};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{oauth2::BasicClaimsWithScope, policy, scope};
+
+    fn start() -> MockServer {
+        let server = MockServer::start(MockIssuer::new(jwa::Algorithm::RS256).unwrap()).unwrap();
+        server.register_client("resource_server", "secret");
+        server
+    }
+
+    fn authority(server: &MockServer) -> IntrospectionAuthority {
+        IntrospectionAuthority::new(IntrospectionConfig::new(
+            server.introspection_url(),
+            "resource_server",
+            "secret",
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn accepts_active_tokens_within_policy() {
+        let server = start();
+        let authority = authority(&server);
+        let token = server.issue_opaque_token(json!({ "scope": "read write", "sub": "user" }));
+
+        let claims: BasicClaimsWithScope = authority
+            .verify_token(&token, &policy![scope!["read"]])
+            .await
+            .unwrap();
+        assert_eq!(claims.basic.sub().unwrap().as_str(), "user");
+
+        let result = authority
+            .verify_token::<BasicClaimsWithScope>(&token, &policy![scope!["admin"]])
+            .await;
+        assert!(matches!(result, Err(IntrospectionError::PolicyDenial(_))));
+    }
+
+    #[tokio::test]
+    async fn authenticates_with_credentials_in_body_when_configured() {
+        let server = start();
+        let token = server.issue_opaque_token(json!({ "scope": "read" }));
+
+        let authority = IntrospectionAuthority::new(
+            IntrospectionConfig::new(server.introspection_url(), "resource_server", "secret")
+                .with_credentials_in_body(),
+        )
+        .unwrap();
+        authority
+            .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+            .await
+            .unwrap();
+
+        let authority = IntrospectionAuthority::new(IntrospectionConfig::new(
+            server.introspection_url(),
+            "resource_server",
+            "wrong",
+        ))
+        .unwrap();
+        let result = authority
+            .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+            .await;
+        assert!(matches!(result, Err(IntrospectionError::Request(_))));
+    }
+
+    #[tokio::test]
+    async fn caches_introspection_results() {
+        let server = start();
+        let authority = authority(&server);
+        let token = server.issue_opaque_token(json!({ "scope": "read" }));
+
+        for _ in 0..3 {
+            authority
+                .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+                .await
+                .unwrap();
+        }
+        assert_eq!(server.request_count(Endpoint::Introspection), 1);
+
+        for _ in 0..3 {
+            let result = authority
+                .verify_token::<BasicClaimsWithScope>("unknown", &ScopePolicy::allow_any())
+                .await;
+            assert!(matches!(result, Err(IntrospectionError::Inactive)));
+        }
+        assert_eq!(server.request_count(Endpoint::Introspection), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_beyond_expiration_or_ttl() {
+        let server = start();
+        let authority = IntrospectionAuthority::new(
+            IntrospectionConfig::new(server.introspection_url(), "resource_server", "secret")
+                .with_negative_ttl(Duration::ZERO),
+        )
+        .unwrap();
+
+        let expired = server.issue_opaque_token(json!({ "exp": 1 }));
+        let result = authority
+            .verify_token::<BasicClaimsWithScope>(&expired, &ScopePolicy::allow_any())
+            .await;
+        assert!(matches!(result, Err(IntrospectionError::Inactive)));
+
+        let token = server.issue_opaque_token(json!({ "scope": "read" }));
+        authority
+            .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+            .await
+            .unwrap();
+
+        server.revoke_token(&token);
+        authority
+            .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+            .await
+            .unwrap();
+
+        authority.clear_cache();
+        for _ in 0..2 {
+            let result = authority
+                .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+                .await;
+            assert!(matches!(result, Err(IntrospectionError::Inactive)));
+        }
+        assert_eq!(server.request_count(Endpoint::Introspection), 4);
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_failures() {
+        let server = start();
+        let authority = authority(&server);
+        let token = server.issue_opaque_token(json!({ "scope": "read" }));
+
+        server.fail_next(Endpoint::Introspection, 1);
+        let result = authority
+            .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+            .await;
+        assert!(matches!(result, Err(IntrospectionError::Request(_))));
+
+        authority
+            .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_tokens_for_other_audiences_or_issuers() {
+        let server = start();
+        let issuer = server.issuer();
+        let config =
+            IntrospectionConfig::new(server.introspection_url(), "resource_server", "secret")
+                .add_allowed_audience(issuer.audience().to_owned())
+                .require_issuer(issuer.issuer().to_owned());
+        let authority = IntrospectionAuthority::new(config).unwrap();
+        let verify = |token: String| {
+            let authority = authority.clone();
+            async move {
+                authority
+                    .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+                    .await
+            }
+        };
+
+        let token = server.issue_opaque_token(json!({ "scope": "read" }));
+        assert!(verify(token).await.is_ok());
+
+        let token = server.issue_opaque_token(json!({ "aud": "https://other.example.com/" }));
+        assert!(matches!(
+            verify(token).await,
+            Err(IntrospectionError::ClaimsRejected(
+                ClaimsRejected::InvalidAudience
+            ))
+        ));
+
+        let token = server.issue_opaque_token(json!({ "iss": "https://impostor.example.com/" }));
+        assert!(matches!(
+            verify(token).await,
+            Err(IntrospectionError::ClaimsRejected(
+                ClaimsRejected::InvalidIssuer
+            ))
+        ));
+    }
+
+    #[test]
+    fn does_not_reveal_secrets_or_tokens() {
+        let authority = IntrospectionAuthority::new(IntrospectionConfig::new(
+            "https://auth.example.com/introspect",
+            "resource_server",
+            "hunter2",
+        ))
+        .unwrap();
+        authority.inner.cache().insert(
+            hash("opaque-token"),
+            CacheEntry {
+                expires: Instant::now(),
+                claims: None,
+            },
+        );
+
+        let debug = format!("{:?}", authority);
+        assert!(!debug.contains("hunter2"));
+        assert!(!debug.contains("opaque-token"));
+    }
+
+    #[tokio::test]
+    async fn rejects_unauthenticated_clients() {
+        let server = start();
+        let authority = IntrospectionAuthority::new(IntrospectionConfig::new(
+            server.introspection_url(),
+            "resource_server",
+            "wrong",
+        ))
+        .unwrap();
+        let token = server.issue_opaque_token(json!({ "scope": "read" }));
+
+        let result = authority
+            .verify_token::<BasicClaimsWithScope>(&token, &ScopePolicy::allow_any())
+            .await;
+        assert!(matches!(result, Err(IntrospectionError::Request(_))));
+    }
+
+    #[tokio::test]
+    async fn verifies_through_authority_trait() {
+        use aliri::Jwt;
+        use aliri_traits::Authority as _;
+
+        let server = start();
+        let authority = authority(&server);
+        let token = Jwt::from(server.issue_opaque_token(json!({ "scope": "read" })));
+
+        let policy = policy![scope!["read"]];
+        let claims: Result<BasicClaimsWithScope, _> = authority.verify(&token, &policy).await;
+        assert!(claims.is_ok());
+
+        let unknown = Jwt::from("unknown");
+        let result: Result<BasicClaimsWithScope, _> = authority.verify(&unknown, &policy).await;
+        assert!(matches!(result, Err(AuthorityError::Inactive)));
+
+        let policy = policy![scope!["admin"]];
+        let result: Result<BasicClaimsWithScope, _> = authority.verify(&token, &policy).await;
+        assert!(matches!(result, Err(AuthorityError::PolicyDenial(_))));
+    }
+}
@@ -32,9 +32,35 @@
 )]
 
 mod authority;
+#[cfg(feature = "reqwest")]
+mod introspection;
+pub mod metrics;
+mod multi;
 pub mod oauth2;
 mod policy;
 
-pub use authority::{Authority, AuthorityError};
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+pub use authority::DiscoveryError;
+#[cfg(all(feature = "reqwest", feature = "file"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "file"))))]
+pub use authority::JwksCacheFile;
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+pub use authority::RefetchPolicy;
+pub use authority::{Authority, AuthorityError, VerifyOverrides};
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub use authority::{JwksChange, JwksSubscriber, RefreshConfig, RefreshHandle};
+#[cfg(feature = "file")]
+#[cfg_attr(docsrs, doc(cfg(feature = "file")))]
+pub use authority::{JwksFile, JwksFileError};
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+pub use introspection::{IntrospectionAuthority, IntrospectionConfig, IntrospectionError};
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+pub use multi::IssuerDiscovery;
+pub use multi::{IssuerPattern, MultiAuthority};
 pub use oauth2::Scope;
-pub use policy::{InsufficientScope, ScopePolicy};
+pub use policy::{InsufficientScope, InvalidScopePolicy, ScopeMatching, ScopePolicy};
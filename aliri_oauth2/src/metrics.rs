@@ -0,0 +1,384 @@
+//! Metrics describing token verification and JWKS refreshes
+//!
+//! An [`Authority`][crate::Authority] reports to a [`MetricsRecorder`] set
+//! with [`set_metrics_recorder()`][crate::Authority::set_metrics_recorder()].
+//! Adapters are provided for the `metrics` crate, with the `metrics` feature,
+//! and for OpenTelemetry, with the `opentelemetry` feature.
+
+use std::fmt;
+#[cfg(any(feature = "metrics", feature = "opentelemetry"))]
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "opentelemetry")]
+use std::{
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use aliri::error::{ClaimsRejected, JwkVerifyError, JwtVerifyError};
+
+use crate::AuthorityError;
+
+/// A recorder of the metrics reported by an [`Authority`][crate::Authority]
+///
+/// Recorders are called inline while verifying tokens, so they should be
+/// cheap and must not block.
+pub trait MetricsRecorder: fmt::Debug + Send + Sync + 'static {
+    /// Records the outcome of verifying a token
+    fn record_verification(&self, outcome: VerifyOutcome);
+
+    /// Records the outcome of refreshing the JWKS
+    fn record_refresh(&self, outcome: RefreshOutcome);
+
+    /// Records the number of keys in the current JWKS
+    fn record_key_count(&self, count: usize);
+}
+
+/// The outcome of verifying a token
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum VerifyOutcome {
+    /// The token was valid and permitted by the policy
+    Success,
+    /// No key in the JWKS matched the token header
+    UnknownKeyId,
+    /// No authority was found for the token issuer
+    UnknownIssuer,
+    /// The token could not be parsed
+    Malformed,
+    /// The token signature did not match the key
+    SignatureMismatch,
+    /// The key cannot be used to verify the token
+    IncompatibleKey,
+    /// The token algorithm is not acceptable
+    InvalidAlgorithm,
+    /// The token audience is not acceptable
+    InvalidAudience,
+    /// The token issuer is not acceptable
+    InvalidIssuer,
+    /// The token subject is not acceptable
+    InvalidSubject,
+    /// The token is expired
+    TokenExpired,
+    /// The token is not yet valid
+    TokenNotYetValid,
+    /// A required claim is missing
+    MissingRequiredClaim,
+    /// The claims were rejected by a custom validation
+    ClaimsRejected,
+    /// The token was valid, but was not permitted by the policy
+    PolicyDenied,
+    /// The token is no longer active
+    Inactive,
+    /// The token could not be verified
+    Unavailable,
+    /// The token was rejected for an unexpected reason
+    Other,
+}
+
+impl VerifyOutcome {
+    /// Determines the outcome of a verification
+    pub fn of<T>(result: &Result<T, AuthorityError>) -> Self {
+        match result {
+            Ok(_) => Self::Success,
+            Err(AuthorityError::UnknownKeyId) => Self::UnknownKeyId,
+            Err(AuthorityError::UnknownIssuer) => Self::UnknownIssuer,
+            Err(AuthorityError::JwtVerifyError(err)) => Self::from_jwt_error(err),
+            Err(AuthorityError::PolicyDenial(_)) => Self::PolicyDenied,
+            Err(AuthorityError::Inactive) => Self::Inactive,
+            Err(AuthorityError::Unavailable(_)) => Self::Unavailable,
+        }
+    }
+
+    fn from_jwt_error(err: &JwtVerifyError) -> Self {
+        match err {
+            JwtVerifyError::JwkVerifyError(JwkVerifyError::SignatureMismatch(_)) => {
+                Self::SignatureMismatch
+            }
+            JwtVerifyError::JwkVerifyError(JwkVerifyError::IncompatibleAlgorithm(_))
+            | JwtVerifyError::JwkVerifyError(JwkVerifyError::JwkUsageMismatch(_)) => {
+                Self::IncompatibleKey
+            }
+            JwtVerifyError::MalformedToken(_)
+            | JwtVerifyError::MalformedTokenHeader(_)
+            | JwtVerifyError::MalformedTokenPayload(_)
+            | JwtVerifyError::MalformedTokenSignature(_) => Self::Malformed,
+            JwtVerifyError::ClaimsRejected(rejected) => match rejected {
+                ClaimsRejected::InvalidAlgorithm => Self::InvalidAlgorithm,
+                ClaimsRejected::InvalidAudience => Self::InvalidAudience,
+                ClaimsRejected::InvalidIssuer => Self::InvalidIssuer,
+                ClaimsRejected::InvalidSubject => Self::InvalidSubject,
+                ClaimsRejected::TokenExpired => Self::TokenExpired,
+                ClaimsRejected::TokenNotYetValid => Self::TokenNotYetValid,
+                ClaimsRejected::MissingRequiredClaim(_) => Self::MissingRequiredClaim,
+                ClaimsRejected::Custom(_) => Self::ClaimsRejected,
+            },
+            _ => Self::Other,
+        }
+    }
+
+    /// A label for the outcome, suitable for use as a metric attribute
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::UnknownKeyId => "unknown_kid",
+            Self::UnknownIssuer => "unknown_issuer",
+            Self::Malformed => "malformed",
+            Self::SignatureMismatch => "signature_mismatch",
+            Self::IncompatibleKey => "incompatible_key",
+            Self::InvalidAlgorithm => "invalid_algorithm",
+            Self::InvalidAudience => "invalid_audience",
+            Self::InvalidIssuer => "invalid_issuer",
+            Self::InvalidSubject => "invalid_subject",
+            Self::TokenExpired => "token_expired",
+            Self::TokenNotYetValid => "token_not_yet_valid",
+            Self::MissingRequiredClaim => "missing_required_claim",
+            Self::ClaimsRejected => "claims_rejected",
+            Self::PolicyDenied => "policy_denied",
+            Self::Inactive => "inactive",
+            Self::Unavailable => "unavailable",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for VerifyOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The outcome of refreshing the JWKS
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RefreshOutcome {
+    /// A new JWKS was fetched
+    Updated,
+    /// The JWKS was unchanged since the last refresh
+    NotModified,
+    /// The JWKS could not be fetched
+    Failed,
+}
+
+impl RefreshOutcome {
+    /// Whether the refresh succeeded
+    #[must_use]
+    pub const fn is_success(self) -> bool {
+        !matches!(self, Self::Failed)
+    }
+
+    /// A label for the outcome, suitable for use as a metric attribute
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Updated => "updated",
+            Self::NotModified => "not_modified",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for RefreshOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(any(feature = "metrics", feature = "opentelemetry"))]
+fn since_unix_epoch() -> Duration {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+}
+
+/// A recorder reporting to the [`metrics`] crate
+///
+/// The following metrics are reported:
+///
+/// * `aliri_oauth2_token_verifications_total`: a counter of token
+///   verifications, labeled by `outcome`
+/// * `aliri_oauth2_jwks_refreshes_total`: a counter of JWKS refreshes,
+///   labeled by `outcome`
+/// * `aliri_oauth2_jwks_last_refresh_success_timestamp_seconds`: a gauge of
+///   the Unix time of the last successful JWKS refresh
+/// * `aliri_oauth2_jwks_keys`: a gauge of the number of keys in the JWKS
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetricsFacade {
+    _p: (),
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsFacade {
+    /// Constructs a new recorder, describing its metrics to the installed
+    /// `metrics` recorder
+    pub fn new() -> Self {
+        ::metrics::describe_counter!(
+            "aliri_oauth2_token_verifications_total",
+            "Token verifications by outcome"
+        );
+        ::metrics::describe_counter!(
+            "aliri_oauth2_jwks_refreshes_total",
+            "JWKS refreshes by outcome"
+        );
+        ::metrics::describe_gauge!(
+            "aliri_oauth2_jwks_last_refresh_success_timestamp_seconds",
+            ::metrics::Unit::Seconds,
+            "Unix time of the last successful JWKS refresh"
+        );
+        ::metrics::describe_gauge!("aliri_oauth2_jwks_keys", "Number of keys in the JWKS");
+
+        Self { _p: () }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for MetricsFacade {
+    fn record_verification(&self, outcome: VerifyOutcome) {
+        ::metrics::counter!(
+            "aliri_oauth2_token_verifications_total",
+            1,
+            "outcome" => outcome.as_str()
+        );
+    }
+
+    fn record_refresh(&self, outcome: RefreshOutcome) {
+        ::metrics::counter!(
+            "aliri_oauth2_jwks_refreshes_total",
+            1,
+            "outcome" => outcome.as_str()
+        );
+
+        if outcome.is_success() {
+            ::metrics::gauge!(
+                "aliri_oauth2_jwks_last_refresh_success_timestamp_seconds",
+                since_unix_epoch().as_secs_f64()
+            );
+        }
+    }
+
+    fn record_key_count(&self, count: usize) {
+        ::metrics::gauge!("aliri_oauth2_jwks_keys", count as f64);
+    }
+}
+
+/// A recorder reporting to an [OpenTelemetry][::opentelemetry] meter
+///
+/// The following instruments are created:
+///
+/// * `aliri_oauth2.token.verifications`: a counter of token verifications,
+///   with an `outcome` attribute
+/// * `aliri_oauth2.jwks.refreshes`: a counter of JWKS refreshes, with an
+///   `outcome` attribute
+/// * `aliri_oauth2.jwks.time_since_last_refresh_success`: a gauge of the
+///   seconds since the last successful JWKS refresh, observed once the JWKS
+///   has been refreshed successfully
+/// * `aliri_oauth2.jwks.keys`: a gauge of the number of keys in the JWKS
+#[cfg(feature = "opentelemetry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "opentelemetry")))]
+#[derive(Debug)]
+pub struct OpenTelemetryRecorder {
+    verifications: ::opentelemetry::metrics::Counter<u64>,
+    refreshes: ::opentelemetry::metrics::Counter<u64>,
+    gauges: Arc<Gauges>,
+    _time_since_last_refresh_success: ::opentelemetry::metrics::ObservableGauge<f64>,
+    _keys: ::opentelemetry::metrics::ObservableGauge<u64>,
+}
+
+#[cfg(feature = "opentelemetry")]
+#[derive(Debug, Default)]
+struct Gauges {
+    key_count: AtomicU64,
+    /// Milliseconds since the Unix epoch, or zero if never refreshed
+    last_refresh_success: AtomicU64,
+}
+
+#[cfg(feature = "opentelemetry")]
+impl OpenTelemetryRecorder {
+    /// Constructs a new recorder, creating its instruments with the given meter
+    pub fn new(meter: &::opentelemetry::metrics::Meter) -> Self {
+        let gauges = Arc::new(Gauges::default());
+
+        let verifications = meter
+            .u64_counter("aliri_oauth2.token.verifications")
+            .with_description("Token verifications by outcome")
+            .init();
+
+        let refreshes = meter
+            .u64_counter("aliri_oauth2.jwks.refreshes")
+            .with_description("JWKS refreshes by outcome")
+            .init();
+
+        let time_since_last_refresh_success = {
+            let gauges = Arc::clone(&gauges);
+            meter
+                .f64_observable_gauge("aliri_oauth2.jwks.time_since_last_refresh_success")
+                .with_description("Seconds since the last successful JWKS refresh")
+                .with_unit(::opentelemetry::metrics::Unit::new("s"))
+                .with_callback(move |observer| {
+                    let millis = gauges.last_refresh_success.load(Ordering::Relaxed);
+                    if millis == 0 {
+                        return;
+                    }
+
+                    let last = SystemTime::UNIX_EPOCH + Duration::from_millis(millis);
+                    let elapsed = SystemTime::now()
+                        .duration_since(last)
+                        .unwrap_or(Duration::ZERO);
+                    observer.observe(elapsed.as_secs_f64(), &[]);
+                })
+                .init()
+        };
+
+        let keys = {
+            let gauges = Arc::clone(&gauges);
+            meter
+                .u64_observable_gauge("aliri_oauth2.jwks.keys")
+                .with_description("Number of keys in the JWKS")
+                .with_callback(move |observer| {
+                    observer.observe(gauges.key_count.load(Ordering::Relaxed), &[]);
+                })
+                .init()
+        };
+
+        Self {
+            verifications,
+            refreshes,
+            gauges,
+            _time_since_last_refresh_success: time_since_last_refresh_success,
+            _keys: keys,
+        }
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+impl MetricsRecorder for OpenTelemetryRecorder {
+    fn record_verification(&self, outcome: VerifyOutcome) {
+        self.verifications.add(
+            1,
+            &[::opentelemetry::KeyValue::new("outcome", outcome.as_str())],
+        );
+    }
+
+    fn record_refresh(&self, outcome: RefreshOutcome) {
+        self.refreshes.add(
+            1,
+            &[::opentelemetry::KeyValue::new("outcome", outcome.as_str())],
+        );
+
+        if outcome.is_success() {
+            let millis = u64::try_from(since_unix_epoch().as_millis()).unwrap_or(u64::MAX);
+            self.gauges
+                .last_refresh_success
+                .store(millis, Ordering::Relaxed);
+        }
+    }
+
+    fn record_key_count(&self, count: usize) {
+        self.gauges.key_count.store(count as u64, Ordering::Relaxed);
+    }
+}
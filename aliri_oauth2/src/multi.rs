@@ -0,0 +1,737 @@
+use std::{collections::HashMap, sync::Arc};
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use aliri::{jwt, JwtRef};
+use aliri_base64::Base64Url;
+use arc_swap::ArcSwap;
+use serde::Deserialize;
+
+use crate::{authority::VerifyFuture, oauth2::HasScope, Authority, AuthorityError, ScopePolicy};
+
+/// An authority that routes each token to one of several authorities
+/// according to the token's issuer
+///
+/// The issuer is read from the `iss` claim of the token before the token is
+/// verified. This is only used to select an authority; the token is then
+/// verified by that authority as usual. Each authority should require its
+/// own issuer, as those constructed with
+/// [`Authority::from_issuer()`] do, so that a token cannot claim one issuer
+/// while having been signed by another.
+///
+/// Authorities can also be created on demand for issuers that match an
+/// allowlist by using discovery. See [`IssuerDiscovery`].
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct MultiAuthority {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    authorities: ArcSwap<HashMap<jwt::Issuer, Authority>>,
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    discovery: Option<Discovery>,
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[derive(Debug)]
+struct Discovery {
+    config: IssuerDiscovery,
+    state: Mutex<DiscoveryState>,
+    in_flight: tokio::sync::Semaphore,
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[derive(Debug, Default)]
+struct DiscoveryState {
+    /// When discovery last failed for each issuer
+    failures: HashMap<jwt::Issuer, Instant>,
+    /// Locks held while discovering each issuer, so that each issuer is
+    /// only discovered once at a time
+    pending: HashMap<jwt::Issuer, Arc<tokio::sync::Mutex<()>>>,
+    /// The discovered issuers, from oldest to newest
+    discovered: VecDeque<jwt::Issuer>,
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+impl DiscoveryState {
+    fn failed_recently(&mut self, issuer: &jwt::IssuerRef, retry_after: Duration) -> bool {
+        let now = Instant::now();
+        self.failures
+            .retain(|_, failed_at| now.duration_since(*failed_at) < retry_after);
+        self.failures.contains_key(issuer)
+    }
+}
+
+impl MultiAuthority {
+    /// Constructs an authority without any issuers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs an authority that discovers authorities for issuers
+    /// matching the allowlist when verifying tokens with
+    /// [`verify_token_async()`][MultiAuthority::verify_token_async()]
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+    pub fn with_discovery(discovery: IssuerDiscovery) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                authorities: ArcSwap::default(),
+                discovery: Some(Discovery {
+                    in_flight: tokio::sync::Semaphore::new(discovery.max_concurrent),
+                    config: discovery,
+                    state: Mutex::default(),
+                }),
+            }),
+        }
+    }
+
+    /// Adds an authority for tokens from the given issuer
+    pub fn with_authority(self, issuer: jwt::Issuer, authority: Authority) -> Self {
+        self.insert(issuer, authority);
+        self
+    }
+
+    /// Adds an authority for tokens from the given issuer, returning the
+    /// authority that it replaces
+    pub fn insert(&self, issuer: jwt::Issuer, authority: Authority) -> Option<Authority> {
+        let previous = self.inner.authorities.rcu(|authorities| {
+            let mut authorities = HashMap::clone(authorities);
+            authorities.insert(issuer.clone(), authority.clone());
+            authorities
+        });
+
+        previous.get(&issuer).cloned()
+    }
+
+    /// Removes the authority for tokens from the given issuer
+    pub fn remove(&self, issuer: &jwt::IssuerRef) -> Option<Authority> {
+        let previous = self.inner.authorities.rcu(|authorities| {
+            let mut authorities = HashMap::clone(authorities);
+            authorities.remove(issuer);
+            authorities
+        });
+
+        previous.get(issuer).cloned()
+    }
+
+    /// The authority for tokens from the given issuer
+    #[must_use]
+    pub fn get(&self, issuer: &jwt::IssuerRef) -> Option<Authority> {
+        self.inner.authorities.load().get(issuer).cloned()
+    }
+
+    /// The issuers for which an authority is held, including any that have
+    /// been discovered
+    #[must_use]
+    pub fn issuers(&self) -> Vec<jwt::Issuer> {
+        self.inner.authorities.load().keys().cloned().collect()
+    }
+
+    /// Authenticates the token with the authority for its issuer and checks
+    /// access according to the policy
+    ///
+    /// Only authorities that have been added or already discovered are used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no authority for the token's issuer, if
+    /// the token is invalid, or if it is not authorized by the policy
+    pub fn verify_token<T>(&self, token: &JwtRef, policy: &ScopePolicy) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let issuer = untrusted_issuer(token)?;
+        let authority = self.get(&issuer).ok_or_else(|| {
+            tracing::debug!(%issuer, "no authority for issuer");
+            AuthorityError::UnknownIssuer
+        })?;
+
+        authority.verify_token(token, policy)
+    }
+
+    /// Authenticates the token with the authority for its issuer and checks
+    /// access according to the policy, discovering the authority if needed
+    ///
+    /// If no authority is held for the token's issuer and the issuer matches
+    /// the discovery allowlist, the authority is constructed with
+    /// [`Authority::from_issuer()`]. Each issuer is discovered at most once at
+    /// a time, and failed discoveries are not retried for the issuer until
+    /// the retry interval has elapsed. If the limit on concurrent discoveries
+    /// has been reached, the issuer is treated as unknown rather than
+    /// waiting. The token is then verified with
+    /// [`Authority::verify_token_async()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no authority for the token's issuer and
+    /// none can be discovered, if the token is invalid, or if it is not
+    /// authorized by the policy
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+    pub async fn verify_token_async<T>(
+        &self,
+        token: &JwtRef,
+        policy: &ScopePolicy,
+    ) -> Result<T, AuthorityError>
+    where
+        T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims,
+    {
+        let issuer = untrusted_issuer(token)?;
+        let authority = match self.get(&issuer) {
+            Some(authority) => authority,
+            None => self.discover(issuer).await?,
+        };
+
+        authority.verify_token_async(token, policy).await
+    }
+
+    #[cfg(all(feature = "reqwest", feature = "tokio"))]
+    #[tracing::instrument(skip(self), fields(%issuer))]
+    async fn discover(&self, issuer: jwt::Issuer) -> Result<Authority, AuthorityError> {
+        let discovery = match &self.inner.discovery {
+            Some(discovery) if discovery.config.allows(&issuer) => discovery,
+            _ => {
+                tracing::debug!("no authority for issuer");
+                return Err(AuthorityError::UnknownIssuer);
+            }
+        };
+
+        let retry_after = discovery.config.retry_after;
+        let pending = {
+            let mut state = discovery.lock();
+            if state.failed_recently(&issuer, retry_after) {
+                tracing::debug!("discovery failed recently; not retrying yet");
+                return Err(AuthorityError::UnknownIssuer);
+            }
+
+            PendingDiscovery {
+                lock: Arc::clone(state.pending.entry(issuer.clone()).or_default()),
+                discovery,
+                issuer: issuer.clone(),
+            }
+        };
+
+        let _discovering = pending.lock.lock().await;
+
+        if let Some(authority) = self.get(&issuer) {
+            tracing::trace!("authority discovered while waiting");
+            return Ok(authority);
+        }
+
+        if discovery.lock().failed_recently(&issuer, retry_after) {
+            tracing::debug!("discovery failed while waiting");
+            return Err(AuthorityError::UnknownIssuer);
+        }
+
+        let _permit = match discovery.in_flight.try_acquire() {
+            Ok(permit) => permit,
+            Err(_) => {
+                tracing::warn!("too many discoveries in progress; not discovering issuer");
+                return Err(AuthorityError::UnknownIssuer);
+            }
+        };
+
+        let result =
+            Authority::from_issuer(issuer.clone(), discovery.config.validator.clone()).await;
+
+        let mut state = discovery.lock();
+
+        match result {
+            Ok(authority) => {
+                tracing::info!("authority discovered");
+                self.insert(issuer.clone(), authority.clone());
+                state.discovered.retain(|discovered| discovered != &issuer);
+                state.discovered.push_back(issuer);
+                while state.discovered.len() > discovery.config.max_discovered {
+                    if let Some(evicted) = state.discovered.pop_front() {
+                        tracing::debug!(evicted = %evicted, "evicting oldest discovered authority");
+                        self.remove(&evicted);
+                    }
+                }
+                Ok(authority)
+            }
+            Err(err) => {
+                let error: &dyn std::error::Error = &err;
+                tracing::warn!(error, "authority discovery failed");
+                state.failures.insert(issuer, Instant::now());
+                Err(AuthorityError::UnknownIssuer)
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+impl Discovery {
+    fn lock(&self) -> std::sync::MutexGuard<'_, DiscoveryState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A share of the lock held while discovering an issuer
+///
+/// The lock is removed from the pending discoveries when the last share is
+/// dropped, including when a discovery is cancelled.
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+struct PendingDiscovery<'a> {
+    lock: Arc<tokio::sync::Mutex<()>>,
+    discovery: &'a Discovery,
+    issuer: jwt::Issuer,
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+impl Drop for PendingDiscovery<'_> {
+    fn drop(&mut self) {
+        let mut state = self.discovery.lock();
+        let is_last_share = matches!(
+            state.pending.get(&self.issuer),
+            Some(lock) if Arc::ptr_eq(lock, &self.lock) && Arc::strong_count(&self.lock) == 2
+        );
+        if is_last_share {
+            state.pending.remove(&self.issuer);
+        }
+    }
+}
+
+/// Verifies tokens with
+/// [`verify_token_async()`][MultiAuthority::verify_token_async()] when the
+/// `reqwest` and `tokio` features are enabled, and with
+/// [`verify_token()`][MultiAuthority::verify_token()] otherwise
+impl<'a, T> aliri_traits::Authority<'a, T> for MultiAuthority
+where
+    T: for<'de> Deserialize<'de> + HasScope + jwt::CoreClaims + Send + 'a,
+{
+    type Policy = &'a ScopePolicy;
+    type Token = &'a JwtRef;
+    type VerifyFuture = VerifyFuture<'a, T>;
+    type VerifyError = AuthorityError;
+
+    fn verify(&'a self, token: Self::Token, policy: Self::Policy) -> Self::VerifyFuture {
+        #[cfg(all(feature = "reqwest", feature = "tokio"))]
+        {
+            Box::pin(self.verify_token_async(token, policy))
+        }
+
+        #[cfg(not(all(feature = "reqwest", feature = "tokio")))]
+        {
+            Box::pin(std::future::ready(self.verify_token(token, policy)))
+        }
+    }
+}
+
+/// Reads the issuer from the token without verifying it
+fn untrusted_issuer(token: &JwtRef) -> Result<jwt::Issuer, AuthorityError> {
+    #[derive(Deserialize)]
+    struct IssuerOnly {
+        iss: Option<jwt::Issuer>,
+    }
+
+    let decomposed: jwt::Decomposed = token.decompose()?;
+    let payload =
+        Base64Url::from_encoded(decomposed.untrusted_payload()).map_err(malformed_payload)?;
+    let claims: IssuerOnly =
+        serde_json::from_slice(payload.as_slice()).map_err(malformed_payload)?;
+
+    claims.iss.ok_or_else(|| {
+        tracing::debug!("token has no issuer");
+        AuthorityError::UnknownIssuer
+    })
+}
+
+fn malformed_payload(
+    source: impl Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+) -> AuthorityError {
+    let error = aliri::error::MalformedJwtPayload::from(source.into());
+    AuthorityError::JwtVerifyError(error.into())
+}
+
+/// Configuration for discovering authorities on demand
+///
+/// Only issuers matching one of the allowed patterns are discovered. Each
+/// discovered authority is configured with the provided validator, seeded
+/// with the issuer and its advertised algorithms.
+///
+/// As a pattern may match many issuers, only a limited number of discovered
+/// authorities are held. When the limit is reached, the authority that was
+/// discovered first is removed, and is discovered again when next needed.
+/// The number of discoveries in progress at once is also limited, so that
+/// tokens naming many different issuers cannot cause an unbounded number of
+/// outbound requests.
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "reqwest", feature = "tokio"))))]
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct IssuerDiscovery {
+    allowed: Vec<IssuerPattern>,
+    validator: jwt::CoreValidator,
+    retry_after: Duration,
+    max_discovered: usize,
+    max_concurrent: usize,
+}
+
+#[cfg(all(feature = "reqwest", feature = "tokio"))]
+impl IssuerDiscovery {
+    /// Constructs a discovery configuration that allows no issuers
+    ///
+    /// By default, failed discoveries are retried after one minute, up to 100
+    /// discovered authorities are held, and up to 4 discoveries may be in
+    /// progress at once.
+    pub fn new(validator: jwt::CoreValidator) -> Self {
+        Self {
+            allowed: Vec::new(),
+            validator,
+            retry_after: Duration::from_secs(60),
+            max_discovered: 100,
+            max_concurrent: 4,
+        }
+    }
+
+    /// Allows discovery of issuers matching the pattern
+    pub fn add_allowed_pattern(mut self, pattern: IssuerPattern) -> Self {
+        self.allowed.push(pattern);
+        self
+    }
+
+    /// Sets how long to wait before retrying a failed discovery
+    pub fn with_retry_after(self, retry_after: Duration) -> Self {
+        Self {
+            retry_after,
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of discovered authorities to hold
+    ///
+    /// Authorities added with [`MultiAuthority::insert()`] do not count
+    /// against this limit.
+    pub fn with_max_discovered(self, max_discovered: usize) -> Self {
+        Self {
+            max_discovered,
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of discoveries that may be in progress at once
+    ///
+    /// Tokens from issuers that would need to be discovered while this many
+    /// discoveries are in progress are rejected as being from an unknown
+    /// issuer.
+    pub fn with_max_concurrent(self, max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent,
+            ..self
+        }
+    }
+
+    /// Whether the issuer may be discovered
+    #[must_use]
+    pub fn allows(&self, issuer: &jwt::IssuerRef) -> bool {
+        self.allowed.iter().any(|pattern| pattern.matches(issuer))
+    }
+}
+
+/// A pattern matching a set of issuers
+///
+/// A `*` in the pattern matches one or more characters within a single host
+/// label or path segment. It will not match any of `/`, `.`, `:`, `@`, `?`, or
+/// `#`, so `https://*.auth.example.com/` matches
+/// `https://tenant.auth.example.com/`, but not
+/// `https://evil.example.org/.auth.example.com/`. All other characters must
+/// match exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssuerPattern {
+    pattern: String,
+}
+
+impl IssuerPattern {
+    /// Constructs a new issuer pattern
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+        }
+    }
+
+    /// Whether the issuer matches the pattern
+    #[must_use]
+    pub fn matches(&self, issuer: &jwt::IssuerRef) -> bool {
+        let mut literals = self.pattern.split('*');
+        let first = literals.next().unwrap_or_default();
+        let literals: Vec<&str> = literals.collect();
+
+        issuer
+            .as_str()
+            .strip_prefix(first)
+            .is_some_and(|rest| matches_wildcards(rest, &literals))
+    }
+}
+
+/// Matches input that begins with a wildcard followed by each literal in turn
+fn matches_wildcards(input: &str, literals: &[&str]) -> bool {
+    let (literal, remaining) = match literals.split_first() {
+        Some(split) => split,
+        None => return input.is_empty(),
+    };
+
+    for (idx, c) in input.char_indices() {
+        if matches!(c, '/' | '.' | ':' | '@' | '?' | '#') {
+            break;
+        }
+
+        let rest = &input[idx + c.len_utf8()..];
+        if let Some(rest) = rest.strip_prefix(literal) {
+            if matches_wildcards(rest, remaining) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, issuer: &str) -> bool {
+        IssuerPattern::new(pattern).matches(jwt::IssuerRef::from_str(issuer))
+    }
+
+    #[test]
+    fn matches_issuer_patterns() {
+        assert!(matches(
+            "https://auth.example.com/",
+            "https://auth.example.com/"
+        ));
+        assert!(!matches(
+            "https://auth.example.com/",
+            "https://auth.example.com/other/"
+        ));
+
+        assert!(matches(
+            "https://*.auth.example.com/",
+            "https://tenant.auth.example.com/"
+        ));
+        assert!(!matches(
+            "https://*.auth.example.com/",
+            "https://.auth.example.com/"
+        ));
+        assert!(!matches(
+            "https://*.auth.example.com/",
+            "https://a.b.auth.example.com/"
+        ));
+        assert!(!matches(
+            "https://*.auth.example.com/",
+            "https://evil.example.org/.auth.example.com/"
+        ));
+        assert!(!matches(
+            "https://*.auth.example.com/",
+            "https://evil.example.org?.auth.example.com/"
+        ));
+
+        assert!(matches(
+            "https://login.example.com/*/v2.0",
+            "https://login.example.com/1234-abcd/v2.0"
+        ));
+        assert!(!matches(
+            "https://login.example.com/*/v2.0",
+            "https://login.example.com/1234/abcd/v2.0"
+        ));
+        assert!(matches(
+            "https://*.example.com/*/",
+            "https://tenant.example.com/realm/"
+        ));
+    }
+
+    #[cfg(all(feature = "reqwest", feature = "tokio", feature = "rsa"))]
+    mod routing {
+        use aliri::{jwa, test_util::MockIssuer};
+        use aliri_mock_server::{Endpoint, MockServer};
+
+        use super::*;
+        use crate::oauth2::BasicClaimsWithScope;
+
+        fn start() -> MockServer {
+            MockServer::start(MockIssuer::new(jwa::Algorithm::RS256).unwrap()).unwrap()
+        }
+
+        fn issuer(server: &MockServer) -> jwt::Issuer {
+            jwt::Issuer::new(server.url().to_owned())
+        }
+
+        async fn verify(
+            authority: &MultiAuthority,
+            token: &JwtRef,
+        ) -> Result<BasicClaimsWithScope, AuthorityError> {
+            authority
+                .verify_token_async(token, &ScopePolicy::allow_any())
+                .await
+        }
+
+        #[tokio::test]
+        async fn routes_by_issuer() {
+            let first = start();
+            let second = start();
+
+            let multi = MultiAuthority::new()
+                .with_authority(
+                    issuer(&first),
+                    Authority::new(first.jwks(), first.validator()),
+                )
+                .with_authority(
+                    issuer(&second),
+                    Authority::new(second.jwks(), second.validator()),
+                );
+
+            let policy = ScopePolicy::allow_any();
+            assert!(multi
+                .verify_token::<BasicClaimsWithScope>(&first.issuer().token(), &policy)
+                .is_ok());
+            assert!(multi
+                .verify_token::<BasicClaimsWithScope>(&second.issuer().token(), &policy)
+                .is_ok());
+
+            let impostor = second.issuer().with_issuer(issuer(&first)).token();
+            assert!(matches!(
+                multi.verify_token::<BasicClaimsWithScope>(&impostor, &policy),
+                Err(AuthorityError::UnknownKeyId)
+            ));
+
+            let unknown = first
+                .issuer()
+                .with_issuer("https://unknown.example.com/")
+                .token();
+            assert!(matches!(
+                multi.verify_token::<BasicClaimsWithScope>(&unknown, &policy),
+                Err(AuthorityError::UnknownIssuer)
+            ));
+        }
+
+        #[tokio::test]
+        async fn discovers_allowed_issuers() {
+            let server = start();
+            let discovery = IssuerDiscovery::new(
+                jwt::CoreValidator::default()
+                    .add_allowed_audience(server.issuer().audience().to_owned()),
+            )
+            .add_allowed_pattern(IssuerPattern::new("http://127.0.0.1:*"));
+            let multi = MultiAuthority::with_discovery(discovery);
+
+            assert!(verify(&multi, &server.issuer().token()).await.is_ok());
+            assert!(verify(&multi, &server.issuer().token()).await.is_ok());
+            assert_eq!(multi.issuers(), vec![issuer(&server)]);
+            assert_eq!(server.request_count(Endpoint::Discovery), 1);
+        }
+
+        #[tokio::test]
+        async fn does_not_discover_other_issuers() {
+            let server = start();
+            let discovery = IssuerDiscovery::new(jwt::CoreValidator::default())
+                .add_allowed_pattern(IssuerPattern::new("https://*.auth.example.com/"));
+            let multi = MultiAuthority::with_discovery(discovery);
+
+            assert!(matches!(
+                verify(&multi, &server.issuer().token()).await,
+                Err(AuthorityError::UnknownIssuer)
+            ));
+            assert_eq!(server.request_count(Endpoint::Discovery), 0);
+        }
+
+        #[tokio::test]
+        async fn does_not_retry_failed_discovery_immediately() {
+            let server = start();
+            server.fail_next(Endpoint::Discovery, 2);
+            let discovery = IssuerDiscovery::new(jwt::CoreValidator::default())
+                .add_allowed_pattern(IssuerPattern::new("http://127.0.0.1:*"));
+            let multi = MultiAuthority::with_discovery(discovery);
+
+            assert!(matches!(
+                verify(&multi, &server.issuer().token()).await,
+                Err(AuthorityError::UnknownIssuer)
+            ));
+            assert!(matches!(
+                verify(&multi, &server.issuer().token()).await,
+                Err(AuthorityError::UnknownIssuer)
+            ));
+            assert_eq!(server.request_count(Endpoint::Discovery), 2);
+        }
+
+        #[tokio::test]
+        async fn discovers_each_issuer_once_at_a_time() {
+            let server = start();
+            let discovery = IssuerDiscovery::new(
+                jwt::CoreValidator::default()
+                    .add_allowed_audience(server.issuer().audience().to_owned()),
+            )
+            .add_allowed_pattern(IssuerPattern::new("http://127.0.0.1:*"));
+            let multi = MultiAuthority::with_discovery(discovery);
+
+            let token = server.issuer().token();
+            let (first, second) = tokio::join!(verify(&multi, &token), verify(&multi, &token));
+            assert!(first.is_ok());
+            assert!(second.is_ok());
+            assert_eq!(server.request_count(Endpoint::Discovery), 1);
+        }
+
+        #[tokio::test]
+        async fn forgets_cancelled_discoveries() {
+            let server = start();
+            let discovery = IssuerDiscovery::new(jwt::CoreValidator::default())
+                .add_allowed_pattern(IssuerPattern::new("http://127.0.0.1:*"));
+            let multi = MultiAuthority::with_discovery(discovery);
+
+            let token = server.issuer().token();
+            let cancelled = tokio::time::timeout(Duration::ZERO, verify(&multi, &token)).await;
+            assert!(cancelled.is_err());
+
+            let discovery = multi.inner.discovery.as_ref().unwrap();
+            assert!(discovery.lock().pending.is_empty());
+        }
+
+        #[tokio::test]
+        async fn limits_concurrent_discoveries() {
+            let server = start();
+            let discovery = IssuerDiscovery::new(jwt::CoreValidator::default())
+                .add_allowed_pattern(IssuerPattern::new("http://127.0.0.1:*"))
+                .with_max_concurrent(0);
+            let multi = MultiAuthority::with_discovery(discovery);
+
+            assert!(matches!(
+                verify(&multi, &server.issuer().token()).await,
+                Err(AuthorityError::UnknownIssuer)
+            ));
+            assert_eq!(server.request_count(Endpoint::Discovery), 0);
+        }
+
+        #[tokio::test]
+        async fn limits_discovered_authorities() {
+            let first = start();
+            let second = start();
+            let discovery = IssuerDiscovery::new(jwt::CoreValidator::default())
+                .add_allowed_pattern(IssuerPattern::new("http://127.0.0.1:*"))
+                .with_max_discovered(1);
+            let multi = MultiAuthority::with_discovery(discovery).with_authority(
+                jwt::Issuer::from_static("https://static.example.com/"),
+                Authority::new(first.jwks(), first.validator()),
+            );
+
+            let _ = verify(&multi, &first.issuer().token()).await;
+            let _ = verify(&multi, &second.issuer().token()).await;
+
+            let mut issuers = multi.issuers();
+            issuers.sort();
+            assert_eq!(
+                issuers,
+                vec![
+                    issuer(&second),
+                    jwt::Issuer::from_static("https://static.example.com/"),
+                ]
+            );
+        }
+    }
+}
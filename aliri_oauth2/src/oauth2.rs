@@ -14,6 +14,10 @@ use aliri_clock::UnixTime;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+mod scope_claims;
+
+pub use scope_claims::{ClaimsWithScopeFrom, InvalidScopeClaim, ScopeClaims, ScopeSource};
+
 /// An invalid scope token
 #[derive(Debug, Error)]
 pub enum InvalidScopeToken {
@@ -178,6 +182,7 @@ impl Scope {
     pub fn and(self, scope_token: ScopeToken) -> Self {
         match self.0 {
             ScopeInner::Empty => Self::single(scope_token),
+            ScopeInner::Single(existing) if existing == scope_token => Self::single(existing),
             ScopeInner::Single(existing) => {
                 let mut set = BTreeSet::new();
                 set.insert(existing);
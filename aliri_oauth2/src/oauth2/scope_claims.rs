@@ -0,0 +1,425 @@
+use std::{fmt, marker::PhantomData};
+
+use aliri::jwt;
+use aliri_clock::UnixTime;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use thiserror::Error;
+
+use super::{HasScope, InvalidScopeToken, Scope, ScopeToken};
+
+/// An error extracting a scope from a claims payload
+#[derive(Debug, Error)]
+pub enum InvalidScopeClaim {
+    /// The claim was neither a string nor an array of strings
+    #[error("scope claim `{claim}` must be a string or an array of strings")]
+    UnexpectedType {
+        /// The claim name or JSON pointer
+        claim: String,
+    },
+
+    /// The claim contained an invalid scope token
+    #[error("scope claim `{claim}` contains an invalid scope token")]
+    InvalidScopeToken {
+        /// The claim name or JSON pointer
+        claim: String,
+        /// The reason the scope token is invalid
+        #[source]
+        source: InvalidScopeToken,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ScopeClaim {
+    Name(String),
+    Pointer(String),
+}
+
+impl ScopeClaim {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Name(name) => name,
+            Self::Pointer(pointer) => pointer,
+        }
+    }
+
+    fn find<'a>(&self, claims: &'a Value) -> Option<&'a Value> {
+        match self {
+            Self::Name(name) => claims.get(name),
+            Self::Pointer(pointer) => claims.pointer(pointer),
+        }
+    }
+}
+
+/// The claims from which a token's scope is collected
+///
+/// Each claim may hold either a space-delimited string, as in the standard
+/// `scope` claim, or an array of scope tokens, as in the `permissions` or
+/// `roles` claims issued by some identity providers. Claims that are absent
+/// or `null` are ignored, and the scope tokens from all of the claims are
+/// combined.
+///
+/// # Example
+///
+/// ```
+/// use aliri_oauth2::{oauth2::ScopeClaims, scope};
+///
+/// let claims = ScopeClaims::new()
+///     .with_claim("scope")
+///     .with_pointer("/realm_access/roles");
+///
+/// let payload = serde_json::json!({
+///     "scope": "profile email",
+///     "realm_access": { "roles": ["admin"] }
+/// });
+///
+/// assert_eq!(
+///     claims.extract(&payload).unwrap(),
+///     scope!["profile", "email", "admin"],
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[must_use]
+pub struct ScopeClaims {
+    claims: Vec<ScopeClaim>,
+}
+
+impl ScopeClaims {
+    /// No claims, producing an empty scope
+    #[inline]
+    pub const fn new() -> Self {
+        Self { claims: Vec::new() }
+    }
+
+    /// Collects scope tokens from the top-level claim with the given name
+    ///
+    /// The name is used as is, so namespaced claims such as
+    /// `https://example.com/roles` do not need to be escaped.
+    pub fn with_claim(mut self, name: impl Into<String>) -> Self {
+        self.claims.push(ScopeClaim::Name(name.into()));
+        self
+    }
+
+    /// Collects scope tokens from the claim at the given [JSON pointer][RFC6901]
+    ///
+    /// [RFC6901]: https://tools.ietf.org/html/rfc6901
+    pub fn with_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.claims.push(ScopeClaim::Pointer(pointer.into()));
+        self
+    }
+
+    /// Collects the scope from a claims payload
+    ///
+    /// Entries of an array claim that are not valid scope tokens, such as
+    /// role names containing spaces, are skipped with a warning rather than
+    /// rejecting the whole claims payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the claims is present but is not a string
+    /// or an array of strings, or if a string claim contains an invalid scope
+    /// token.
+    pub fn extract(&self, claims: &Value) -> Result<Scope, InvalidScopeClaim> {
+        let mut scope = Scope::empty();
+
+        for claim in &self.claims {
+            let invalid_token = |source| InvalidScopeClaim::InvalidScopeToken {
+                claim: claim.as_str().to_owned(),
+                source,
+            };
+            let unexpected_type = || InvalidScopeClaim::UnexpectedType {
+                claim: claim.as_str().to_owned(),
+            };
+
+            match claim.find(claims) {
+                None | Some(Value::Null) => {}
+                Some(Value::String(s)) => {
+                    for token in s.split_whitespace() {
+                        scope.insert(
+                            ScopeToken::from_string(token.to_owned()).map_err(invalid_token)?,
+                        );
+                    }
+                }
+                Some(Value::Array(arr)) => {
+                    for value in arr {
+                        let token = value.as_str().ok_or_else(unexpected_type)?;
+                        match ScopeToken::from_string(token.to_owned()) {
+                            Ok(token) => scope.insert(token),
+                            Err(err) => {
+                                let error: &dyn std::error::Error = &err;
+                                tracing::warn!(
+                                    error,
+                                    claim = claim.as_str(),
+                                    "skipping invalid scope token in claim"
+                                );
+                            }
+                        }
+                    }
+                }
+                Some(_) => return Err(unexpected_type()),
+            }
+        }
+
+        Ok(scope)
+    }
+}
+
+/// A source of the claims from which a token's scope is collected
+///
+/// Implementations are used with [`ClaimsWithScopeFrom`] to select the scope
+/// claims at the type level, so that the claims can be used anywhere that
+/// expects a type implementing [`HasScope`].
+///
+/// # Example
+///
+/// ```
+/// use aliri_oauth2::oauth2::{ClaimsWithScopeFrom, HasScope, ScopeClaims, ScopeSource};
+/// use aliri_oauth2::scope;
+/// use once_cell::sync::Lazy;
+///
+/// /// Azure AD delegated and application permissions
+/// struct AzureAd;
+///
+/// impl ScopeSource for AzureAd {
+///     fn scope_claims() -> &'static ScopeClaims {
+///         static CLAIMS: Lazy<ScopeClaims> =
+///             Lazy::new(|| ScopeClaims::new().with_claim("scp").with_claim("roles"));
+///         &CLAIMS
+///     }
+/// }
+///
+/// let claims: ClaimsWithScopeFrom<AzureAd> = serde_json::from_str(r#"{
+///     "iss": "https://login.microsoftonline.com/tenant/v2.0",
+///     "scp": "User.Read",
+///     "roles": ["Orders.Manage"]
+/// }"#).unwrap();
+///
+/// assert_eq!(claims.scope(), &scope!["User.Read", "Orders.Manage"]);
+/// ```
+pub trait ScopeSource: 'static {
+    /// The claims from which the scope is collected
+    fn scope_claims() -> &'static ScopeClaims;
+}
+
+/// Claims whose scope is collected from the claims selected by a [`ScopeSource`]
+///
+/// The full claims payload is retained, and the remaining claims can be
+/// accessed through [`claims()`][ClaimsWithScopeFrom::claims()].
+pub struct ClaimsWithScopeFrom<S> {
+    claims: jwt::DynamicClaims,
+    scope: Scope,
+    _source: PhantomData<fn() -> S>,
+}
+
+impl<S> ClaimsWithScopeFrom<S> {
+    /// The full claims payload
+    #[inline]
+    pub fn claims(&self) -> &jwt::DynamicClaims {
+        &self.claims
+    }
+
+    /// Takes ownership of the full claims payload
+    #[inline]
+    pub fn into_claims(self) -> jwt::DynamicClaims {
+        self.claims
+    }
+}
+
+impl<S> Clone for ClaimsWithScopeFrom<S> {
+    fn clone(&self) -> Self {
+        Self {
+            claims: self.claims.clone(),
+            scope: self.scope.clone(),
+            _source: PhantomData,
+        }
+    }
+}
+
+impl<S> fmt::Debug for ClaimsWithScopeFrom<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClaimsWithScopeFrom")
+            .field("claims", &self.claims)
+            .field("scope", &self.scope)
+            .finish()
+    }
+}
+
+impl<S> HasScope for ClaimsWithScopeFrom<S> {
+    #[inline]
+    fn scope(&self) -> &Scope {
+        &self.scope
+    }
+}
+
+impl<S> jwt::CoreClaims for ClaimsWithScopeFrom<S> {
+    #[inline]
+    fn nbf(&self) -> Option<UnixTime> {
+        self.claims.nbf()
+    }
+
+    #[inline]
+    fn exp(&self) -> Option<UnixTime> {
+        self.claims.exp()
+    }
+
+    #[inline]
+    fn aud(&self) -> &jwt::Audiences {
+        self.claims.aud()
+    }
+
+    #[inline]
+    fn iss(&self) -> Option<&jwt::IssuerRef> {
+        self.claims.iss()
+    }
+
+    #[inline]
+    fn sub(&self) -> Option<&jwt::SubjectRef> {
+        self.claims.sub()
+    }
+}
+
+impl<'de, S: ScopeSource> Deserialize<'de> for ClaimsWithScopeFrom<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let claims = jwt::DynamicClaims::deserialize(deserializer)?;
+        let scope = S::scope_claims()
+            .extract(claims.as_value())
+            .map_err(de::Error::custom)?;
+
+        Ok(Self {
+            claims,
+            scope,
+            _source: PhantomData,
+        })
+    }
+}
+
+impl<S> Serialize for ClaimsWithScopeFrom<S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        self.claims.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri::jwt::CoreClaims;
+    use serde_json::json;
+
+    use super::*;
+    use crate::scope;
+
+    struct Keycloak;
+
+    impl ScopeSource for Keycloak {
+        fn scope_claims() -> &'static ScopeClaims {
+            static CLAIMS: once_cell::sync::Lazy<ScopeClaims> = once_cell::sync::Lazy::new(|| {
+                ScopeClaims::new()
+                    .with_claim("scope")
+                    .with_pointer("/realm_access/roles")
+            });
+            &CLAIMS
+        }
+    }
+
+    #[test]
+    fn combines_strings_arrays_and_nested_claims() {
+        let claims = ScopeClaims::new()
+            .with_claim("scp")
+            .with_claim("permissions")
+            .with_claim("https://example.com/roles")
+            .with_pointer("/realm_access/roles");
+
+        let scope = claims
+            .extract(&json!({
+                "scp": "read write",
+                "permissions": ["orders:read", "read"],
+                "https://example.com/roles": ["editor"],
+                "realm_access": { "roles": ["admin"] }
+            }))
+            .unwrap();
+
+        assert_eq!(
+            scope,
+            scope!["read", "write", "orders:read", "editor", "admin"]
+        );
+    }
+
+    #[test]
+    fn ignores_missing_and_null_claims() {
+        let claims = ScopeClaims::new()
+            .with_claim("scope")
+            .with_pointer("/realm_access/roles");
+
+        assert_eq!(
+            claims.extract(&json!({ "scope": null })).unwrap(),
+            Scope::empty()
+        );
+        assert_eq!(
+            ScopeClaims::new().extract(&json!({})).unwrap(),
+            Scope::empty()
+        );
+    }
+
+    #[test]
+    fn rejects_unexpected_claim_shapes() {
+        let claims = ScopeClaims::new().with_claim("roles");
+
+        assert!(matches!(
+            claims.extract(&json!({ "roles": 5 })),
+            Err(InvalidScopeClaim::UnexpectedType { .. }),
+        ));
+        assert!(matches!(
+            claims.extract(&json!({ "roles": ["admin", 5] })),
+            Err(InvalidScopeClaim::UnexpectedType { .. }),
+        ));
+        assert!(matches!(
+            ScopeClaims::new()
+                .with_claim("scope")
+                .extract(&json!({ "scope": "admin \u{7f}" })),
+            Err(InvalidScopeClaim::InvalidScopeToken { .. }),
+        ));
+    }
+
+    #[test]
+    fn skips_invalid_scope_tokens_in_arrays() {
+        let claims = ScopeClaims::new().with_claim("roles");
+
+        assert_eq!(
+            claims
+                .extract(&json!({ "roles": ["site admin", "admin"] }))
+                .unwrap(),
+            scope!["admin"]
+        );
+
+        let claims: ClaimsWithScopeFrom<Keycloak> = serde_json::from_value(json!({
+            "scope": "profile",
+            "realm_access": { "roles": ["offline access", "admin"] }
+        }))
+        .unwrap();
+        assert_eq!(claims.scope(), &scope!["profile", "admin"]);
+    }
+
+    #[test]
+    fn deserializes_claims_with_scope_from_source() {
+        let claims: ClaimsWithScopeFrom<Keycloak> = serde_json::from_value(json!({
+            "sub": "user",
+            "scope": "profile",
+            "realm_access": { "roles": ["admin"] }
+        }))
+        .unwrap();
+
+        assert_eq!(claims.sub().unwrap().as_str(), "user");
+        assert_eq!(claims.scope(), &scope!["profile", "admin"]);
+        assert_eq!(
+            claims.claims().get("/realm_access/roles/0").unwrap(),
+            "admin"
+        );
+
+        assert!(
+            serde_json::from_value::<ClaimsWithScopeFrom<Keycloak>>(json!({
+                "realm_access": { "roles": "admin user" },
+                "scope": 7
+            }))
+            .is_err()
+        );
+    }
+}
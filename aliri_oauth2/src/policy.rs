@@ -1,9 +1,16 @@
-use std::{iter, slice, vec};
+use std::{fmt, iter, slice, str::FromStr, vec};
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 use crate::Scope;
 
+mod expr;
+mod matching;
+
+pub use expr::InvalidScopePolicy;
+pub use matching::ScopeMatching;
+
 /// Indicates the requester held insufficient scope to be granted access
 /// to a controlled resource
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Error)]
@@ -99,10 +106,61 @@ pub struct InsufficientScope;
 /// # Ok(())
 /// # }
 /// ```
+///
+/// ## Parse a policy from an expression
+///
+/// Policies can be written as boolean expressions over scope tokens, using
+/// `&&` to require all of a set of scopes and `||` to allow alternatives.
+/// `&&` binds more tightly than `||`, and parentheses may be used for
+/// grouping. An empty expression denies all requests, while `()` requires no
+/// scopes and allows any request. Scope tokens in expressions may not contain
+/// `(`, `)`, `&`, or `|`.
+///
+/// Policies are displayed and serialized in the same form. The
+/// [`ScopeMatching`] rules of a policy are not part of the expression.
+///
+/// ```
+/// use aliri_traits::Policy;
+/// use aliri_oauth2::{Scope, ScopePolicy};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let policy: ScopePolicy = "(read || list) && write || admin".parse()?;
+/// assert_eq!(policy.to_string(), "(read && write) || (list && write) || admin");
+///
+/// let request: Scope = "list write".parse()?;
+/// assert!(policy.evaluate(&request).is_ok());
+///
+/// let request: Scope = "read".parse()?;
+/// assert!(policy.evaluate(&request).is_err());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Match namespaced scopes
+///
+/// By default, a required scope token is only satisfied by an identical
+/// held scope token. Wildcard, hierarchical, and URL prefix matching can be
+/// enabled with [`ScopeMatching`].
+///
+/// ```
+/// use aliri_traits::Policy;
+/// use aliri_oauth2::{Scope, ScopeMatching, ScopePolicy};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let policy = "orders:read".parse::<ScopePolicy>()?
+///     .with_matching(ScopeMatching::exact().with_wildcards().with_hierarchy(':'));
+///
+/// assert!(policy.evaluate(&"orders:*".parse()?).is_ok());
+/// assert!(policy.evaluate(&"orders".parse()?).is_ok());
+/// assert!(policy.evaluate(&"orders:write".parse()?).is_err());
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[must_use]
 pub struct ScopePolicy {
     inner: ScopePolicyInner,
+    matching: ScopeMatching,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -128,6 +186,7 @@ impl ScopePolicy {
     pub const fn deny_all() -> Self {
         Self {
             inner: ScopePolicyInner::DenyAll,
+            matching: ScopeMatching::exact(),
         }
     }
 
@@ -136,6 +195,7 @@ impl ScopePolicy {
     pub const fn allow_any() -> Self {
         Self {
             inner: ScopePolicyInner::AllowAny,
+            matching: ScopeMatching::exact(),
         }
     }
 
@@ -144,30 +204,46 @@ impl ScopePolicy {
     pub const fn allow_one(scope: Scope) -> Self {
         Self {
             inner: ScopePolicyInner::AllowOne(scope),
+            matching: ScopeMatching::exact(),
         }
     }
 
+    /// Sets the rules used to decide whether held scope tokens satisfy the
+    /// scope tokens required by this policy
+    #[inline]
+    pub fn with_matching(self, matching: ScopeMatching) -> Self {
+        Self { matching, ..self }
+    }
+
+    /// The rules used to decide whether held scope tokens satisfy the scope
+    /// tokens required by this policy
+    #[inline]
+    pub fn matching(&self) -> &ScopeMatching {
+        &self.matching
+    }
+
     /// Add an alternate allowable scope
     #[inline]
     pub fn or_allow(self, scope: Scope) -> Self {
-        if scope.is_empty() {
-            let mut this = self;
-            this.inner = ScopePolicyInner::AllowAny;
-            this
+        let inner = if scope.is_empty() {
+            ScopePolicyInner::AllowAny
         } else {
             match self.inner {
-                ScopePolicyInner::AllowAny => Self::allow_any(),
-                ScopePolicyInner::DenyAll => Self::allow_one(scope),
-                ScopePolicyInner::AllowOne(existing) => Self {
-                    inner: ScopePolicyInner::AllowMany(vec![existing, scope]),
-                },
+                ScopePolicyInner::AllowAny => ScopePolicyInner::AllowAny,
+                ScopePolicyInner::DenyAll => ScopePolicyInner::AllowOne(scope),
+                ScopePolicyInner::AllowOne(existing) => {
+                    ScopePolicyInner::AllowMany(vec![existing, scope])
+                }
                 ScopePolicyInner::AllowMany(mut scopes) => {
                     scopes.push(scope);
-                    Self {
-                        inner: ScopePolicyInner::AllowMany(scopes),
-                    }
+                    ScopePolicyInner::AllowMany(scopes)
                 }
             }
+        };
+
+        Self {
+            inner,
+            matching: self.matching,
         }
     }
 
@@ -223,7 +299,9 @@ impl aliri_traits::Policy for ScopePolicy {
     type Denial = InsufficientScope;
 
     fn evaluate(&self, held: &Self::Request) -> Result<(), Self::Denial> {
-        let allowed = self.into_iter().any(|req| held.contains_all(req));
+        let allowed = self
+            .into_iter()
+            .any(|req| self.matching.contains_all(held, req));
 
         if allowed {
             Ok(())
@@ -352,6 +430,82 @@ impl From<Scope> for ScopePolicy {
     }
 }
 
+impl FromStr for ScopePolicy {
+    type Err = InvalidScopePolicy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(expr::parse(s)?.into_iter().collect())
+    }
+}
+
+impl fmt::Display for ScopePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let alternatives = match &self.inner {
+            ScopePolicyInner::DenyAll => return Ok(()),
+            ScopePolicyInner::AllowAny => return f.write_str("()"),
+            ScopePolicyInner::AllowOne(_) => 1,
+            ScopePolicyInner::AllowMany(scopes) => scopes.len(),
+        };
+
+        for (i, scope) in self.into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(" || ")?;
+            }
+
+            // An empty alternative allows any scope, as does an empty group
+            if scope.is_empty() {
+                f.write_str("()")?;
+                continue;
+            }
+
+            let grouped = alternatives > 1 && scope.len() > 1;
+            if grouped {
+                f.write_str("(")?;
+            }
+            for (j, token) in scope.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(" && ")?;
+                }
+                fmt::Display::fmt(token, f)?;
+            }
+            if grouped {
+                f.write_str(")")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for ScopePolicy {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let inexpressible = self
+            .into_iter()
+            .flat_map(|scope| scope.iter())
+            .find(|token| !expr::is_expressible(token.as_str()));
+        if let Some(token) = inexpressible {
+            return Err(serde::ser::Error::custom(format_args!(
+                "scope token `{token}` cannot be written in a policy expression"
+            )));
+        }
+
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScopePolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// Construct a policy from a list of scope alternatives.
 ///
 /// For more information about how the alternatives are evaluated, see [`ScopePolicy`].
@@ -0,0 +1,390 @@
+use thiserror::Error;
+
+use crate::{
+    oauth2::{InvalidScopeToken, ScopeToken},
+    Scope,
+};
+
+/// The maximum number of alternatives a policy expression may expand to
+const MAX_ALTERNATIVES: usize = 1024;
+
+/// The maximum depth of nested parentheses in a policy expression
+const MAX_DEPTH: usize = 32;
+
+/// An error parsing a scope policy expression
+#[derive(Debug, Error)]
+pub enum InvalidScopePolicy {
+    /// The expression was not well formed
+    #[error("expected {expected} at position {position}")]
+    Expected {
+        /// A description of what was expected
+        expected: &'static str,
+        /// The byte offset in the expression where the error was found
+        position: usize,
+    },
+
+    /// The expression contained an invalid scope token
+    #[error("invalid scope token at position {position}")]
+    InvalidScopeToken {
+        /// The byte offset in the expression where the scope token starts
+        position: usize,
+        /// The reason the scope token is invalid
+        #[source]
+        source: InvalidScopeToken,
+    },
+
+    /// The expression expands to more than 1024 alternatives or nests
+    /// parentheses more than 32 deep
+    #[error("policy expression is too complex")]
+    TooComplex,
+}
+
+#[derive(Debug)]
+enum Token {
+    Scope(ScopeToken),
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn is_delimiter(c: char) -> bool {
+        c.is_whitespace() || matches!(c, '(' | ')' | '&' | '|')
+    }
+
+    /// Returns the next token and its position, or `None` at the end of the input
+    fn next(&mut self) -> Result<Option<(usize, Token)>, InvalidScopePolicy> {
+        let rest = &self.input[self.position..];
+        let trimmed = rest.trim_start();
+        self.position += rest.len() - trimmed.len();
+        let start = self.position;
+
+        let (token, len) = if trimmed.is_empty() {
+            return Ok(None);
+        } else if trimmed.starts_with('(') {
+            (Token::Open, 1)
+        } else if trimmed.starts_with(')') {
+            (Token::Close, 1)
+        } else if trimmed.starts_with("&&") {
+            (Token::And, 2)
+        } else if trimmed.starts_with("||") {
+            (Token::Or, 2)
+        } else if trimmed.starts_with('&') {
+            return Err(InvalidScopePolicy::Expected {
+                expected: "`&&`",
+                position: start,
+            });
+        } else if trimmed.starts_with('|') {
+            return Err(InvalidScopePolicy::Expected {
+                expected: "`||`",
+                position: start,
+            });
+        } else {
+            let len = trimmed.find(Self::is_delimiter).unwrap_or(trimmed.len());
+            let scope_token =
+                ScopeToken::from_string(trimmed[..len].to_owned()).map_err(|source| {
+                    InvalidScopePolicy::InvalidScopeToken {
+                        position: start,
+                        source,
+                    }
+                })?;
+            (Token::Scope(scope_token), len)
+        };
+
+        self.position += len;
+        Ok(Some((start, token)))
+    }
+}
+
+/// A recursive descent parser that expands the expression into alternatives
+///
+/// ```text
+/// expr = term *( "||" term )
+/// term = atom *( "&&" atom )
+/// atom = scope-token / "(" expr ")" / "(" ")"
+/// ```
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Option<(usize, Token)>>,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Result<Option<&(usize, Token)>, InvalidScopePolicy> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lexer.next()?);
+        }
+        Ok(self.peeked.as_ref().and_then(Option::as_ref))
+    }
+
+    fn next(&mut self) -> Result<Option<(usize, Token)>, InvalidScopePolicy> {
+        match self.peeked.take() {
+            Some(peeked) => Ok(peeked),
+            None => self.lexer.next(),
+        }
+    }
+
+    fn position(&mut self) -> Result<usize, InvalidScopePolicy> {
+        let end = self.lexer.input.len();
+        Ok(self.peek()?.map_or(end, |(position, _)| *position))
+    }
+
+    fn expr(&mut self) -> Result<Vec<Scope>, InvalidScopePolicy> {
+        let mut alternatives = self.term()?;
+        while let Some((_, Token::Or)) = self.peek()? {
+            self.next()?;
+            for scope in self.term()? {
+                push_alternative(&mut alternatives, scope)?;
+            }
+        }
+        Ok(alternatives)
+    }
+
+    fn term(&mut self) -> Result<Vec<Scope>, InvalidScopePolicy> {
+        let mut alternatives = self.atom()?;
+        while let Some((_, Token::And)) = self.peek()? {
+            self.next()?;
+            let right = self.atom()?;
+            let mut product = Vec::new();
+            for left in &alternatives {
+                for right in &right {
+                    let scope = left.clone().into_iter().chain(right.clone()).collect();
+                    push_alternative(&mut product, scope)?;
+                }
+            }
+            alternatives = product;
+        }
+        Ok(alternatives)
+    }
+
+    fn atom(&mut self) -> Result<Vec<Scope>, InvalidScopePolicy> {
+        let position = self.position()?;
+        match self.next()? {
+            Some((_, Token::Scope(scope_token))) => Ok(vec![Scope::single(scope_token)]),
+            Some((_, Token::Open)) => {
+                if let Some((_, Token::Close)) = self.peek()? {
+                    self.next()?;
+                    return Ok(vec![Scope::empty()]);
+                }
+
+                self.depth += 1;
+                if self.depth > MAX_DEPTH {
+                    return Err(InvalidScopePolicy::TooComplex);
+                }
+                let alternatives = self.expr()?;
+                self.depth -= 1;
+
+                let position = self.position()?;
+                match self.next()? {
+                    Some((_, Token::Close)) => Ok(alternatives),
+                    _ => Err(InvalidScopePolicy::Expected {
+                        expected: "`)`",
+                        position,
+                    }),
+                }
+            }
+            _ => Err(InvalidScopePolicy::Expected {
+                expected: "a scope token or `(`",
+                position,
+            }),
+        }
+    }
+}
+
+fn push_alternative(alternatives: &mut Vec<Scope>, scope: Scope) -> Result<(), InvalidScopePolicy> {
+    if !alternatives.contains(&scope) {
+        if alternatives.len() >= MAX_ALTERNATIVES {
+            return Err(InvalidScopePolicy::TooComplex);
+        }
+        alternatives.push(scope);
+    }
+    Ok(())
+}
+
+/// Whether the scope token can be written in a policy expression
+///
+/// Scope tokens containing `&`, `|`, `(`, or `)` would be split apart when
+/// the expression is parsed.
+pub(super) fn is_expressible(token: &str) -> bool {
+    !token.contains(Lexer::is_delimiter)
+}
+
+/// Parses a policy expression into its alternatives
+///
+/// An expression that is empty or contains only whitespace has no
+/// alternatives.
+pub(super) fn parse(input: &str) -> Result<Vec<Scope>, InvalidScopePolicy> {
+    let mut parser = Parser {
+        lexer: Lexer { input, position: 0 },
+        peeked: None,
+        depth: 0,
+    };
+
+    if parser.peek()?.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let alternatives = parser.expr()?;
+
+    let position = parser.position()?;
+    if parser.next()?.is_some() {
+        return Err(InvalidScopePolicy::Expected {
+            expected: "`&&`, `||`, or end of expression",
+            position,
+        });
+    }
+
+    Ok(alternatives)
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri_traits::Policy;
+
+    use super::*;
+    use crate::{policy, scope, ScopePolicy};
+
+    fn parse_policy(s: &str) -> ScopePolicy {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn and_binds_more_tightly_than_or() {
+        assert_eq!(
+            parse_policy("read && write || admin"),
+            policy![scope!["read", "write"], scope!["admin"]],
+        );
+        assert_eq!(
+            parse_policy("admin || read && write"),
+            policy![scope!["admin"], scope!["read", "write"]],
+        );
+    }
+
+    #[test]
+    fn distributes_and_over_grouped_alternatives() {
+        assert_eq!(
+            parse_policy("(a || b) && (c || d)"),
+            policy![
+                scope!["a", "c"],
+                scope!["a", "d"],
+                scope!["b", "c"],
+                scope!["b", "d"],
+            ],
+        );
+        assert_eq!(parse_policy("(a || a) && a"), policy![scope!["a"]]);
+    }
+
+    #[test]
+    fn empty_expressions_deny_and_empty_groups_allow() {
+        assert_eq!(parse_policy(""), ScopePolicy::deny_all());
+        assert_eq!(parse_policy("  "), ScopePolicy::deny_all());
+        assert_eq!(parse_policy("()"), ScopePolicy::allow_any());
+        assert_eq!(parse_policy("admin || ()"), ScopePolicy::allow_any());
+        assert_eq!(parse_policy("admin && ()"), policy![scope!["admin"]]);
+
+        let policy = parse_policy("()");
+        assert!(policy.evaluate(&Scope::empty()).is_ok());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in [
+            "",
+            "()",
+            "admin",
+            "read && write",
+            "admin || (read && write)",
+            "a || b || (c && d && e)",
+        ] {
+            let policy = parse_policy(s);
+            assert_eq!(policy.to_string(), s);
+            assert_eq!(parse_policy(&policy.to_string()), policy);
+        }
+    }
+
+    #[test]
+    fn serializes_as_an_expression() {
+        let policy = parse_policy("admin || (read && write)");
+        let json = serde_json::to_value(&policy).unwrap();
+        assert_eq!(json, "admin || (read && write)");
+
+        let deserialized: ScopePolicy = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, policy);
+
+        assert!(serde_json::from_str::<ScopePolicy>(r#""admin ||""#).is_err());
+    }
+
+    #[test]
+    fn empty_alternatives_round_trip_as_allowing_any_scope() {
+        for policy in [
+            ScopePolicy::allow_one(Scope::empty()),
+            ScopePolicy::allow_one_from_static(""),
+            ScopePolicy::from(Scope::empty()),
+        ] {
+            assert_eq!(policy.to_string(), "()");
+
+            let json = serde_json::to_value(&policy).unwrap();
+            let deserialized: ScopePolicy = serde_json::from_value(json).unwrap();
+            assert!(deserialized.evaluate(&Scope::empty()).is_ok());
+        }
+    }
+
+    #[test]
+    fn does_not_serialize_inexpressible_scope_tokens() {
+        for token in ["read&write", "read|write", "(admin)"] {
+            let policy = ScopePolicy::allow_one(Scope::single(
+                ScopeToken::from_string(token.to_owned()).unwrap(),
+            ));
+            assert!(serde_json::to_value(&policy).is_err(), "{}", token);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for (s, expected_position) in [
+            ("admin ||", 8),
+            ("&& admin", 0),
+            ("(admin", 6),
+            ("admin)", 5),
+            ("read write", 5),
+            ("read & write", 5),
+            ("read | write", 5),
+        ] {
+            match s.parse::<ScopePolicy>() {
+                Err(InvalidScopePolicy::Expected { position, .. }) => {
+                    assert_eq!(position, expected_position, "{s}")
+                }
+                other => panic!("unexpected result for {:?}: {:?}", s, other),
+            }
+        }
+
+        assert!(matches!(
+            r"read && wr\ite".parse::<ScopePolicy>(),
+            Err(InvalidScopePolicy::InvalidScopeToken { position: 8, .. }),
+        ));
+    }
+
+    #[test]
+    fn rejects_overly_complex_expressions() {
+        let nested = format!("{}a{}", "(".repeat(40), ")".repeat(40));
+        assert!(matches!(
+            nested.parse::<ScopePolicy>(),
+            Err(InvalidScopePolicy::TooComplex),
+        ));
+
+        let product = (0..6)
+            .map(|i| format!("(a{i} || b{i} || c{i} || d{i})"))
+            .collect::<Vec<_>>()
+            .join(" && ");
+        assert!(matches!(
+            product.parse::<ScopePolicy>(),
+            Err(InvalidScopePolicy::TooComplex),
+        ));
+    }
+}
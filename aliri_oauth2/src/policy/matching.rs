@@ -0,0 +1,284 @@
+use crate::{oauth2::ScopeTokenRef, Scope};
+
+/// Rules for deciding whether a held scope token satisfies a required one
+///
+/// By default, scope tokens are only satisfied by an identical scope token.
+/// Additional rules can be enabled for identity providers that issue
+/// namespaced scopes.
+///
+/// # Examples
+///
+/// ```
+/// use aliri_oauth2::{scope, ScopeMatching};
+///
+/// let matching = ScopeMatching::exact()
+///     .with_wildcards()
+///     .with_hierarchy(':')
+///     .with_url_prefixes();
+///
+/// // Wildcards
+/// assert!(matching.contains_all(&scope!["orders:*"], &scope!["orders:read"]));
+///
+/// // Hierarchy
+/// assert!(matching.contains_all(&scope!["orders"], &scope!["orders:read"]));
+/// assert!(!matching.contains_all(&scope!["orders:read"], &scope!["orders"]));
+///
+/// // URL prefixes
+/// assert!(matching.contains_all(
+///     &scope!["https://api.example.com/orders"],
+///     &scope!["https://api.example.com/orders.read"],
+/// ));
+/// assert!(!matching.contains_all(
+///     &scope!["https://api.example.com/orders"],
+///     &scope!["https://api.example.com/ordersheet"],
+/// ));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[must_use]
+pub struct ScopeMatching {
+    wildcards: bool,
+    separators: Vec<char>,
+    url_prefixes: bool,
+}
+
+impl ScopeMatching {
+    /// Only identical scope tokens match
+    #[inline]
+    pub const fn exact() -> Self {
+        Self {
+            wildcards: false,
+            separators: Vec::new(),
+            url_prefixes: false,
+        }
+    }
+
+    /// A held scope token ending in `*` satisfies any required scope token
+    /// starting with the text before the `*`
+    ///
+    /// For example, `orders:*` satisfies `orders:read`, and `*` satisfies
+    /// every scope token.
+    pub fn with_wildcards(self) -> Self {
+        Self {
+            wildcards: true,
+            ..self
+        }
+    }
+
+    /// A held scope token satisfies any required scope token nested beneath
+    /// it using the given separator
+    ///
+    /// For example, with `:` as a separator, `orders` satisfies both
+    /// `orders:read` and `orders:read:all`. Multiple separators may be added.
+    pub fn with_hierarchy(mut self, separator: char) -> Self {
+        if !self.separators.contains(&separator) {
+            self.separators.push(separator);
+        }
+        self
+    }
+
+    /// A held scope token that is a URL satisfies any required scope token
+    /// for the same scheme and host that extends its path at a `/` or `.`
+    /// boundary
+    ///
+    /// For example, `https://api.example.com/orders` satisfies
+    /// `https://api.example.com/orders.read` and
+    /// `https://api.example.com/orders/items`, but not
+    /// `https://api.example.com/ordersheet`. The scheme and host must match
+    /// exactly, so `https://api` does not satisfy
+    /// `https://api.example.com/orders`.
+    pub fn with_url_prefixes(self) -> Self {
+        Self {
+            url_prefixes: true,
+            ..self
+        }
+    }
+
+    /// Whether only identical scope tokens match
+    #[inline]
+    #[must_use]
+    pub fn is_exact(&self) -> bool {
+        !self.wildcards && self.separators.is_empty() && !self.url_prefixes
+    }
+
+    /// Checks whether the held scope token satisfies the required scope token
+    #[must_use]
+    pub fn satisfies(&self, held: &ScopeTokenRef, required: &ScopeTokenRef) -> bool {
+        let held = held.as_str();
+        let required = required.as_str();
+
+        if held == required {
+            return true;
+        }
+
+        if self.wildcards {
+            if let Some(prefix) = held.strip_suffix('*') {
+                if required.starts_with(prefix) {
+                    return true;
+                }
+            }
+        }
+
+        if let Some(rest) = required.strip_prefix(held) {
+            if self.separators.iter().any(|&sep| rest.starts_with(sep)) {
+                return true;
+            }
+        }
+
+        self.url_prefixes && url_prefix_satisfies(held, required)
+    }
+
+    /// Checks whether every scope token in `required` is satisfied by some
+    /// scope token in `held`
+    #[must_use]
+    pub fn contains_all(&self, held: &Scope, required: &Scope) -> bool {
+        if self.is_exact() {
+            return held.contains_all(required);
+        }
+
+        required
+            .iter()
+            .all(|required| held.iter().any(|held| self.satisfies(held, required)))
+    }
+}
+
+/// Splits a URL into its scheme, authority, and the remainder, starting with
+/// the path
+///
+/// Returns `None` if the value is not a URL with a non-empty authority.
+fn split_url(value: &str) -> Option<(&str, &str, &str)> {
+    let (scheme, rest) = value.split_once("://")?;
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return None;
+    }
+
+    let authority_end = rest.find(&['/', '?', '#'][..]).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    if authority.is_empty() {
+        return None;
+    }
+
+    Some((scheme, authority, path))
+}
+
+/// Checks whether the held URL extends to the required URL within its path
+fn url_prefix_satisfies(held: &str, required: &str) -> bool {
+    let (held_scheme, held_authority, held_path) = match split_url(held) {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let (scheme, authority, path) = match split_url(required) {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    if held_scheme != scheme || held_authority != authority {
+        return false;
+    }
+
+    match path.strip_prefix(held_path) {
+        Some(rest) => held_path.ends_with(&['/', '.'][..]) || rest.starts_with(&['/', '.'][..]),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri_traits::Policy;
+
+    use super::*;
+    use crate::{policy, scope, ScopePolicy};
+
+    #[test]
+    fn exact_matching_ignores_wildcards_and_hierarchy() {
+        let matching = ScopeMatching::exact();
+        assert!(matching.is_exact());
+        assert!(!matching.contains_all(&scope!["orders:*"], &scope!["orders:read"]));
+        assert!(!matching.contains_all(&scope!["orders"], &scope!["orders:read"]));
+        assert!(matching.contains_all(&scope!["orders:read"], &scope!["orders:read"]));
+    }
+
+    #[test]
+    fn wildcards_match_by_prefix() {
+        let matching = ScopeMatching::exact().with_wildcards();
+        assert!(matching.contains_all(&scope!["*"], &scope!["anything", "else"]));
+        assert!(matching.contains_all(&scope!["orders:*"], &scope!["orders:read:all"]));
+        assert!(!matching.contains_all(&scope!["orders:*"], &scope!["orders"]));
+        assert!(!matching.contains_all(&scope!["orders:read"], &scope!["orders:*"]));
+    }
+
+    #[test]
+    fn hierarchy_matches_only_at_separators() {
+        let matching = ScopeMatching::exact()
+            .with_hierarchy(':')
+            .with_hierarchy('.');
+        assert!(matching.contains_all(&scope!["orders"], &scope!["orders:read"]));
+        assert!(matching.contains_all(&scope!["orders"], &scope!["orders.read"]));
+        assert!(!matching.contains_all(&scope!["orders"], &scope!["ordersheet"]));
+        assert!(!matching.contains_all(&scope!["orders"], &scope!["orders/read"]));
+    }
+
+    #[test]
+    fn url_prefixes_apply_only_to_urls() {
+        let matching = ScopeMatching::exact().with_url_prefixes();
+        assert!(matching.contains_all(
+            &scope!["https://api.example.com/"],
+            &scope!["https://api.example.com/orders.read"],
+        ));
+        assert!(matching.contains_all(
+            &scope!["https://api.example.com/orders"],
+            &scope!["https://api.example.com/orders/items"],
+        ));
+        assert!(!matching.contains_all(&scope!["orders"], &scope!["orders.read"]));
+    }
+
+    #[test]
+    fn url_prefixes_require_the_same_scheme_and_host() {
+        let matching = ScopeMatching::exact().with_url_prefixes();
+        assert!(matching.contains_all(
+            &scope!["https://api.example.com"],
+            &scope!["https://api.example.com/orders"],
+        ));
+        assert!(!matching.contains_all(
+            &scope!["https://api"],
+            &scope!["https://api.example.com/orders.read"],
+        ));
+        assert!(!matching.contains_all(
+            &scope!["https://"],
+            &scope!["https://api.example.com/orders.read"],
+        ));
+        assert!(!matching.contains_all(
+            &scope!["https://api.example.com"],
+            &scope!["https://api.example.com.evil.test/orders"],
+        ));
+        assert!(!matching.contains_all(
+            &scope!["http://api.example.com/"],
+            &scope!["https://api.example.com/orders"],
+        ));
+    }
+
+    #[test]
+    fn every_required_scope_token_must_be_satisfied() {
+        let matching = ScopeMatching::exact().with_wildcards();
+        assert!(matching.contains_all(
+            &scope!["orders:*", "admin"],
+            &scope!["orders:read", "admin"]
+        ));
+        assert!(!matching.contains_all(&scope!["orders:*"], &scope!["orders:read", "admin"]));
+    }
+
+    #[test]
+    fn policies_keep_matching_rules_as_alternatives_are_added() {
+        let policy = ScopePolicy::deny_all()
+            .with_matching(ScopeMatching::exact().with_wildcards())
+            .or_allow(scope!["orders:read"])
+            .or_allow(scope!["admin"]);
+
+        assert!(policy.evaluate(&scope!["orders:*"]).is_ok());
+        assert!(policy.evaluate(&scope!["users:*"]).is_err());
+        assert_ne!(policy, policy!(scope!["orders:read"], scope!["admin"]));
+    }
+}
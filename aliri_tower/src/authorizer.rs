@@ -1,12 +1,17 @@
-use std::{fmt, marker::PhantomData};
+use std::{fmt, marker::PhantomData, sync::Arc};
 
-use aliri::jwt::CoreClaims;
+use aliri::{jwt::CoreClaims, JwtRef};
 use aliri_oauth2::{
     oauth2::{BasicClaimsWithScope, HasScope},
-    Authority, ScopePolicy,
+    Authority, AuthorityError, ScopePolicy,
 };
+use aliri_traits::Policy;
+use http::Request;
 use http_body::Body;
-use tower_http::auth::{AuthorizeRequest, RequireAuthorizationLayer};
+use tower_http::auth::{
+    AsyncAuthorizeRequest, AsyncRequireAuthorizationLayer, AuthorizeRequest,
+    RequireAuthorizationLayer,
+};
 
 use crate::{OnJwtError, OnScopeError, TerseErrorHandler, VerboseErrorHandler};
 
@@ -56,7 +61,7 @@ impl Oauth2Authorizer<BasicClaimsWithScope, ()> {
 impl<OnError> Oauth2Authorizer<BasicClaimsWithScope, OnError> {
     /// Verification will expect the given custom claims object in request extensions
     #[inline]
-    pub fn with_claims<Claims: HasScope>(self) -> Oauth2Authorizer<Claims, OnError> {
+    pub fn with_claims<Claims>(self) -> Oauth2Authorizer<Claims, OnError> {
         Oauth2Authorizer {
             on_error: self.on_error,
             _claim: PhantomData,
@@ -126,7 +131,57 @@ where
     ) -> RequireAuthorizationLayer<
         impl AuthorizeRequest<ReqBody, ResponseBody = OnError::Body> + Clone,
     > {
-        RequireAuthorizationLayer::custom(crate::jwt::VerifyJwt::<Claims, _>::new(
+        RequireAuthorizationLayer::custom(crate::jwt::VerifyJwt::<Claims, _, _>::new(
+            authority,
+            self.on_error.clone(),
+        ))
+    }
+}
+
+impl<Claims, OnError> Oauth2Authorizer<Claims, OnError>
+where
+    OnError: OnJwtError + Clone + Send + Sync + 'static,
+    OnError::Body: Body + Default + Send,
+    Claims: for<'de> serde::Deserialize<'de> + HasScope + CoreClaims + Send + Sync + 'static,
+{
+    /// Authorizer layer that verifies the validity of a JWT with any
+    /// authority
+    ///
+    /// The JWT will be parsed from the request `Authorization` header and
+    /// checked for validity by any type implementing
+    /// [`aliri_traits::Authority`] for bearer tokens, such as a
+    /// [`MultiAuthority`][aliri_oauth2::MultiAuthority]. Verification may
+    /// wait on the authority, such as when refetching a JWKS to find an
+    /// unknown key.
+    ///
+    /// The extracted `Claims` in the JWT payload will be made available
+    /// through [`Request::extensions`][http::Request::extensions].
+    pub fn async_jwt_layer<A, ReqBody>(
+        &self,
+        authority: A,
+    ) -> AsyncRequireAuthorizationLayer<
+        impl AsyncAuthorizeRequest<
+                ReqBody,
+                RequestBody = ReqBody,
+                ResponseBody = OnError::Body,
+                Future = crate::jwt::AuthorizeFuture<ReqBody, OnError::Body>,
+            > + Clone,
+    >
+    where
+        A: for<'a> aliri_traits::Authority<
+                'a,
+                Claims,
+                Token = &'a JwtRef,
+                Policy = &'a ScopePolicy,
+                VerifyError = AuthorityError,
+            > + Clone
+            + Send
+            + Sync
+            + 'static,
+        for<'a> <A as aliri_traits::Authority<'a, Claims>>::VerifyFuture: Send,
+        ReqBody: Send + 'static,
+    {
+        AsyncRequireAuthorizationLayer::new(crate::jwt::VerifyJwt::<Claims, _, _>::new(
             authority,
             self.on_error.clone(),
         ))
@@ -157,6 +212,95 @@ where
     }
 }
 
+impl<Claims, OnError> Oauth2Authorizer<Claims, OnError>
+where
+    OnError: OnScopeError + Clone,
+    OnError::Body: Body + Default,
+    Claims: Send + Sync + 'static,
+{
+    /// Authorizer layer that checks the claims against a policy
+    ///
+    /// The policy may check any of the claims, such as group membership,
+    /// authentication context class, or email domain. A denial is handled
+    /// by [`OnScopeError::on_policy_denied()`].
+    ///
+    /// The `Claims` object is expected to have already been added to
+    /// the [`Request::extensions`][http::Request::extensions].
+    ///
+    /// Policies can be combined with the combinators from [`aliri_traits`],
+    /// such as requiring both a scope and a tenant:
+    ///
+    /// ```
+    /// use aliri::jwt::CoreClaims;
+    /// use aliri_oauth2::{oauth2::{BasicClaimsWithScope, HasScope}, ScopePolicy};
+    /// use aliri_tower::Oauth2Authorizer;
+    /// use aliri_traits::{AllOf, Mapped, Policy};
+    ///
+    /// struct AcmeTenant;
+    ///
+    /// impl Policy for AcmeTenant {
+    ///     type Request = BasicClaimsWithScope;
+    ///     type Denial = &'static str;
+    ///
+    ///     fn evaluate(&self, claims: &BasicClaimsWithScope) -> Result<(), Self::Denial> {
+    ///         match claims.sub() {
+    ///             Some(sub) if sub.as_str().starts_with("acme|") => Ok(()),
+    ///             _ => Err("token does not belong to the acme tenant"),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let policy = AllOf::new(
+    ///     Mapped::new(
+    ///         ScopePolicy::allow_one_from_static("orders:read"),
+    ///         |claims: &BasicClaimsWithScope| claims.scope(),
+    ///     ),
+    ///     AcmeTenant,
+    /// );
+    ///
+    /// let authorizer = Oauth2Authorizer::new().with_verbose_error_handler::<axum::body::Body>();
+    /// let layer = authorizer.policy_layer::<_, axum::body::Body>(policy);
+    /// # let _: tower_http::auth::RequireAuthorizationLayer<_> = layer;
+    /// ```
+    pub fn policy_layer<P, ReqBody>(
+        &self,
+        policy: P,
+    ) -> RequireAuthorizationLayer<
+        impl AuthorizeRequest<ReqBody, ResponseBody = OnError::Body> + Clone,
+    >
+    where
+        P: Policy<Request = Claims>,
+    {
+        let policy = Arc::new(policy);
+        self.policy_layer_fn(move |_: &Request<ReqBody>| Arc::clone(&policy))
+    }
+
+    /// Authorizer layer that checks the claims against a policy built
+    /// for each request
+    ///
+    /// This allows the policy to depend on the request, such as requiring
+    /// that a tenant claim match a tenant in the request path. A denial is
+    /// handled by [`OnScopeError::on_policy_denied()`].
+    ///
+    /// The `Claims` object is expected to have already been added to
+    /// the [`Request::extensions`][http::Request::extensions].
+    pub fn policy_layer_fn<F, P, ReqBody>(
+        &self,
+        make_policy: F,
+    ) -> RequireAuthorizationLayer<
+        impl AuthorizeRequest<ReqBody, ResponseBody = OnError::Body> + Clone,
+    >
+    where
+        F: Fn(&Request<ReqBody>) -> P + Clone,
+        P: Policy<Request = Claims>,
+    {
+        RequireAuthorizationLayer::custom(crate::policy::VerifyPolicy::<Claims, _, _>::new(
+            make_policy,
+            self.on_error.clone(),
+        ))
+    }
+}
+
 impl Default for Oauth2Authorizer<BasicClaimsWithScope, ()> {
     fn default() -> Self {
         Self::new()
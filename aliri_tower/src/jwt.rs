@@ -1,22 +1,30 @@
-use std::{fmt, marker::PhantomData};
+use std::{fmt, future::Future, marker::PhantomData, pin::Pin};
 
-use aliri::{error::JwtVerifyError, jwt::CoreClaims, Jwt};
+use aliri::{error::JwtVerifyError, jwt::CoreClaims, Jwt, JwtRef};
 use aliri_oauth2::{oauth2::HasScope, Authority, AuthorityError, ScopePolicy};
 use http::{Request, Response};
 use http_body::Body;
-use tower_http::auth::AuthorizeRequest;
+use tower_http::auth::{AsyncAuthorizeRequest, AuthorizeRequest};
 
-use crate::{util::unauthorized, TerseErrorHandler, VerboseErrorHandler};
+use crate::{
+    util::{service_unavailable, unauthorized},
+    TerseErrorHandler, VerboseErrorHandler,
+};
 
-pub(crate) struct VerifyJwt<Claims, OnError> {
-    authority: Authority,
+/// Future returned while authorizing a request with a JWT
+pub type AuthorizeFuture<ReqBody, ResBody> =
+    Pin<Box<dyn Future<Output = Result<Request<ReqBody>, Response<ResBody>>> + Send>>;
+
+pub(crate) struct VerifyJwt<Claims, OnError, A> {
+    authority: A,
     on_error: OnError,
     _claim: PhantomData<fn() -> Claims>,
 }
 
-impl<Claims, OnError> Clone for VerifyJwt<Claims, OnError>
+impl<Claims, OnError, A> Clone for VerifyJwt<Claims, OnError, A>
 where
     OnError: Clone,
+    A: Clone,
 {
     #[inline]
     fn clone(&self) -> Self {
@@ -28,9 +36,10 @@ where
     }
 }
 
-impl<Claims, OnError> fmt::Debug for VerifyJwt<Claims, OnError>
+impl<Claims, OnError, A> fmt::Debug for VerifyJwt<Claims, OnError, A>
 where
     OnError: fmt::Debug,
+    A: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("VerifyJwt")
@@ -40,9 +49,9 @@ where
     }
 }
 
-impl<Claims, OnError> VerifyJwt<Claims, OnError> {
+impl<Claims, OnError, A> VerifyJwt<Claims, OnError, A> {
     #[inline]
-    pub(crate) fn new(authority: Authority, on_error: OnError) -> Self {
+    pub(crate) fn new(authority: A, on_error: OnError) -> Self {
         Self {
             authority,
             on_error,
@@ -51,23 +60,25 @@ impl<Claims, OnError> VerifyJwt<Claims, OnError> {
     }
 }
 
-impl<Claims, OnError> VerifyJwt<Claims, OnError>
+fn handle_jwt_invalid<OnError>(on_error: &OnError, error: AuthorityError) -> Response<OnError::Body>
 where
     OnError: OnJwtError,
-    OnError::Body: Body + Default,
+    OnError::Body: Default,
 {
-    fn handle_jwt_invalid(&self, error: AuthorityError) -> Response<OnError::Body> {
-        match error {
-            AuthorityError::UnknownKeyId => self.on_error.on_no_matching_jwk(),
-            AuthorityError::JwtVerifyError(err) => self.on_error.on_jwt_invalid(err),
-            AuthorityError::PolicyDenial(_) => {
-                unreachable!("called only when policy is set to allow all")
-            }
+    match error {
+        AuthorityError::UnknownKeyId | AuthorityError::UnknownIssuer => {
+            on_error.on_no_matching_jwk()
         }
+        AuthorityError::JwtVerifyError(err) => on_error.on_jwt_invalid(err),
+        AuthorityError::PolicyDenial(_) => {
+            unreachable!("called only when policy is set to allow all")
+        }
+        err @ AuthorityError::Unavailable(_) => on_error.on_authority_unavailable(err),
+        err => on_error.on_token_rejected(err),
     }
 }
 
-impl<Claims, OnError, ReqBody> AuthorizeRequest<ReqBody> for VerifyJwt<Claims, OnError>
+impl<Claims, OnError, ReqBody> AuthorizeRequest<ReqBody> for VerifyJwt<Claims, OnError, Authority>
 where
     OnError: OnJwtError,
     OnError::Body: Body + Default,
@@ -101,7 +112,7 @@ where
         let claims = self
             .authority
             .verify_token::<Claims>(jwt, &ScopePolicy::allow_any())
-            .map_err(|err| self.handle_jwt_invalid(err))?;
+            .map_err(|err| handle_jwt_invalid(&self.on_error, err))?;
 
         let _ = request.extensions_mut().insert(claims);
 
@@ -111,6 +122,64 @@ where
     }
 }
 
+impl<Claims, OnError, A, ReqBody> AsyncAuthorizeRequest<ReqBody> for VerifyJwt<Claims, OnError, A>
+where
+    OnError: OnJwtError + Clone + Send + Sync + 'static,
+    OnError::Body: Body + Default + Send,
+    Claims: for<'de> serde::Deserialize<'de> + HasScope + CoreClaims + Send + Sync + 'static,
+    A: for<'a> aliri_traits::Authority<
+            'a,
+            Claims,
+            Token = &'a JwtRef,
+            Policy = &'a ScopePolicy,
+            VerifyError = AuthorityError,
+        > + Clone
+        + Send
+        + Sync
+        + 'static,
+    for<'a> <A as aliri_traits::Authority<'a, Claims>>::VerifyFuture: Send,
+    ReqBody: Send + 'static,
+{
+    type RequestBody = ReqBody;
+    type ResponseBody = OnError::Body;
+    type Future = AuthorizeFuture<ReqBody, OnError::Body>;
+
+    fn authorize(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let authority = self.authority.clone();
+        let on_error = self.on_error.clone();
+
+        Box::pin(async move {
+            let jwt = if let Some(jwt) = request.extensions().get::<Jwt>() {
+                tracing::trace!("found cached jwt");
+                jwt.clone()
+            } else {
+                tracing::trace!("extracting jwt from headers");
+                let jwt = request
+                    .headers()
+                    .get(http::header::AUTHORIZATION)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(extract_jwt)
+                    .ok_or_else(|| on_error.on_missing_or_malformed())?;
+
+                let _ = request.extensions_mut().insert(jwt.clone());
+                jwt
+            };
+
+            let policy = ScopePolicy::allow_any();
+            let claims: Claims = authority
+                .verify(&jwt, &policy)
+                .await
+                .map_err(|err| handle_jwt_invalid(&on_error, err))?;
+
+            let _ = request.extensions_mut().insert(claims);
+
+            tracing::trace!("jwt was valid");
+
+            Ok(request)
+        })
+    }
+}
+
 /// Handler for responding to failures while verifying a JWT
 pub trait OnJwtError {
     /// The body type returned on an error
@@ -124,6 +193,29 @@ pub trait OnJwtError {
 
     /// Response when the JWT was rejected by the authority as invalid
     fn on_jwt_invalid(&self, error: JwtVerifyError) -> Response<Self::Body>;
+
+    /// Response when the token was rejected by the authority for another
+    /// reason, such as an introspection endpoint reporting that the token is
+    /// not active
+    ///
+    /// By default, this responds as though the token was missing or malformed.
+    fn on_token_rejected(&self, error: AuthorityError) -> Response<Self::Body> {
+        let _ = error;
+        self.on_missing_or_malformed()
+    }
+
+    /// Response when the authority could not determine whether the token is
+    /// valid, such as when an introspection endpoint could not be reached
+    ///
+    /// By default, this responds with `503 Service Unavailable`, so that
+    /// clients do not discard tokens that may still be valid.
+    fn on_authority_unavailable(&self, error: AuthorityError) -> Response<Self::Body>
+    where
+        Self::Body: Default,
+    {
+        let _ = error;
+        service_unavailable()
+    }
 }
 
 macro_rules! delegate_impls {
@@ -146,6 +238,17 @@ macro_rules! delegate_impls {
                 fn on_jwt_invalid(&self, error: JwtVerifyError) -> Response<Self::Body> {
                     T::on_jwt_invalid(self, error)
                 }
+
+                fn on_token_rejected(&self, error: AuthorityError) -> Response<Self::Body> {
+                    T::on_token_rejected(self, error)
+                }
+
+                fn on_authority_unavailable(&self, error: AuthorityError) -> Response<Self::Body>
+                where
+                    Self::Body: Default,
+                {
+                    T::on_authority_unavailable(self, error)
+                }
             }
         )*
     }
@@ -178,6 +281,11 @@ where
     fn on_jwt_invalid(&self, _: JwtVerifyError) -> Response<Self::Body> {
         unauthorized("")
     }
+
+    #[inline]
+    fn on_token_rejected(&self, _: AuthorityError) -> Response<Self::Body> {
+        unauthorized("")
+    }
 }
 
 impl<ResBody> OnJwtError for VerboseErrorHandler<ResBody>
@@ -198,20 +306,29 @@ where
 
     #[inline]
     fn on_jwt_invalid(&self, error: JwtVerifyError) -> Response<Self::Body> {
-        use std::fmt::Write;
-
-        let mut description = String::new();
-        let mut err: &dyn std::error::Error = &error;
-        write!(&mut description, "{err}").unwrap();
-        while let Some(next) = err.source() {
-            write!(&mut description, ": {next}").unwrap();
-            err = next;
-        }
+        unauthorized(&describe(&error))
+    }
 
-        unauthorized(&description)
+    #[inline]
+    fn on_token_rejected(&self, error: AuthorityError) -> Response<Self::Body> {
+        unauthorized(&describe(&error))
     }
 }
 
+fn describe(error: &dyn std::error::Error) -> String {
+    use std::fmt::Write;
+
+    let mut description = String::new();
+    let mut err = error;
+    write!(&mut description, "{err}").unwrap();
+    while let Some(next) = err.source() {
+        write!(&mut description, ": {next}").unwrap();
+        err = next;
+    }
+
+    description
+}
+
 fn extract_jwt(auth: &str) -> Option<Jwt> {
     if auth.len() <= 7 || !auth[..7].eq_ignore_ascii_case("bearer ") {
         return None;
@@ -219,3 +336,23 @@ fn extract_jwt(auth: &str) -> Option<Jwt> {
 
     Some(Jwt::from(auth[7..].trim()))
 }
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::*;
+
+    #[test]
+    fn unavailable_authority_is_distinguished_from_rejected_token() {
+        let on_error = VerboseErrorHandler::<()>::new();
+
+        let unavailable = AuthorityError::Unavailable("connection refused".into());
+        let resp = handle_jwt_invalid(&on_error, unavailable);
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!resp.headers().contains_key(http::header::WWW_AUTHENTICATE));
+
+        let resp = handle_jwt_invalid(&on_error, AuthorityError::Inactive);
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+}
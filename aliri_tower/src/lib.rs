@@ -103,9 +103,14 @@ use std::{fmt, marker::PhantomData};
 mod authorizer;
 mod jwt;
 mod oauth2;
+mod policy;
 pub mod util;
 
-pub use crate::{authorizer::Oauth2Authorizer, jwt::OnJwtError, oauth2::OnScopeError};
+pub use crate::{
+    authorizer::Oauth2Authorizer,
+    jwt::{AuthorizeFuture, OnJwtError},
+    oauth2::OnScopeError,
+};
 
 /// Terse responders for authentication and authorization failures
 ///
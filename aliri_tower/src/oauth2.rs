@@ -92,6 +92,14 @@ pub trait OnScopeError {
 
     /// Response when access is rejected due to insufficient permissions
     fn on_scope_policy_failure(&self, held: &Scope, policy: &ScopePolicy) -> Response<Self::Body>;
+
+    /// Response when access is rejected by a policy over other claims
+    ///
+    /// By default, this responds as though the scope claim was missing.
+    fn on_policy_denied(&self, denial: &dyn fmt::Display) -> Response<Self::Body> {
+        let _ = denial;
+        self.on_missing_scope_claim()
+    }
 }
 
 macro_rules! delegate_impls {
@@ -110,6 +118,10 @@ macro_rules! delegate_impls {
                 fn on_scope_policy_failure(&self, held: &Scope, policy: &ScopePolicy) -> Response<Self::Body> {
                     T::on_scope_policy_failure(self, held, policy)
                 }
+
+                fn on_policy_denied(&self, denial: &dyn fmt::Display) -> Response<Self::Body> {
+                    T::on_policy_denied(self, denial)
+                }
             }
         )*
     }
@@ -137,6 +149,11 @@ where
     fn on_scope_policy_failure(&self, _: &Scope, policy: &ScopePolicy) -> Response<Self::Body> {
         forbidden("", Some(policy))
     }
+
+    #[inline]
+    fn on_policy_denied(&self, _: &dyn fmt::Display) -> Response<Self::Body> {
+        forbidden("", None)
+    }
 }
 
 impl<ResBody> OnScopeError for VerboseErrorHandler<ResBody>
@@ -160,4 +177,12 @@ where
             Some(policy),
         )
     }
+
+    #[inline]
+    fn on_policy_denied(&self, denial: &dyn fmt::Display) -> Response<Self::Body> {
+        forbidden(
+            &format!("authorization token is not permitted to access this endpoint: {denial}"),
+            None,
+        )
+    }
 }
@@ -0,0 +1,151 @@
+use std::{fmt, marker::PhantomData};
+
+use aliri_traits::Policy;
+use http::{Request, Response};
+use http_body::Body;
+use tower_http::auth::AuthorizeRequest;
+
+use crate::OnScopeError;
+
+pub(crate) struct VerifyPolicy<Claims, MakePolicy, OnError> {
+    make_policy: MakePolicy,
+    on_error: OnError,
+    _claim: PhantomData<fn() -> Claims>,
+}
+
+impl<Claims, MakePolicy, OnError> Clone for VerifyPolicy<Claims, MakePolicy, OnError>
+where
+    MakePolicy: Clone,
+    OnError: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            make_policy: self.make_policy.clone(),
+            on_error: self.on_error.clone(),
+            _claim: PhantomData,
+        }
+    }
+}
+
+impl<Claims, MakePolicy, OnError> fmt::Debug for VerifyPolicy<Claims, MakePolicy, OnError>
+where
+    OnError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VerifyPolicy")
+            .field("on_error", &self.on_error)
+            .finish()
+    }
+}
+
+impl<Claims, MakePolicy, OnError> VerifyPolicy<Claims, MakePolicy, OnError> {
+    /// Constructs a new policy verifier that builds the policy for each request
+    pub(crate) fn new(make_policy: MakePolicy, on_error: OnError) -> Self {
+        Self {
+            make_policy,
+            on_error,
+            _claim: PhantomData,
+        }
+    }
+}
+
+impl<Claims, MakePolicy, P, OnError, ReqBody> AuthorizeRequest<ReqBody>
+    for VerifyPolicy<Claims, MakePolicy, OnError>
+where
+    MakePolicy: Fn(&Request<ReqBody>) -> P,
+    P: Policy<Request = Claims>,
+    OnError: OnScopeError,
+    OnError::Body: Body + Default,
+    Claims: Send + Sync + 'static,
+{
+    type ResponseBody = OnError::Body;
+
+    fn authorize(
+        &mut self,
+        request: &mut Request<ReqBody>,
+    ) -> Result<(), Response<Self::ResponseBody>> {
+        let policy = (self.make_policy)(request);
+
+        let claims = request
+            .extensions()
+            .get::<Claims>()
+            .ok_or_else(|| self.on_error.on_missing_scope_claim())?;
+
+        policy.evaluate(claims).map_err(|denial| {
+            tracing::trace!(%denial, "request denied by claims policy");
+            self.on_error.on_policy_denied(&denial)
+        })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, StatusCode};
+
+    use super::*;
+    use crate::VerboseErrorHandler;
+
+    struct Claims {
+        tenant: &'static str,
+    }
+
+    struct SameTenant(String);
+
+    impl Policy for SameTenant {
+        type Request = Claims;
+        type Denial = &'static str;
+
+        fn evaluate(&self, claims: &Claims) -> Result<(), Self::Denial> {
+            if claims.tenant == self.0 {
+                Ok(())
+            } else {
+                Err("wrong tenant")
+            }
+        }
+    }
+
+    fn verifier() -> impl AuthorizeRequest<(), ResponseBody = String> {
+        VerifyPolicy::<Claims, _, _>::new(
+            |request: &Request<()>| {
+                SameTenant(request.uri().path().trim_start_matches('/').to_owned())
+            },
+            VerboseErrorHandler::<String>::new(),
+        )
+    }
+
+    fn request(path: &str, claims: Option<Claims>) -> Request<()> {
+        let mut request = Request::builder().uri(path).body(()).unwrap();
+        if let Some(claims) = claims {
+            request.extensions_mut().insert(claims);
+        }
+        request
+    }
+
+    #[test]
+    fn allows_claims_satisfying_request_policy() {
+        let mut request = request("/acme", Some(Claims { tenant: "acme" }));
+        assert!(verifier().authorize(&mut request).is_ok());
+    }
+
+    #[test]
+    fn forbids_claims_denied_by_request_policy() {
+        let mut request = request("/globex", Some(Claims { tenant: "acme" }));
+        let response = verifier().authorize(&mut request).unwrap_err();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer error="insufficient_scope" error_description="authorization token is not permitted to access this endpoint: wrong tenant""#
+        );
+    }
+
+    #[test]
+    fn forbids_requests_without_claims() {
+        let mut request = request("/acme", None);
+        let response = verifier().authorize(&mut request).unwrap_err();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
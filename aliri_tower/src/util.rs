@@ -25,6 +25,17 @@ pub fn unauthorized<Body: Default>(description: &str) -> Response<Body> {
     resp
 }
 
+/// Build a `503 Service Unavailable` response
+///
+/// This is used when the validity of a token could not be determined, such
+/// as when the authority could not reach a remote service. No
+/// `www-authenticate` header is included, as the token was not rejected.
+pub fn service_unavailable<Body: Default>() -> Response<Body> {
+    let mut resp = Response::new(Body::default());
+    *resp.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    resp
+}
+
 /// Build a `403 Forbidden` response with the appropriate `www-authenticate` header(s)
 ///
 /// The description provided will be automatically escaped to make sure it
@@ -19,4 +19,4 @@ mod authority;
 mod policy;
 
 pub use authority::Authority;
-pub use policy::Policy;
+pub use policy::{AllOf, AllOfDenial, AnyOf, AnyOfDenial, Mapped, Not, NotDenial, Policy};
@@ -1,5 +1,9 @@
 use std::fmt;
 
+mod combinators;
+
+pub use combinators::{AllOf, AllOfDenial, AnyOf, AnyOfDenial, Mapped, Not, NotDenial};
+
 /// A policy against which a request will be evaluated
 pub trait Policy {
     /// The request type evaluated by this policy
@@ -0,0 +1,364 @@
+use std::{fmt, marker::PhantomData};
+
+use super::Policy;
+
+/// A policy that is satisfied only when both policies are satisfied
+///
+/// The first policy is evaluated before the second, and the denial reports
+/// which of the two denied the request. Nest `AllOf` to require more than
+/// two policies.
+///
+/// # Example
+///
+/// ```
+/// use aliri_traits::{AllOf, AllOfDenial, Policy};
+///
+/// struct AtLeast(u32);
+///
+/// impl Policy for AtLeast {
+///     type Request = u32;
+///     type Denial = &'static str;
+///
+///     fn evaluate(&self, request: &u32) -> Result<(), Self::Denial> {
+///         if *request >= self.0 { Ok(()) } else { Err("too small") }
+///     }
+/// }
+///
+/// struct Even;
+///
+/// impl Policy for Even {
+///     type Request = u32;
+///     type Denial = &'static str;
+///
+///     fn evaluate(&self, request: &u32) -> Result<(), Self::Denial> {
+///         if request % 2 == 0 { Ok(()) } else { Err("odd") }
+///     }
+/// }
+///
+/// let policy = AllOf::new(AtLeast(10), Even);
+///
+/// assert!(policy.evaluate(&12).is_ok());
+/// assert!(matches!(policy.evaluate(&4), Err(AllOfDenial::First("too small"))));
+/// assert!(matches!(policy.evaluate(&13), Err(AllOfDenial::Second("odd"))));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct AllOf<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> AllOf<A, B> {
+    /// Requires that both policies be satisfied
+    pub const fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// Takes ownership of the underlying policies
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A, B> Policy for AllOf<A, B>
+where
+    A: Policy,
+    B: Policy<Request = A::Request>,
+{
+    type Request = A::Request;
+    type Denial = AllOfDenial<A::Denial, B::Denial>;
+
+    fn evaluate(&self, request: &Self::Request) -> Result<(), Self::Denial> {
+        self.first.evaluate(request).map_err(AllOfDenial::First)?;
+        self.second.evaluate(request).map_err(AllOfDenial::Second)
+    }
+}
+
+/// The denial from an [`AllOf`] policy, reporting which policy denied the request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllOfDenial<A, B> {
+    /// The first policy denied the request
+    First(A),
+    /// The second policy denied the request
+    Second(B),
+}
+
+impl<A, B> fmt::Display for AllOfDenial<A, B>
+where
+    A: fmt::Display,
+    B: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::First(denial) => denial.fmt(f),
+            Self::Second(denial) => denial.fmt(f),
+        }
+    }
+}
+
+/// A policy that is satisfied when either policy is satisfied
+///
+/// The second policy is only evaluated if the first denies the request. If
+/// both deny the request, the denial holds both reasons. Nest `AnyOf` to
+/// accept more than two policies.
+///
+/// # Example
+///
+/// ```
+/// use aliri_traits::{AnyOf, Policy};
+///
+/// struct Is(&'static str);
+///
+/// impl Policy for Is {
+///     type Request = String;
+///     type Denial = String;
+///
+///     fn evaluate(&self, request: &String) -> Result<(), Self::Denial> {
+///         if request == self.0 { Ok(()) } else { Err(format!("not {}", self.0)) }
+///     }
+/// }
+///
+/// let policy = AnyOf::new(Is("admin"), Is("owner"));
+///
+/// assert!(policy.evaluate(&"owner".to_string()).is_ok());
+/// assert_eq!(
+///     policy.evaluate(&"guest".to_string()).unwrap_err().to_string(),
+///     "not admin; not owner",
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct AnyOf<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> AnyOf<A, B> {
+    /// Requires that either policy be satisfied
+    pub const fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// Takes ownership of the underlying policies
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A, B> Policy for AnyOf<A, B>
+where
+    A: Policy,
+    B: Policy<Request = A::Request>,
+{
+    type Request = A::Request;
+    type Denial = AnyOfDenial<A::Denial, B::Denial>;
+
+    fn evaluate(&self, request: &Self::Request) -> Result<(), Self::Denial> {
+        let first = match self.first.evaluate(request) {
+            Ok(()) => return Ok(()),
+            Err(denial) => denial,
+        };
+
+        self.second
+            .evaluate(request)
+            .map_err(|second| AnyOfDenial { first, second })
+    }
+}
+
+/// The denial from an [`AnyOf`] policy, holding the denials from both policies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnyOfDenial<A, B> {
+    /// The denial from the first policy
+    pub first: A,
+    /// The denial from the second policy
+    pub second: B,
+}
+
+impl<A, B> fmt::Display for AnyOfDenial<A, B>
+where
+    A: fmt::Display,
+    B: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}; {}", self.first, self.second)
+    }
+}
+
+/// A policy that is satisfied only when the underlying policy denies the request
+///
+/// # Example
+///
+/// ```
+/// use aliri_traits::{Not, Policy};
+///
+/// struct Suspended;
+///
+/// impl Policy for Suspended {
+///     type Request = bool;
+///     type Denial = &'static str;
+///
+///     fn evaluate(&self, suspended: &bool) -> Result<(), Self::Denial> {
+///         if *suspended { Ok(()) } else { Err("account is not suspended") }
+///     }
+/// }
+///
+/// let policy = Not::new(Suspended);
+///
+/// assert!(policy.evaluate(&false).is_ok());
+/// assert!(policy.evaluate(&true).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct Not<P> {
+    policy: P,
+}
+
+impl<P> Not<P> {
+    /// Requires that the policy deny the request
+    pub const fn new(policy: P) -> Self {
+        Self { policy }
+    }
+
+    /// Takes ownership of the underlying policy
+    pub fn into_inner(self) -> P {
+        self.policy
+    }
+}
+
+impl<P> Policy for Not<P>
+where
+    P: Policy,
+{
+    type Request = P::Request;
+    type Denial = NotDenial;
+
+    fn evaluate(&self, request: &Self::Request) -> Result<(), Self::Denial> {
+        match self.policy.evaluate(request) {
+            Ok(()) => Err(NotDenial),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// The denial from a [`Not`] policy, indicating that the underlying policy
+/// was satisfied
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotDenial;
+
+impl fmt::Display for NotDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("request satisfied a policy that it is required not to satisfy")
+    }
+}
+
+/// A policy that evaluates the underlying policy over a projection of the request
+///
+/// This allows policies over different request types to be combined, such as
+/// a policy over a token's scope with a policy over its other claims.
+///
+/// # Example
+///
+/// ```
+/// use aliri_traits::{AllOf, Mapped, Policy};
+///
+/// struct Claims {
+///     tenant: String,
+///     level: u32,
+/// }
+///
+/// struct Tenant(&'static str);
+///
+/// impl Policy for Tenant {
+///     type Request = String;
+///     type Denial = &'static str;
+///
+///     fn evaluate(&self, tenant: &String) -> Result<(), Self::Denial> {
+///         if tenant == self.0 { Ok(()) } else { Err("wrong tenant") }
+///     }
+/// }
+///
+/// struct Level(u32);
+///
+/// impl Policy for Level {
+///     type Request = u32;
+///     type Denial = &'static str;
+///
+///     fn evaluate(&self, level: &u32) -> Result<(), Self::Denial> {
+///         if *level >= self.0 { Ok(()) } else { Err("insufficient level") }
+///     }
+/// }
+///
+/// let policy = AllOf::new(
+///     Mapped::new(Tenant("acme"), |c: &Claims| &c.tenant),
+///     Mapped::new(Level(2), |c: &Claims| &c.level),
+/// );
+///
+/// let claims = Claims { tenant: "acme".into(), level: 1 };
+/// assert_eq!(policy.evaluate(&claims).unwrap_err().to_string(), "insufficient level");
+/// ```
+#[must_use]
+pub struct Mapped<P, F, R> {
+    policy: P,
+    project: F,
+    _request: PhantomData<fn(&R)>,
+}
+
+impl<P, F, R> Mapped<P, F, R>
+where
+    P: Policy,
+    F: Fn(&R) -> &P::Request,
+{
+    /// Evaluates the policy over the part of the request selected by `project`
+    pub fn new(policy: P, project: F) -> Self {
+        Self {
+            policy,
+            project,
+            _request: PhantomData,
+        }
+    }
+}
+
+impl<P, F, R> Mapped<P, F, R> {
+    /// Takes ownership of the underlying policy
+    pub fn into_inner(self) -> P {
+        self.policy
+    }
+}
+
+impl<P, F, R> Clone for Mapped<P, F, R>
+where
+    P: Clone,
+    F: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            policy: self.policy.clone(),
+            project: self.project.clone(),
+            _request: PhantomData,
+        }
+    }
+}
+
+impl<P, F, R> fmt::Debug for Mapped<P, F, R>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mapped")
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<P, F, R> Policy for Mapped<P, F, R>
+where
+    P: Policy,
+    F: Fn(&R) -> &P::Request,
+{
+    type Request = R;
+    type Denial = P::Denial;
+
+    fn evaluate(&self, request: &Self::Request) -> Result<(), Self::Denial> {
+        self.policy.evaluate((self.project)(request))
+    }
+}
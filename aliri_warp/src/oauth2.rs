@@ -1,7 +1,7 @@
 //! Warp filters for validating JWTs against OAuth2 authorities and scope
 
-use aliri::{jwt, Jwt};
-use aliri_oauth2::{oauth2, Authority, AuthorityError, ScopePolicy};
+use aliri::{jwt, Jwt, JwtRef};
+use aliri_oauth2::{oauth2, AuthorityError, ScopePolicy};
 use serde::Deserialize;
 use thiserror::Error;
 use warp::Filter;
@@ -14,22 +14,37 @@ pub struct AuthFailed(#[from] pub AuthorityError);
 impl warp::reject::Reject for AuthFailed {}
 
 /// Require the JWT to be valid according to the JWKS authority and scope
-pub fn require_scope<C, F, P>(
+///
+/// The authority is usually an [`aliri_oauth2::Authority`], but may be any
+/// authority implementing [`aliri_traits::Authority`] for bearer tokens.
+pub fn require_scope<C, F, P, A>(
     jwt: F,
-    authority: Authority,
+    authority: A,
     policy: P,
 ) -> impl Filter<Extract = (C,), Error = warp::Rejection> + Clone
 where
-    C: for<'de> Deserialize<'de> + jwt::CoreClaims + oauth2::HasScope,
+    C: for<'de> Deserialize<'de> + jwt::CoreClaims + oauth2::HasScope + Send,
     F: Filter<Extract = (Jwt,), Error = warp::Rejection> + Clone,
     P: AsRef<ScopePolicy> + Clone + Send + Sync + 'static,
+    A: for<'a> aliri_traits::Authority<
+            'a,
+            C,
+            Token = &'a JwtRef,
+            Policy = &'a ScopePolicy,
+            VerifyError = AuthorityError,
+        > + Clone
+        + Send
+        + Sync
+        + 'static,
+    for<'a> <A as aliri_traits::Authority<'a, C>>::VerifyFuture: Send,
 {
     jwt.and_then(move |jwt: Jwt| {
         let authority = authority.clone();
         let policy = policy.clone();
         async move {
             let result = authority
-                .verify_token(&jwt, policy.as_ref())
+                .verify(&jwt, policy.as_ref())
+                .await
                 .map_err(AuthFailed);
             result.map_err(warp::reject::custom)
         }